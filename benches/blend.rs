@@ -0,0 +1,56 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use interpol::{smooth, smooth_in, ColorType, Image, Interpolator};
+
+/// An RGB image filled with a repeating pattern varying with `seed`
+fn image(width: u32, height: u32, seed: usize) -> Image {
+    let data = (0..width as usize * height as usize * 3)
+        .map(|i| (i * seed % 251) as u8)
+        .collect::<Vec<_>>();
+    Image::new_from_parts(data, ColorType::Rgb, width, height).unwrap()
+}
+
+fn smooth_channel(c: &mut Criterion) {
+    c.bench_function("smooth u8", |b| {
+        b.iter(|| smooth(black_box(0.3), black_box(17u8), black_box(230u8)))
+    });
+    c.bench_function("smooth u8 in f64", |b| {
+        b.iter(|| smooth_in::<f64, _>(black_box(0.3), black_box(17u8), black_box(230u8)))
+    });
+    c.bench_function("smooth u16", |b| {
+        b.iter(|| smooth(black_box(0.3), black_box(4369u16), black_box(59110u16)))
+    });
+}
+
+fn blend_frame(c: &mut Criterion) {
+    let mut group = c.benchmark_group("blend frame");
+    group.sample_size(20);
+    for (width, height) in [(640, 360), (1920, 1080), (3840, 2160)] {
+        let interpolator =
+            Interpolator::new(vec![image(width, height, 7), image(width, height, 13)], 2).unwrap();
+        group.throughput(Throughput::Elements(width as u64 * height as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!("{}x{}", width, height)),
+            &interpolator,
+            |b, interpolator| b.iter(|| interpolator.frame_at_position(0.5).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+fn interpolate_sequence(c: &mut Criterion) {
+    let frames = 30;
+    let keyframes = vec![image(640, 360, 7), image(640, 360, 13), image(640, 360, 29)];
+    let interpolator = Interpolator::new(keyframes, frames / 2).unwrap();
+
+    let mut group = c.benchmark_group("interpolator");
+    group.sample_size(20);
+    group.throughput(Throughput::Elements(interpolator.total_frames() as u64));
+    group.bench_function("640x360 sequence", |b| {
+        b.iter(|| interpolator.clone().collect::<Result<Vec<_>, _>>().unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, smooth_channel, blend_frame, interpolate_sequence);
+criterion_main!(benches);
@@ -0,0 +1,504 @@
+#[cfg(feature = "gif")]
+use std::io::BufReader;
+use std::{
+    collections::HashMap,
+    fmt::{self, Debug, Formatter},
+    fs::File,
+    io::Read,
+    mem,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{anyhow, Result};
+use log::debug;
+
+#[cfg(feature = "gif")]
+use crate::error::DecodeContext;
+use crate::{
+    bmp,
+    error::open_image,
+    exr,
+    image::{open_png, png_error},
+    jpeg, tiff, BitDepth, ColorProfile, ColorType, Image, ImageInfo, Sample, Samples,
+};
+
+/// The bytes every GIF file starts with, followed by its version
+const GIF_SIGNATURE: [u8; 4] = *b"GIF8";
+
+/// Checks the first bytes of the file at `path` to see whether it is a GIF
+pub(crate) fn is_gif(path: &Path) -> Result<bool> {
+    let mut file = open_image(path)?;
+    let mut signature = [0; 4];
+    match file.read_exact(&mut signature) {
+        Ok(()) => Ok(signature == GIF_SIGNATURE),
+        // files too short to be a GIF are left for the PNG decoder to report
+        Err(_) => Ok(false),
+    }
+}
+
+/// The number of frames in the animation at `path`, or `None` when it is a still image.
+///
+/// Every GIF counts as an animation, even one with a single frame, as they are only
+/// decoded frame by frame. PNGs are animations when they have an `acTL` chunk,
+/// and TIFFs when they have more than one page.
+pub(crate) fn frame_count(path: &Path) -> Result<Option<usize>> {
+    if is_gif(path)? {
+        return animation_frames(path, |_| false, usize::MAX).map(|(_, count)| Some(count));
+    }
+    if jpeg::is_jpeg(path)? || bmp::is_bmp(path)? || exr::is_exr(path)? {
+        return Ok(None);
+    }
+    if tiff::is_tiff(path)? {
+        let pages = tiff::page_count(path)?;
+        return Ok((pages > 1).then_some(pages));
+    }
+
+    let (reader, _) = open_png(path)?;
+    Ok(reader
+        .info()
+        .animation_control
+        .map(|animation| animation.num_frames as usize))
+}
+
+/// The properties of the frames of the GIF at `path`, which are always 8 bit RGBA
+/// as each one is drawn on to a canvas which starts out transparent
+pub(crate) fn gif_info(path: &Path) -> Result<ImageInfo> {
+    let (width, height) = gif_size(path)?;
+    Ok(ImageInfo {
+        width,
+        height,
+        color_type: ColorType::Rgba,
+        bit_depth: BitDepth::Eight,
+    })
+}
+
+/// Decodes frame `index` of the animation at `path` as it is shown, having drawn every
+/// frame before it on to the canvas in turn
+pub(crate) fn decode_frame(path: &Path, index: usize) -> Result<Image> {
+    if tiff::is_tiff(path)? {
+        return tiff::decode(path, index);
+    }
+    Animation::open(path)?.frame(index)
+}
+
+/// Draws the first `limit` frames of the GIF or APNG at `path` in turn, returning the frames
+/// whose index `keep` returns true for along with the number of frames drawn
+fn animation_frames(
+    path: &Path,
+    keep: impl Fn(usize) -> bool,
+    limit: usize,
+) -> Result<(Vec<Image>, usize)> {
+    let mut animation = Animation::open(path)?;
+    let mut kept = Vec::new();
+    while animation.drawn < limit && animation.draw_next()? {
+        if keep(animation.drawn - 1) {
+            kept.push(animation.image()?);
+        }
+    }
+    debug!("Decoded {} frames of {:?}", animation.drawn, path);
+    Ok((kept, animation.drawn))
+}
+
+/// The animations lazily loaded keyframes are taken from, each one left at the last frame
+/// drawn so that asking for its frames in order draws each of them only once
+#[derive(Debug, Default)]
+pub(crate) struct Animations(Mutex<HashMap<PathBuf, Arc<Mutex<Animation>>>>);
+
+impl Animations {
+    /// Decodes frame `index` of the animation at `path` as it is shown, carrying on from
+    /// the last frame drawn of it when that comes before `index`
+    pub(crate) fn frame(&self, path: &Path, index: usize) -> Result<Image> {
+        // the pages of a TIFF don't depend on each other, so any can be decoded directly
+        if tiff::is_tiff(path)? {
+            return tiff::decode(path, index);
+        }
+        let animation = {
+            let mut animations = self.0.lock().unwrap();
+            match animations.get(path) {
+                Some(animation) => Arc::clone(animation),
+                None => {
+                    let animation = Arc::new(Mutex::new(Animation::open(path)?));
+                    animations.insert(path.to_path_buf(), Arc::clone(&animation));
+                    animation
+                }
+            }
+        };
+        let mut animation = animation.lock().unwrap();
+        animation.frame(index)
+    }
+}
+
+/// A GIF or APNG part way through being drawn, its canvas showing the last frame drawn
+pub(crate) struct Animation {
+    path: PathBuf,
+    decoder: Decoder,
+    info: ImageInfo,
+    profile: ColorProfile,
+    canvas: Samples,
+    /// The canvas as it was showing the frame before the last one drawn, kept by `frame`
+    /// as threads fetching neighbouring keyframes can ask for them either way round
+    previous: Option<Samples>,
+    /// The number of frames drawn on to the canvas so far
+    drawn: usize,
+    /// What happens to the last frame drawn before the next one is drawn
+    dispose: Dispose,
+}
+
+enum Decoder {
+    #[cfg(feature = "gif")]
+    Gif(Box<gif::Decoder<BufReader<File>>>),
+    Apng {
+        reader: Box<png::Reader<File>>,
+        buf: Vec<u8>,
+        frames: usize,
+    },
+}
+
+/// How a frame is removed from the canvas before the next one is drawn
+enum Dispose {
+    Keep,
+    /// Clears the region the frame covered back to transparent black
+    Clear(Region),
+    /// Puts back the canvas from before the frame was drawn
+    Restore(Samples),
+}
+
+impl Debug for Animation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Animation")
+            .field("path", &self.path)
+            .field("info", &self.info)
+            .field("drawn", &self.drawn)
+            .finish()
+    }
+}
+
+impl Animation {
+    /// Opens the GIF or APNG at `path` with nothing yet drawn on to its canvas
+    pub(crate) fn open(path: &Path) -> Result<Self> {
+        if is_gif(path)? {
+            return Self::open_gif(path);
+        }
+
+        let (mut reader, info) = open_png(path)?;
+        let profile = ColorProfile::from_png_info(reader.info());
+        let frames = reader
+            .info()
+            .animation_control
+            .map_or(1, |animation| animation.num_frames as usize);
+        // without an fcTL chunk before it the IDAT image only stands in for the animation
+        // in viewers which can't play it
+        let skip_default = reader.info().frame_control.is_none();
+
+        let mut buf = vec![0; reader.output_buffer_size()];
+        if skip_default {
+            reader.next_frame(&mut buf).map_err(|e| {
+                png_error(e, path, "part way through its pixels", || {
+                    format!("Reader failed to read any frames from {:?}", path)
+                })
+            })?;
+        }
+
+        Ok(Animation {
+            path: path.to_path_buf(),
+            canvas: Samples::from_be_bytes(vec![0; buf.len()], info.bit_depth),
+            decoder: Decoder::Apng {
+                reader: Box::new(reader),
+                buf,
+                frames,
+            },
+            info,
+            profile,
+            previous: None,
+            drawn: 0,
+            dispose: Dispose::Keep,
+        })
+    }
+
+    #[cfg(feature = "gif")]
+    fn open_gif(path: &Path) -> Result<Self> {
+        let decoder = open_gif(path)?;
+        let info = ImageInfo {
+            width: decoder.width() as u32,
+            height: decoder.height() as u32,
+            color_type: ColorType::Rgba,
+            bit_depth: BitDepth::Eight,
+        };
+        Ok(Animation {
+            path: path.to_path_buf(),
+            canvas: Samples::Eight(vec![0; info.width as usize * info.height as usize * 4]),
+            decoder: Decoder::Gif(Box::new(decoder)),
+            info,
+            profile: ColorProfile::default(),
+            previous: None,
+            drawn: 0,
+            dispose: Dispose::Keep,
+        })
+    }
+
+    #[cfg(not(feature = "gif"))]
+    fn open_gif(path: &Path) -> Result<Self> {
+        Err(disabled(path))
+    }
+
+    /// Decodes frame `index` as it is shown, carrying on from the last frame drawn when
+    /// `index` comes after it and starting again from the first frame when it doesn't
+    pub(crate) fn frame(&mut self, index: usize) -> Result<Image> {
+        if index + 1 == self.drawn {
+            return self.image();
+        }
+        if index + 2 == self.drawn {
+            if let Some(previous) = &self.previous {
+                return self.canvas_image(previous.clone());
+            }
+        }
+        if index + 1 < self.drawn {
+            *self = Self::open(&self.path)?;
+        }
+
+        while self.drawn <= index {
+            if self.drawn == index {
+                self.previous = (index > 0).then(|| self.canvas.clone());
+            }
+            if !self.draw_next()? {
+                return Err(anyhow!("{:?} has no frame {}.", self.path, index));
+            }
+        }
+        self.image()
+    }
+
+    /// Draws the next frame on to the canvas, returning false once every frame is drawn
+    fn draw_next(&mut self) -> Result<bool> {
+        let info = self.info;
+        match mem::replace(&mut self.dispose, Dispose::Keep) {
+            Dispose::Keep => {}
+            Dispose::Clear(region) => match &mut self.canvas {
+                Samples::Eight(canvas) => clear(canvas, info, region),
+                Samples::Sixteen(canvas) => clear(canvas, info, region),
+                Samples::Float(canvas) => clear(canvas, info, region),
+            },
+            Dispose::Restore(canvas) => self.canvas = canvas,
+        }
+
+        let (n, path) = (self.drawn, &self.path);
+        let (frame, region, over, dispose) = match &mut self.decoder {
+            #[cfg(feature = "gif")]
+            Decoder::Gif(decoder) => {
+                let frame = match decoder.read_next_frame().decode_context(path, || {
+                    format!("Decoder failed to decode frame {} of {:?}", n, path)
+                })? {
+                    Some(frame) => frame,
+                    None => return Ok(false),
+                };
+                let region = Region {
+                    left: frame.left as u32,
+                    top: frame.top as u32,
+                    width: frame.width as u32,
+                    height: frame.height as u32,
+                };
+                let dispose = match frame.dispose {
+                    gif::DisposalMethod::Any | gif::DisposalMethod::Keep => Dispose::Keep,
+                    gif::DisposalMethod::Background => Dispose::Clear(region),
+                    gif::DisposalMethod::Previous => Dispose::Restore(self.canvas.clone()),
+                };
+                // GIF transparency is all or nothing, so a frame is always drawn over the canvas
+                (Samples::Eight(frame.buffer.to_vec()), region, true, dispose)
+            }
+            Decoder::Apng {
+                reader,
+                buf,
+                frames,
+            } => {
+                if n >= *frames {
+                    return Ok(false);
+                }
+                let output = reader.next_frame(buf).map_err(|e| {
+                    png_error(e, path, "part way through its frames", || {
+                        format!("Reader failed to read frame {} from {:?}", n, path)
+                    })
+                })?;
+                let control = reader.info().frame_control.unwrap_or_default();
+                let region = Region {
+                    left: control.x_offset,
+                    top: control.y_offset,
+                    width: output.width,
+                    height: output.height,
+                };
+                let dispose = match control.dispose_op {
+                    png::DisposeOp::None => Dispose::Keep,
+                    png::DisposeOp::Background => Dispose::Clear(region),
+                    png::DisposeOp::Previous => Dispose::Restore(self.canvas.clone()),
+                };
+                let frame =
+                    Samples::from_be_bytes(buf[..output.buffer_size()].to_vec(), info.bit_depth);
+                let over = control.blend_op == png::BlendOp::Over && info.color_type.has_alpha();
+                (frame, region, over, dispose)
+            }
+        };
+
+        match (&mut self.canvas, &frame) {
+            (Samples::Eight(canvas), Samples::Eight(frame)) => {
+                draw(canvas, frame, info, region, over)
+            }
+            (Samples::Sixteen(canvas), Samples::Sixteen(frame)) => {
+                draw(canvas, frame, info, region, over)
+            }
+            _ => unreachable!("the frames of an animation share its bit depth"),
+        }
+        self.dispose = dispose;
+        self.drawn += 1;
+        Ok(true)
+    }
+
+    /// The canvas as it is showing the last frame drawn
+    fn image(&self) -> Result<Image> {
+        self.canvas_image(self.canvas.clone())
+    }
+
+    fn canvas_image(&self, canvas: Samples) -> Result<Image> {
+        let image = Image::new_from_parts(
+            canvas,
+            self.info.color_type,
+            self.info.width,
+            self.info.height,
+        )?;
+        Ok(image.with_color_profile(self.profile.clone()))
+    }
+}
+
+impl Image {
+    /// Decodes every frame of the animated GIF or APNG at `p` as it is shown, or every page
+    /// of a multi-page TIFF, for interpolating between them as keyframes, any other image
+    /// giving just itself.
+    ///
+    /// Frames covering only part of the image are drawn over what came before them,
+    /// following the way each frame says it should be removed again. The pages of a TIFF
+    /// are decoded as they are stored, without turning them upright.
+    pub fn frames_from_path<P>(p: P) -> Result<Vec<Image>>
+    where
+        P: AsRef<Path> + Debug,
+    {
+        let path = p.as_ref();
+        match frame_count(path)? {
+            Some(pages) if tiff::is_tiff(path)? => {
+                (0..pages).map(|page| tiff::decode(path, page)).collect()
+            }
+            Some(_) => Ok(animation_frames(path, |_| true, usize::MAX)?.0),
+            None => Ok(vec![Image::new_from_path(path)?]),
+        }
+    }
+}
+
+#[cfg(feature = "gif")]
+fn open_gif(path: &Path) -> Result<gif::Decoder<BufReader<File>>> {
+    let file = open_image(path)?;
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::RGBA);
+    let decoder = options
+        .read_info(BufReader::new(file))
+        .decode_context(path, || {
+            format!("Decoder failed to read information from {:?}", path)
+        })?;
+    Ok(decoder)
+}
+
+/// The size of the canvas the frames of the GIF at `path` are drawn on to
+#[cfg(feature = "gif")]
+fn gif_size(path: &Path) -> Result<(u32, u32)> {
+    let decoder = open_gif(path)?;
+    Ok((decoder.width() as u32, decoder.height() as u32))
+}
+
+// GIFs are still recognised without the decoder, so they can be reported clearly
+// rather than failing as broken PNGs
+#[cfg(not(feature = "gif"))]
+fn gif_size(path: &Path) -> Result<(u32, u32)> {
+    Err(disabled(path))
+}
+
+#[cfg(not(feature = "gif"))]
+fn disabled(path: &Path) -> anyhow::Error {
+    anyhow!(
+        "{:?} is a GIF, which can only be decoded when interpol is built with the \"gif\" feature.",
+        path
+    )
+}
+
+/// The rectangle of the canvas a frame covers
+#[derive(Debug, Clone, Copy)]
+struct Region {
+    left: u32,
+    top: u32,
+    width: u32,
+    height: u32,
+}
+
+impl Region {
+    /// The rows of the canvas described by `info` the region covers,
+    /// as ranges of channel values along with the matching range of the frame's values
+    fn rows(self, info: ImageInfo) -> impl Iterator<Item = (Range<usize>, Range<usize>)> {
+        let channels = info.color_type.channels();
+        // frames reaching past the edges of the canvas are clipped to it
+        let width = self.width.min(info.width.saturating_sub(self.left)) as usize;
+        let height = self.height.min(info.height.saturating_sub(self.top)) as usize;
+        (0..height).map(move |y| {
+            let canvas =
+                ((self.top as usize + y) * info.width as usize + self.left as usize) * channels;
+            let frame = y * self.width as usize * channels;
+            (
+                canvas..canvas + width * channels,
+                frame..frame + width * channels,
+            )
+        })
+    }
+}
+
+/// Draws `frame` on to the `region` of `canvas`, either replacing the pixels there or
+/// compositing it `over` them by its alpha
+fn draw<S: Sample>(canvas: &mut [S], frame: &[S], info: ImageInfo, region: Region, over: bool) {
+    let channels = info.color_type.channels();
+    for (canvas_row, frame_row) in region.rows(info) {
+        let (canvas_row, frame_row) = (&mut canvas[canvas_row], &frame[frame_row]);
+        if !over {
+            canvas_row.copy_from_slice(frame_row);
+            continue;
+        }
+
+        for (dst, src) in canvas_row
+            .chunks_exact_mut(channels)
+            .zip(frame_row.chunks_exact(channels))
+        {
+            let (src_alpha, dst_alpha) = (
+                src[channels - 1].to_normalized(),
+                dst[channels - 1].to_normalized(),
+            );
+            if src_alpha >= 1.0 || dst_alpha <= 0.0 {
+                dst.copy_from_slice(src);
+                continue;
+            }
+            if src_alpha <= 0.0 {
+                continue;
+            }
+
+            // source-over compositing of two colors which both have alpha
+            let alpha = src_alpha + dst_alpha * (1.0 - src_alpha);
+            for (d, s) in dst[..channels - 1].iter_mut().zip(src) {
+                let color = s.to_normalized() * src_alpha
+                    + d.to_normalized() * dst_alpha * (1.0 - src_alpha);
+                *d = S::from_normalized(color / alpha);
+            }
+            dst[channels - 1] = S::from_normalized(alpha);
+        }
+    }
+}
+
+/// Clears the `region` of `canvas` back to transparent black
+fn clear<S: Sample>(canvas: &mut [S], info: ImageInfo, region: Region) {
+    for (canvas_row, _) in region.rows(info) {
+        for value in &mut canvas[canvas_row] {
+            *value = S::from_f64(0.0);
+        }
+    }
+}
@@ -0,0 +1,852 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use wide::f32x8;
+
+use crate::{Accumulator, BitDepth, ColorType, Image, Sample, Samples};
+
+pub(crate) fn interpolate(
+    mu: f64,
+    im1: &Image,
+    im2: &Image,
+    color_space: ColorSpace,
+    gamma_correct: bool,
+) -> Image {
+    let color_type = im1.color_type;
+    let new_image_data = match (&im1.data, &im2.data) {
+        // the most common case has a vectorised fast path
+        (Samples::Eight(s), Samples::Eight(e))
+            if !gamma_correct
+                && (color_space == ColorSpace::Rgb
+                    || (color_space == ColorSpace::Hsv && color_type.color_channels() == 1)) =>
+        {
+            Samples::Eight(blend_eight_bit(mu, s, e))
+        }
+        (Samples::Eight(s), Samples::Eight(e)) => {
+            Samples::Eight(blend(mu, s, e, color_type, color_space, gamma_correct))
+        }
+        (Samples::Sixteen(s), Samples::Sixteen(e)) => {
+            Samples::Sixteen(blend(mu, s, e, color_type, color_space, gamma_correct))
+        }
+        (Samples::Float(s), Samples::Float(e)) => {
+            Samples::Float(blend(mu, s, e, color_type, color_space, gamma_correct))
+        }
+        _ => unreachable!("images of differing bit depths are rejected by the Interpolator"),
+    };
+
+    // the data is built pixel for pixel from images of identical dimensions and color type
+    Image {
+        data: new_image_data,
+        color_type: im1.color_type,
+        width: im1.width,
+        height: im1.height,
+        profile: im1.profile.clone(),
+    }
+}
+
+/// Blends two images with a separate weight for each of the color channels and `mode`
+/// combining them, blending any alpha channel by `alpha_mu` in the usual way.
+///
+/// Gray images take the first weight, RGB and RGBA ones one each for red, green and blue.
+pub(crate) fn interpolate_per_channel(
+    mode: BlendMode,
+    mus: [f64; 3],
+    alpha_mu: f64,
+    im1: &Image,
+    im2: &Image,
+    gamma_correct: bool,
+) -> Image {
+    let color_type = im1.color_type;
+    let (channels, color_channels) = (color_type.channels(), color_type.color_channels());
+    let weight = |i: usize| match i % channels {
+        c if c < color_channels => mus[c],
+        _ => alpha_mu,
+    };
+    // alpha is always mixed, whatever happens to the colors
+    let blend = |i: usize| {
+        if i % channels < color_channels {
+            (mode, weight(i), gamma_correct)
+        } else {
+            (BlendMode::Mix, weight(i), false)
+        }
+    };
+    let new_image_data = match (&im1.data, &im2.data) {
+        (Samples::Eight(s), Samples::Eight(e)) => Samples::Eight(blend_per_channel(blend, s, e)),
+        (Samples::Sixteen(s), Samples::Sixteen(e)) => {
+            Samples::Sixteen(blend_per_channel(blend, s, e))
+        }
+        (Samples::Float(s), Samples::Float(e)) => Samples::Float(blend_per_channel(blend, s, e)),
+        _ => unreachable!("images of differing bit depths are rejected by the Interpolator"),
+    };
+
+    Image {
+        data: new_image_data,
+        color_type: im1.color_type,
+        width: im1.width,
+        height: im1.height,
+        profile: im1.profile.clone(),
+    }
+}
+
+/// Blends the channel values of two images in RGB, `blend` giving the mode, weight and
+/// whether to work in linear light for each value given its index
+fn blend_per_channel<S: Sample>(
+    blend: impl Fn(usize) -> (BlendMode, f64, bool),
+    im1: &[S],
+    im2: &[S],
+) -> Vec<S> {
+    im1.iter()
+        .zip(im2.iter())
+        .enumerate()
+        .map(|(i, (&s, &e))| match blend(i) {
+            (BlendMode::Mix, mu, false) => smooth(mu, s, e),
+            (BlendMode::Mix, mu, true) => smooth_linear_light(mu, s, e),
+            (mode, mu, false) => {
+                S::from_normalized(mode.apply(mu, s.to_normalized(), e.to_normalized()))
+            }
+            (mode, mu, true) => {
+                let (s, e) = (
+                    srgb_to_linear(s.to_normalized()),
+                    srgb_to_linear(e.to_normalized()),
+                );
+                S::from_normalized(linear_to_srgb(mode.apply(mu, s, e)))
+            }
+        })
+        .collect()
+}
+
+/// Blends every 8 bit channel value of two images directly, eight at a time using SIMD.
+///
+/// 8 bit values don't need the precision of `f64`, so this works in `f32` which fits twice
+/// as many values in each vector, the leftover values use the same arithmetic one at a time.
+fn blend_eight_bit(mu: f64, im1: &[u8], im2: &[u8]) -> Vec<u8> {
+    let t2 = mu as f32;
+    let t1 = 1.0 - t2;
+
+    let mut data = vec![0; im1.len()];
+    let mut out = data.chunks_exact_mut(LANES);
+    let mut starts = im1.chunks_exact(LANES);
+    let mut ends = im2.chunks_exact(LANES);
+    let (w1, w2) = (f32x8::splat(t1), f32x8::splat(t2));
+    let (half, max) = (f32x8::splat(0.5), f32x8::splat(255.0));
+    for ((out, s), e) in (&mut out).zip(&mut starts).zip(&mut ends) {
+        let s = f32x8::from(std::array::from_fn::<_, LANES, _>(|i| s[i] as f32));
+        let e = f32x8::from(std::array::from_fn::<_, LANES, _>(|i| e[i] as f32));
+        // adding a half and truncating rounds halves up like f64::round does, as anything
+        // an overshooting curve takes below zero is clamped to it first
+        let blended = (s * w1 + e * w2 + half).max(f32x8::ZERO).min(max);
+        for (o, c) in out.iter_mut().zip(blended.trunc_int().to_array()) {
+            *o = c as u8;
+        }
+    }
+
+    for ((o, &s), &e) in out
+        .into_remainder()
+        .iter_mut()
+        .zip(starts.remainder())
+        .zip(ends.remainder())
+    {
+        *o = (s as f32 * t1 + e as f32 * t2 + 0.5).clamp(0.0, 255.0) as u8;
+    }
+    data
+}
+
+/// The number of channel values blended at once by `blend_eight_bit`
+const LANES: usize = 8;
+
+/// Blends the channel values of two images whose pixels are made up of `color_type`'s channels
+fn blend<S: Sample>(
+    mu: f64,
+    im1: &[S],
+    im2: &[S],
+    color_type: ColorType,
+    color_space: ColorSpace,
+    gamma_correct: bool,
+) -> Vec<S> {
+    let channels = color_type.channels();
+    let color_channels = color_type.color_channels();
+    // gray has no hue or saturation, so it blends the same in HSV as in RGB
+    let color_space = if color_channels == 1 && color_space == ColorSpace::Hsv {
+        ColorSpace::Rgb
+    } else {
+        color_space
+    };
+
+    match color_space {
+        ColorSpace::Rgb if gamma_correct => {
+            // alpha is already linear so only the color channels go through the transfer function
+            im1.iter()
+                .zip(im2.iter())
+                .enumerate()
+                .map(|(i, (s, e))| {
+                    if i % channels < color_channels {
+                        smooth_linear_light(mu, *s, *e)
+                    } else {
+                        smooth(mu, *s, *e)
+                    }
+                })
+                .collect()
+        }
+        // every channel, including alpha, is blended in the same way
+        ColorSpace::Rgb => im1
+            .iter()
+            .zip(im2.iter())
+            .map(|(s, e)| smooth(mu, *s, *e))
+            .collect(),
+        ColorSpace::Hsv => {
+            let decode = |c: S| {
+                if gamma_correct {
+                    srgb_to_linear(c.to_normalized())
+                } else {
+                    c.to_normalized()
+                }
+            };
+            let encode =
+                |c: f64| S::from_normalized(if gamma_correct { linear_to_srgb(c) } else { c });
+
+            let mut data = Vec::with_capacity(im1.len());
+            for (p1, p2) in im1.chunks_exact(channels).zip(im2.chunks_exact(channels)) {
+                let hsv1 = rgb_to_hsv([decode(p1[0]), decode(p1[1]), decode(p1[2])]);
+                let hsv2 = rgb_to_hsv([decode(p2[0]), decode(p2[1]), decode(p2[2])]);
+                let rgb = hsv_to_rgb(smooth_hsv(mu, hsv1, hsv2, S::BOUNDED));
+                data.extend(rgb.iter().map(|c| encode(*c)));
+                data.extend(
+                    p1[3..]
+                        .iter()
+                        .zip(&p2[3..])
+                        .map(|(s, e)| smooth(mu, *s, *e)),
+                );
+            }
+            data
+        }
+        // OKLab is defined on linear light, so the channels are always decoded from sRGB
+        ColorSpace::Oklab => {
+            let decode = |p: &[S]| {
+                let linear = |c: S| srgb_to_linear(c.to_normalized());
+                if color_channels == 1 {
+                    [linear(p[0]); 3]
+                } else {
+                    [linear(p[0]), linear(p[1]), linear(p[2])]
+                }
+            };
+
+            let mut data = Vec::with_capacity(im1.len());
+            for (p1, p2) in im1.chunks_exact(channels).zip(im2.chunks_exact(channels)) {
+                let (lab1, lab2) = (linear_to_oklab(decode(p1)), linear_to_oklab(decode(p2)));
+                let lab: Vec<_> = lab1
+                    .iter()
+                    .zip(&lab2)
+                    .map(|(a, b)| lerp(mu, *a, *b))
+                    .collect();
+                // colors between two in gamut colors can still fall just outside the RGB
+                // cube, so each channel is clipped back into range before encoding, apart
+                // from in float images where they can be brighter than white
+                let rgb = oklab_to_linear([lab[0], lab[1], lab[2]])
+                    .map(|c| S::from_normalized(linear_to_srgb(clip::<S>(c))));
+                data.extend_from_slice(&rgb[..color_channels]);
+                data.extend(
+                    p1[color_channels..]
+                        .iter()
+                        .zip(&p2[color_channels..])
+                        .map(|(s, e)| smooth(mu, *s, *e)),
+                );
+            }
+            data
+        }
+    }
+}
+
+/// Blends two images with alpha in RGB with their colors premultiplied by their alpha,
+/// so the colors of transparent pixels don't bleed into the blend as dark fringes.
+///
+/// The blended colors are divided by the blended alpha again afterwards, pixels which
+/// end up fully transparent have no color to recover and are left as transparent black.
+pub(crate) fn interpolate_premultiplied(
+    mu: f64,
+    im1: &Image,
+    im2: &Image,
+    gamma_correct: bool,
+) -> Image {
+    let color_type = im1.color_type;
+    let new_image_data = match (&im1.data, &im2.data) {
+        (Samples::Eight(s), Samples::Eight(e)) => {
+            Samples::Eight(blend_premultiplied(mu, s, e, color_type, gamma_correct))
+        }
+        (Samples::Sixteen(s), Samples::Sixteen(e)) => {
+            Samples::Sixteen(blend_premultiplied(mu, s, e, color_type, gamma_correct))
+        }
+        (Samples::Float(s), Samples::Float(e)) => {
+            Samples::Float(blend_premultiplied(mu, s, e, color_type, gamma_correct))
+        }
+        _ => unreachable!("images of differing bit depths are rejected by the Interpolator"),
+    };
+
+    Image {
+        data: new_image_data,
+        color_type,
+        width: im1.width,
+        height: im1.height,
+        profile: im1.profile.clone(),
+    }
+}
+
+/// Blends the pixels of two images for `interpolate_premultiplied`,
+/// each pixel being its color channels followed by alpha
+fn blend_premultiplied<S: Sample>(
+    mu: f64,
+    im1: &[S],
+    im2: &[S],
+    color_type: ColorType,
+    gamma_correct: bool,
+) -> Vec<S> {
+    let color_channels = color_type.color_channels();
+    let decode = |c: S| {
+        if gamma_correct {
+            srgb_to_linear(c.to_normalized())
+        } else {
+            c.to_normalized()
+        }
+    };
+    let encode = |c: f64| S::from_normalized(if gamma_correct { linear_to_srgb(c) } else { c });
+
+    let mut data = Vec::with_capacity(im1.len());
+    for (p1, p2) in im1
+        .chunks_exact(color_type.channels())
+        .zip(im2.chunks_exact(color_type.channels()))
+    {
+        let (a1, a2) = (
+            p1[color_channels].to_normalized(),
+            p2[color_channels].to_normalized(),
+        );
+        let alpha = lerp(mu, a1, a2);
+        if alpha <= 0.0 {
+            data.extend((0..=color_channels).map(|_| S::from_f64(0.0)));
+            continue;
+        }
+
+        data.extend(
+            p1[..color_channels]
+                .iter()
+                .zip(&p2[..color_channels])
+                .map(|(s, e)| encode(lerp(mu, decode(*s) * a1, decode(*e) * a2) / alpha)),
+        );
+        data.push(S::from_normalized(alpha));
+    }
+    data
+}
+
+/// Blends between the two middle images of four consecutive keyframes along a Catmull-Rom
+/// spline through every channel value, so the motion carries smoothly through keyframes
+pub(crate) fn interpolate_catmull_rom(
+    mu: f64,
+    [im0, im1, im2, im3]: [&Image; 4],
+    gamma_correct: bool,
+) -> Image {
+    let color_type = im1.color_type;
+    let new_image_data = match (&im0.data, &im1.data, &im2.data, &im3.data) {
+        (Samples::Eight(p0), Samples::Eight(p1), Samples::Eight(p2), Samples::Eight(p3)) => {
+            Samples::Eight(catmull_rom(mu, [p0, p1, p2, p3], color_type, gamma_correct))
+        }
+        (
+            Samples::Sixteen(p0),
+            Samples::Sixteen(p1),
+            Samples::Sixteen(p2),
+            Samples::Sixteen(p3),
+        ) => Samples::Sixteen(catmull_rom(mu, [p0, p1, p2, p3], color_type, gamma_correct)),
+        (Samples::Float(p0), Samples::Float(p1), Samples::Float(p2), Samples::Float(p3)) => {
+            Samples::Float(catmull_rom(mu, [p0, p1, p2, p3], color_type, gamma_correct))
+        }
+        _ => unreachable!("images of differing bit depths are rejected by the Interpolator"),
+    };
+
+    Image {
+        data: new_image_data,
+        color_type: im1.color_type,
+        width: im1.width,
+        height: im1.height,
+        profile: im1.profile.clone(),
+    }
+}
+
+/// Evaluates the spline through the channel values of four images of `color_type`,
+/// `mu` going from the second image at 0.0 to the third at 1.0
+fn catmull_rom<S: Sample>(
+    mu: f64,
+    points: [&[S]; 4],
+    color_type: ColorType,
+    gamma_correct: bool,
+) -> Vec<S> {
+    let (channels, color_channels) = (color_type.channels(), color_type.color_channels());
+    (0..points[1].len())
+        .map(|i| {
+            // alpha is already linear so only the color channels go through the transfer function
+            let linear = gamma_correct && i % channels < color_channels;
+            let p = points.map(|channel| {
+                if linear {
+                    srgb_to_linear(channel[i].to_normalized())
+                } else {
+                    channel[i].to_normalized()
+                }
+            });
+
+            // the spline can overshoot past the control points, which from_normalized clamps
+            let c = 0.5
+                * (2.0 * p[1]
+                    + (p[2] - p[0]) * mu
+                    + (2.0 * p[0] - 5.0 * p[1] + 4.0 * p[2] - p[3]) * mu * mu
+                    + (3.0 * (p[1] - p[2]) + p[3] - p[0]) * mu * mu * mu);
+            S::from_normalized(if linear {
+                linear_to_srgb(c.max(0.0))
+            } else {
+                c
+            })
+        })
+        .collect()
+}
+
+/// The mean of any number of images of the same size and format in a color space, summing
+/// their channel values at the precision of `f64` so the mean is only rounded once
+pub(crate) struct Mean {
+    /// The first image added without its pixels, which the mean takes its format from
+    image: Image,
+    bit_depth: BitDepth,
+    /// The values of every pixel in the color space, added up over the images
+    sums: Vec<f64>,
+    count: usize,
+    color_space: ColorSpace,
+    gamma_correct: bool,
+}
+
+impl Mean {
+    /// Starts the mean off with just `image`
+    pub(crate) fn new(image: Image, color_space: ColorSpace, gamma_correct: bool) -> Self {
+        // gray has no hue or saturation, so it averages the same in HSV as in RGB
+        let color_space =
+            if image.color_type.color_channels() == 1 && color_space == ColorSpace::Hsv {
+                ColorSpace::Rgb
+            } else {
+                color_space
+            };
+        let mut mean = Mean {
+            sums: Vec::new(),
+            count: 0,
+            bit_depth: image.data.bit_depth(),
+            image: Image {
+                data: Samples::Eight(Vec::new()),
+                ..image
+            },
+            color_space,
+            gamma_correct,
+        };
+        mean.add_samples(&image.data);
+        mean
+    }
+
+    /// Adds `image` into the mean, it must have the size and color type of the first image
+    pub(crate) fn add(&mut self, image: &Image) {
+        self.add_samples(&image.data);
+    }
+
+    /// The mean of the images added, at the bit depth of the first one
+    pub(crate) fn finish(self) -> Image {
+        let data = match self.bit_depth {
+            BitDepth::Eight => Samples::Eight(self.pixels()),
+            BitDepth::Sixteen => Samples::Sixteen(self.pixels()),
+            BitDepth::Float => Samples::Float(self.pixels()),
+        };
+        Image { data, ..self.image }
+    }
+
+    /// The number of values kept for each pixel, which is one for each channel apart from
+    /// in HSV where the hue takes two
+    fn slots(&self) -> usize {
+        let color_type = self.image.color_type;
+        let alpha = color_type.channels() - color_type.color_channels();
+        match self.color_space {
+            ColorSpace::Rgb => color_type.channels(),
+            ColorSpace::Hsv => 4 + alpha,
+            ColorSpace::Oklab => 3 + alpha,
+        }
+    }
+
+    fn add_samples(&mut self, data: &Samples) {
+        match data {
+            Samples::Eight(data) => self.add_pixels(data),
+            Samples::Sixteen(data) => self.add_pixels(data),
+            Samples::Float(data) => self.add_pixels(data),
+        }
+    }
+
+    fn add_pixels<S: Sample>(&mut self, data: &[S]) {
+        let color_type = self.image.color_type;
+        let (channels, color_channels) = (color_type.channels(), color_type.color_channels());
+        let slots = self.slots();
+        if self.sums.is_empty() {
+            self.sums = vec![0.0; data.len() / channels * slots];
+        }
+        let gamma_correct = self.gamma_correct;
+        let decode = |c: S| {
+            if gamma_correct {
+                srgb_to_linear(c.to_normalized())
+            } else {
+                c.to_normalized()
+            }
+        };
+
+        for (pixel, sums) in data
+            .chunks_exact(channels)
+            .zip(self.sums.chunks_exact_mut(slots))
+        {
+            let (color, alpha) = sums.split_at_mut(slots - (channels - color_channels));
+            match self.color_space {
+                ColorSpace::Rgb => {
+                    for (sum, c) in color.iter_mut().zip(&pixel[..color_channels]) {
+                        *sum += decode(*c);
+                    }
+                }
+                ColorSpace::Hsv => {
+                    // hues are added as directions around the color wheel, weighted by their
+                    // saturation as a color without any has no meaningful hue
+                    let [h, s, v] =
+                        rgb_to_hsv([decode(pixel[0]), decode(pixel[1]), decode(pixel[2])]);
+                    let h = h.to_radians();
+                    for (sum, value) in color.iter_mut().zip([s * h.cos(), s * h.sin(), s, v]) {
+                        *sum += value;
+                    }
+                }
+                // OKLab is defined on linear light, so the channels are always decoded from sRGB
+                ColorSpace::Oklab => {
+                    let linear = |c: S| srgb_to_linear(c.to_normalized());
+                    let rgb = if color_channels == 1 {
+                        [linear(pixel[0]); 3]
+                    } else {
+                        [linear(pixel[0]), linear(pixel[1]), linear(pixel[2])]
+                    };
+                    for (sum, value) in color.iter_mut().zip(linear_to_oklab(rgb)) {
+                        *sum += value;
+                    }
+                }
+            }
+            for (sum, a) in alpha.iter_mut().zip(&pixel[color_channels..]) {
+                *sum += a.to_normalized();
+            }
+        }
+        self.count += 1;
+    }
+
+    fn pixels<S: Sample>(&self) -> Vec<S> {
+        let color_type = self.image.color_type;
+        let (channels, color_channels) = (color_type.channels(), color_type.color_channels());
+        let slots = self.slots();
+        let n = self.count as f64;
+        let encode = |c: f64| {
+            S::from_normalized(if self.gamma_correct {
+                linear_to_srgb(c)
+            } else {
+                c
+            })
+        };
+
+        let mut data = Vec::with_capacity(self.sums.len() / slots * channels);
+        for sums in self.sums.chunks_exact(slots) {
+            let (color, alpha) = sums.split_at(slots - (channels - color_channels));
+            match self.color_space {
+                ColorSpace::Rgb => data.extend(color.iter().map(|sum| encode(sum / n))),
+                ColorSpace::Hsv => {
+                    let h = color[1].atan2(color[0]).to_degrees().rem_euclid(360.0);
+                    data.extend(hsv_to_rgb([h, color[2] / n, color[3] / n]).map(encode));
+                }
+                ColorSpace::Oklab => {
+                    // as in `blend`, colors can fall just outside the RGB cube
+                    let rgb = oklab_to_linear([color[0] / n, color[1] / n, color[2] / n])
+                        .map(|c| S::from_normalized(linear_to_srgb(clip::<S>(c))));
+                    data.extend_from_slice(&rgb[..color_channels]);
+                }
+            }
+            data.extend(alpha.iter().map(|sum| S::from_normalized(sum / n)));
+        }
+        data
+    }
+}
+
+/// The ways the frames between keyframes can be calculated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Interpolation {
+    /// Blend straight from one keyframe to the next
+    Linear,
+    /// Follow a Catmull-Rom spline through each keyframe and its neighbours,
+    /// avoiding sudden changes of direction at the keyframes
+    CatmullRom,
+    /// Estimate how the content moves between keyframes and slide it along that motion
+    /// while blending, which suits photographs of the same scene
+    Morph,
+}
+
+impl Interpolation {
+    pub const VARIANTS: [&'static str; 3] = ["linear", "catmull-rom", "morph"];
+}
+
+impl FromStr for Interpolation {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "linear" => Ok(Interpolation::Linear),
+            "catmull-rom" => Ok(Interpolation::CatmullRom),
+            "morph" => Ok(Interpolation::Morph),
+            _ => Err(anyhow!("Unknown interpolation {:?}.", s)),
+        }
+    }
+}
+
+/// The ways the colors of two images can be combined on the way from one to the other
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    /// A weighted average of the two images
+    Mix,
+    /// Darken the transition by multiplying the images together
+    Multiply,
+    /// Brighten the transition by multiplying the inverted images, like overlaid projections
+    Screen,
+    /// Brighten the transition by adding the images together, like a double exposure
+    Add,
+}
+
+impl BlendMode {
+    pub const VARIANTS: [&'static str; 4] = ["mix", "multiply", "screen", "add"];
+
+    /// Blends two channel values `a` and `b` in [0.0..1.0] `mu` of the way from `a` to `b`.
+    ///
+    /// Each mode follows a quadratic curve from `a` through the images composited together
+    /// to `b`, weighting the composite most in the middle of the transition. The result is
+    /// left for the sample type to clamp, so float images keep values past 1.0. Compositing
+    /// with `Mix` gives the average, for which the curve is just the straight line
+    /// `(1 - mu) * a + mu * b`.
+    pub fn apply(self, mu: f64, a: f64, b: f64) -> f64 {
+        let composite = match self {
+            BlendMode::Mix => (a + b) / 2.0,
+            BlendMode::Multiply => a * b,
+            BlendMode::Screen => 1.0 - (1.0 - a) * (1.0 - b),
+            BlendMode::Add => a + b,
+        };
+        let (t1, t2) = (1.0 - mu, mu);
+        t1 * t1 * a + 2.0 * t1 * t2 * composite + t2 * t2 * b
+    }
+}
+
+impl FromStr for BlendMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "mix" => Ok(BlendMode::Mix),
+            "multiply" => Ok(BlendMode::Multiply),
+            "screen" => Ok(BlendMode::Screen),
+            "add" => Ok(BlendMode::Add),
+            _ => Err(anyhow!("Unknown blend mode {:?}.", s)),
+        }
+    }
+}
+
+/// The color spaces which pixels can be converted into for blending
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColorSpace {
+    /// Blend each of the red, green and blue channels independently
+    Rgb,
+    /// Blend the hue along the shortest path around the color wheel,
+    /// and the saturation and value independently
+    Hsv,
+    /// Blend the lightness and the two color axes of the perceptually uniform OKLab
+    /// color space, always from linear light
+    Oklab,
+}
+
+impl ColorSpace {
+    pub const VARIANTS: [&'static str; 3] = ["rgb", "hsv", "oklab"];
+}
+
+impl FromStr for ColorSpace {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "rgb" => Ok(ColorSpace::Rgb),
+            "hsv" => Ok(ColorSpace::Hsv),
+            "oklab" => Ok(ColorSpace::Oklab),
+            _ => Err(anyhow!("Unknown color space {:?}.", s)),
+        }
+    }
+}
+
+/// This func takes 2 channel values and a float in [0.0..1.0]
+/// which represents how far to interpolate between the two,
+/// working in the sample type's `Accumulator`.
+///
+/// The weight is used as it is, never wrapped back into range, so `0.0` and `1.0` give
+/// exactly `c1` and `c2`. Floats outside of that range extrapolate past `c1` or `c2`
+/// along the same line, as overshooting easing curves rely on, with the result clamped
+/// to the values the sample type can hold. Callers which need the blend to stay between
+/// the two values clamp `mu` themselves.
+pub fn smooth<S: Sample>(mu: f64, c1: S, c2: S) -> S {
+    smooth_in::<S::Accumulator, S>(mu, c1, c2)
+}
+
+/// This func is the same as `smooth` except the blend is done in `A`
+/// whatever the sample type, e.g. `smooth_in::<f64, u8>` for the full precision of `f64`
+pub fn smooth_in<A: Accumulator, S: Sample>(mu: f64, c1: S, c2: S) -> S {
+    let t2 = A::from_f64(mu);
+    let t1 = A::ONE - t2;
+
+    // round to the nearest value rather than truncating, which would bias every frame darker,
+    // and clamp so extrapolating or floating point error can't wrap around
+    let (c1, c2) = (A::from_f64(c1.to_f64()), A::from_f64(c2.to_f64()));
+    S::from_f64((c1 * t1 + c2 * t2).to_f64())
+}
+
+/// This func is the same as `smooth` except the channel values are decoded
+/// from sRGB to linear light before blending and encoded back afterwards
+fn smooth_linear_light<S: Sample>(mu: f64, c1: S, c2: S) -> S {
+    let t2 = mu;
+    let t1 = 1.0 - t2;
+
+    let linear = srgb_to_linear(c1.to_normalized()) * t1 + srgb_to_linear(c2.to_normalized()) * t2;
+    S::from_normalized(linear_to_srgb(linear))
+}
+
+/// Decodes an sRGB value in [0.0..1.0] into linear light in [0.0..1.0]
+/// using the piecewise sRGB transfer function
+fn srgb_to_linear(c: f64) -> f64 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Encodes linear light in [0.0..1.0] back into an sRGB value in [0.0..1.0],
+/// this is the inverse of `srgb_to_linear`
+fn linear_to_srgb(l: f64) -> f64 {
+    if l <= 0.0031308 {
+        l * 12.92
+    } else {
+        1.055 * l.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// This func takes 2 colors as hue in degrees, saturation and value
+/// and interpolates between them taking the shortest path around the color wheel,
+/// so 350 -> 10 goes forward through 0 rather than backwards through 180.
+///
+/// The saturation and value are clamped into [0.0..1.0] when `bounded`, float images
+/// are left unclamped so their values past 1.0 are kept.
+fn smooth_hsv(mu: f64, c1: [f64; 3], c2: [f64; 3], bounded: bool) -> [f64; 3] {
+    let t2 = mu;
+    let t1 = 1.0 - t2;
+
+    // a color without saturation has no meaningful hue, so borrow the other one's
+    // to avoid sweeping through unrelated hues when fading from grey
+    let (h1, h2) = match (c1[1] == 0.0, c2[1] == 0.0) {
+        (true, false) => (c2[0], c2[0]),
+        (false, true) => (c1[0], c1[0]),
+        _ => (c1[0], c2[0]),
+    };
+
+    let mut delta = h2 - h1;
+    if delta > 180.0 {
+        delta -= 360.0;
+    } else if delta < -180.0 {
+        delta += 360.0;
+    }
+
+    // overshooting curves would otherwise take these out of range, flipping the hue
+    // around when the saturation went negative
+    let clamp = |c: f64| if bounded { c.clamp(0.0, 1.0) } else { c };
+    [
+        (h1 + delta * t2).rem_euclid(360.0),
+        clamp(c1[1] * t1 + c2[1] * t2),
+        clamp(c1[2] * t1 + c2[2] * t2),
+    ]
+}
+
+/// Converts a color with channels in [0.0..1.0] into hue in degrees, saturation and value
+fn rgb_to_hsv([r, g, b]: [f64; 3]) -> [f64; 3] {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let chroma = max - min;
+
+    let hue = if chroma == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / chroma).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / chroma + 2.0)
+    } else {
+        60.0 * ((r - g) / chroma + 4.0)
+    };
+    let saturation = if max == 0.0 { 0.0 } else { chroma / max };
+
+    [hue, saturation, max]
+}
+
+/// Converts a color as hue in degrees, saturation and value back into channels in [0.0..1.0],
+/// this is the inverse of `rgb_to_hsv`
+fn hsv_to_rgb([h, s, v]: [f64; 3]) -> [f64; 3] {
+    let chroma = v * s;
+    let h = h / 60.0;
+    let x = chroma * (1.0 - (h.rem_euclid(2.0) - 1.0).abs());
+    let m = v - chroma;
+
+    let (r, g, b) = match h as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+
+    [r + m, g + m, b + m]
+}
+
+/// Clips a channel value in linear light into [0.0..1.0] for sample types which can't go
+/// past it, leaving float values as they are
+fn clip<S: Sample>(c: f64) -> f64 {
+    if S::BOUNDED {
+        c.clamp(0.0, 1.0)
+    } else {
+        c
+    }
+}
+
+/// The point `mu` of the way from `a` to `b`
+fn lerp(mu: f64, a: f64, b: f64) -> f64 {
+    a * (1.0 - mu) + b * mu
+}
+
+/// Converts a color in linear light with channels in [0.0..1.0] into OKLab lightness,
+/// green-red and blue-yellow, using Björn Ottosson's matrices
+fn linear_to_oklab([r, g, b]: [f64; 3]) -> [f64; 3] {
+    let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+
+    [
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    ]
+}
+
+/// Converts an OKLab color back into linear light, this is the inverse of `linear_to_oklab`
+/// and can give channels outside of [0.0..1.0] for colors outside the sRGB gamut
+fn oklab_to_linear([lightness, a, b]: [f64; 3]) -> [f64; 3] {
+    let l = (lightness + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+    let m = (lightness - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+    let s = (lightness - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+
+    [
+        4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+        -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+        -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+    ]
+}
@@ -0,0 +1,248 @@
+use std::{convert::TryInto, io::Read, path::Path};
+
+use anyhow::{anyhow, ensure, Context, Result};
+
+use crate::{
+    error::{open_image, InterpolError},
+    image::sample_count,
+    BitDepth, ColorType, Image, ImageInfo,
+};
+
+/// The bytes every BMP file starts with
+const SIGNATURE: [u8; 2] = *b"BM";
+
+/// The compression methods of uncompressed pixels, the second giving the position of each
+/// channel within a pixel as a bit mask
+const BI_RGB: u32 = 0;
+const BI_BITFIELDS: u32 = 3;
+const BI_ALPHABITFIELDS: u32 = 6;
+
+/// Checks the first bytes of the file at `path` to see whether it is a BMP
+pub(crate) fn is_bmp(path: &Path) -> Result<bool> {
+    let mut file = open_image(path)?;
+    let mut signature = [0; 2];
+    match file.read_exact(&mut signature) {
+        Ok(()) => Ok(signature == SIGNATURE),
+        // files too short to be a BMP are left for the PNG decoder to report
+        Err(_) => Ok(false),
+    }
+}
+
+/// The layout of a BMP's pixels, read from its headers
+struct Header {
+    width: u32,
+    height: u32,
+    /// Whether the first row stored is the top of the image, rather than the bottom
+    top_down: bool,
+    bits_per_pixel: u16,
+    /// The offset of the first row from the start of the file
+    pixels_start: usize,
+    pixels: Pixels,
+}
+
+/// How the colors of the pixels are stored
+enum Pixels {
+    /// Indices into a table of colors
+    Palette(Vec<[u8; 3]>),
+    /// Each channel at the position of its mask, in red, green, blue and alpha order,
+    /// with no alpha when its mask is zero
+    Masks([u32; 4]),
+}
+
+impl Header {
+    fn read(bytes: &[u8], path: &Path) -> Result<Self> {
+        let truncated = || InterpolError::Truncated {
+            path: path.to_owned(),
+            ends: "part way through its header".to_string(),
+        };
+        let u16_at = |at: usize| -> Result<u16> {
+            let bytes = bytes.get(at..at + 2).ok_or_else(truncated)?;
+            Ok(u16::from_le_bytes(bytes.try_into()?))
+        };
+        let u32_at = |at: usize| -> Result<u32> {
+            let bytes = bytes.get(at..at + 4).ok_or_else(truncated)?;
+            Ok(u32::from_le_bytes(bytes.try_into()?))
+        };
+
+        let pixels_start = u32_at(10)? as usize;
+        let header_size = u32_at(14)? as usize;
+        // the original OS/2 header only has 16 bit dimensions and no compression
+        let (width, height, bits_per_pixel, compression, colors_used) = if header_size == 12 {
+            let (width, height) = (u16_at(18)? as i32, u16_at(20)? as i32);
+            (width, height, u16_at(24)?, BI_RGB, 0)
+        } else {
+            ensure!(
+                header_size >= 40,
+                "Unsupported header size {} in {:?}, it isn't a BMP header interpol knows.",
+                header_size,
+                path
+            );
+            let (width, height) = (u32_at(18)? as i32, u32_at(22)? as i32);
+            (width, height, u16_at(28)?, u32_at(30)?, u32_at(46)?)
+        };
+        ensure!(
+            width > 0 && height != 0,
+            "{:?} has no pixels, it is {}x{}.",
+            path,
+            width,
+            height
+        );
+
+        let colors_start = 14 + header_size;
+        let pixels = match (bits_per_pixel, compression) {
+            (1 | 4 | 8, BI_RGB) => {
+                // the OS/2 header has 3 bytes for each color, the others 4
+                let entry_size = if header_size == 12 { 3 } else { 4 };
+                // some files leave out the colors which aren't used without saying so,
+                // in which case the table stops where the pixels start
+                let colors = match colors_used as usize {
+                    0 => 1 << bits_per_pixel,
+                    n => n.min(1 << bits_per_pixel),
+                }
+                .min(pixels_start.saturating_sub(colors_start) / entry_size);
+                let table = bytes
+                    .get(colors_start..colors_start + colors * entry_size)
+                    .ok_or_else(truncated)?;
+                let palette = table
+                    .chunks_exact(entry_size)
+                    .map(|color| [color[2], color[1], color[0]])
+                    .collect();
+                Pixels::Palette(palette)
+            }
+            (16, BI_RGB) => Pixels::Masks([0x7C00, 0x03E0, 0x001F, 0]),
+            (24 | 32, BI_RGB) => Pixels::Masks([0xFF_0000, 0x00_FF00, 0x00_00FF, 0]),
+            (16 | 32, BI_BITFIELDS | BI_ALPHABITFIELDS) => {
+                // the masks come straight after the 40 byte header, either as part of
+                // a newer header or after it, with the alpha mask only in the newer ones
+                let alpha = if header_size >= 56 || compression == BI_ALPHABITFIELDS {
+                    u32_at(66)?
+                } else {
+                    0
+                };
+                Pixels::Masks([u32_at(54)?, u32_at(58)?, u32_at(62)?, alpha])
+            }
+            (bits, BI_RGB | BI_BITFIELDS | BI_ALPHABITFIELDS) => {
+                return Err(anyhow!(
+                    "Unsupported bit depth {} in {:?}, only 1, 4, 8, 16, 24 and 32 bit BMPs are supported.",
+                    bits,
+                    path
+                ))
+            }
+            (_, compression) => {
+                return Err(anyhow!(
+                    "Unsupported compression {} in {:?}, only uncompressed BMPs are supported.",
+                    compression_name(compression),
+                    path
+                ))
+            }
+        };
+
+        Ok(Self {
+            width: width as u32,
+            height: height.unsigned_abs(),
+            top_down: height < 0,
+            bits_per_pixel,
+            pixels_start,
+            pixels,
+        })
+    }
+
+    fn info(&self) -> ImageInfo {
+        let color_type = match self.pixels {
+            Pixels::Masks([_, _, _, alpha]) if alpha != 0 => ColorType::Rgba,
+            _ => ColorType::Rgb,
+        };
+        ImageInfo {
+            width: self.width,
+            height: self.height,
+            color_type,
+            bit_depth: BitDepth::Eight,
+        }
+    }
+}
+
+/// The name of a BMP compression method, for reporting the ones which aren't supported
+fn compression_name(compression: u32) -> String {
+    match compression {
+        1 => "RLE8".to_string(),
+        2 => "RLE4".to_string(),
+        4 => "JPEG".to_string(),
+        5 => "PNG".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Reads just the headers of the BMP at `path`
+pub(crate) fn read_info(path: &Path) -> Result<ImageInfo> {
+    let mut header = Vec::new();
+    open_image(path)?
+        // enough for the largest header and a full palette of 256 colors
+        .take(14 + 124 + 256 * 4)
+        .read_to_end(&mut header)
+        .with_context(|| format!("Failed to read the header of {:?}", path))?;
+    Ok(Header::read(&header, path)?.info())
+}
+
+/// Decodes the BMP at `path` into an RGB image, or RGBA when it has an alpha channel
+pub(crate) fn decode(path: &Path) -> Result<Image> {
+    let mut bytes = Vec::new();
+    open_image(path)?
+        .read_to_end(&mut bytes)
+        .with_context(|| format!("Failed to read image file {:?}", path))?;
+    let header = Header::read(&bytes, path)?;
+    let info = header.info();
+    let channels = info.color_type.channels();
+
+    // every row is padded to a multiple of 4 bytes
+    let row_size = (header.width as usize * header.bits_per_pixel as usize).div_ceil(32) * 4;
+    let rows_size = row_size
+        .checked_mul(header.height as usize)
+        .ok_or_else(|| anyhow!("{:?} is too large to decode.", path))?;
+    let rows = bytes
+        .get(header.pixels_start..)
+        .and_then(|pixels| pixels.get(..rows_size))
+        .ok_or_else(|| InterpolError::Truncated {
+            path: path.to_owned(),
+            ends: "part way through its pixels".to_string(),
+        })?;
+
+    let mut data = Vec::with_capacity(sample_count(info.width, info.height, info.color_type)?);
+    for y in 0..header.height as usize {
+        let y = if header.top_down {
+            y
+        } else {
+            header.height as usize - 1 - y
+        };
+        let row = &rows[y * row_size..(y + 1) * row_size];
+        for x in 0..header.width as usize {
+            match &header.pixels {
+                Pixels::Palette(palette) => {
+                    let bits = header.bits_per_pixel as usize;
+                    let (byte, shift) = (x * bits / 8, 8 - bits - x * bits % 8);
+                    let index = (row[byte] >> shift) as usize & ((1 << bits) - 1);
+                    // indices past the end of the palette are treated as black
+                    data.extend_from_slice(palette.get(index).unwrap_or(&[0; 3]));
+                }
+                Pixels::Masks(masks) => {
+                    let bytes = header.bits_per_pixel as usize / 8;
+                    let mut pixel = [0; 4];
+                    pixel[..bytes].copy_from_slice(&row[x * bytes..(x + 1) * bytes]);
+                    let pixel = u32::from_le_bytes(pixel);
+                    data.extend(masks[..channels].iter().map(|&mask| channel(pixel, mask)));
+                }
+            }
+        }
+    }
+
+    Image::new_from_parts(data, info.color_type, info.width, info.height)
+}
+
+/// The value of the channel at `mask` within `pixel`, scaled to 8 bits
+fn channel(pixel: u32, mask: u32) -> u8 {
+    if mask == 0 {
+        return 0;
+    }
+    let value = (pixel & mask) >> mask.trailing_zeros();
+    let max = mask >> mask.trailing_zeros();
+    ((value as u64 * 255 + max as u64 / 2) / max as u64) as u8
+}
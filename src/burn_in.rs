@@ -0,0 +1,190 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+
+use crate::{Image, Sample, Samples};
+
+/// The characters of a 5x7 pixel font, each row's pixels in the low five bits
+/// with the leftmost pixel in the highest of them
+const GLYPHS: [(char, [u8; 7]); 11] = [
+    ('0', [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E]),
+    ('1', [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E]),
+    ('2', [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F]),
+    ('3', [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E]),
+    ('4', [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02]),
+    ('5', [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E]),
+    ('6', [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E]),
+    ('7', [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08]),
+    ('8', [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E]),
+    ('9', [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C]),
+    (':', [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00]),
+];
+const GLYPH_WIDTH: u32 = 5;
+const GLYPH_HEIGHT: u32 = 7;
+
+/// The label stamped on to each frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BurnIn {
+    None,
+    /// The frame's index in the sequence
+    Frame,
+    /// The frame's time into the sequence as hours, minutes, seconds and frames
+    Timecode,
+}
+
+impl BurnIn {
+    pub const VARIANTS: [&'static str; 3] = ["none", "frame", "timecode"];
+
+    /// The label for frame `n` of a sequence played at `fps` frames a second
+    pub fn label(self, n: usize, fps: f64) -> Option<String> {
+        match self {
+            BurnIn::None => None,
+            BurnIn::Frame => Some(n.to_string()),
+            BurnIn::Timecode => {
+                // timecodes count whole frames, so fractional rates count at the nearest
+                let fps = (fps.round() as usize).max(1);
+                let (seconds, frames) = (n / fps, n % fps);
+                Some(format!(
+                    "{:02}:{:02}:{:02}:{:02}",
+                    seconds / 3600,
+                    seconds / 60 % 60,
+                    seconds % 60,
+                    frames
+                ))
+            }
+        }
+    }
+}
+
+impl FromStr for BurnIn {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "none" => Ok(BurnIn::None),
+            "frame" => Ok(BurnIn::Frame),
+            "timecode" => Ok(BurnIn::Timecode),
+            _ => Err(anyhow!("Unknown burn-in {:?}.", s)),
+        }
+    }
+}
+
+/// The corners of an image text can be drawn in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Corner {
+    pub const VARIANTS: [&'static str; 4] =
+        ["top-left", "top-right", "bottom-left", "bottom-right"];
+}
+
+impl FromStr for Corner {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "top-left" => Ok(Corner::TopLeft),
+            "top-right" => Ok(Corner::TopRight),
+            "bottom-left" => Ok(Corner::BottomLeft),
+            "bottom-right" => Ok(Corner::BottomRight),
+            _ => Err(anyhow!("Unknown corner {:?}.", s)),
+        }
+    }
+}
+
+impl Image {
+    /// Writes `text` in white on a black box in `corner`, each pixel of the font taking up
+    /// `scale` by `scale` pixels of the image. The box covers what is under it by
+    /// `opacity`, from `0.0` for not at all to `1.0` for completely.
+    ///
+    /// Only digits and colons can be drawn, any other characters are left as gaps.
+    pub(crate) fn draw_text(&mut self, text: &str, scale: u32, corner: Corner, opacity: f64) {
+        let glyphs: Vec<_> = text
+            .chars()
+            .map(|c| GLYPHS.iter().find(|(g, _)| *g == c).map(|(_, rows)| rows))
+            .collect();
+
+        // a pixel of padding around the characters, and between each of them
+        let box_width = (glyphs.len() as u32 * (GLYPH_WIDTH + 1) + 1) * scale;
+        let box_height = (GLYPH_HEIGHT + 2) * scale;
+        let lit = |x: u32, y: u32| {
+            let (x, y) = (x / scale, y / scale);
+            if x == 0 || y == 0 || y > GLYPH_HEIGHT {
+                return false;
+            }
+            let (glyph, column) = ((x - 1) / (GLYPH_WIDTH + 1), (x - 1) % (GLYPH_WIDTH + 1));
+            glyphs
+                .get(glyph as usize)
+                .copied()
+                .flatten()
+                .is_some_and(|rows| {
+                    column < GLYPH_WIDTH && rows[y as usize - 1] & (0x10 >> column) != 0
+                })
+        };
+
+        let (width, height) = (box_width.min(self.width), box_height.min(self.height));
+        let left = match corner {
+            Corner::TopLeft | Corner::BottomLeft => 0,
+            Corner::TopRight | Corner::BottomRight => self.width - width,
+        };
+        let top = match corner {
+            Corner::TopLeft | Corner::TopRight => 0,
+            Corner::BottomLeft | Corner::BottomRight => self.height - height,
+        };
+        let area = Area {
+            image_width: self.width as usize,
+            channels: self.color_type.channels(),
+            color_channels: self.color_type.color_channels(),
+            left,
+            top,
+            width,
+            height,
+        };
+        match &mut self.data {
+            Samples::Eight(data) => draw(data, &area, opacity, lit),
+            Samples::Sixteen(data) => draw(data, &area, opacity, lit),
+            Samples::Float(data) => draw(data, &area, opacity, lit),
+        }
+    }
+}
+
+/// The part of an image being drawn over, and the layout of its pixels
+struct Area {
+    image_width: usize,
+    channels: usize,
+    color_channels: usize,
+    left: u32,
+    top: u32,
+    width: u32,
+    height: u32,
+}
+
+/// Draws over `area` of an image, white where `lit` and elsewhere darkening the image
+/// towards black by `opacity`, `lit` taking positions relative to the top left of the area.
+/// Any alpha is raised as though the box was composited over the image.
+fn draw<S: Sample>(data: &mut [S], area: &Area, opacity: f64, lit: impl Fn(u32, u32) -> bool) {
+    for y in 0..area.height {
+        for x in 0..area.width {
+            let (image_x, image_y) = ((area.left + x) as usize, (area.top + y) as usize);
+            let start = (image_y * area.image_width + image_x) * area.channels;
+            let pixel = &mut data[start..start + area.channels];
+            let (color, alpha) = pixel.split_at_mut(area.color_channels);
+            if lit(x, y) {
+                color.fill(S::from_f64(S::MAX));
+                alpha.fill(S::from_f64(S::MAX));
+            } else {
+                for c in color {
+                    *c = S::from_f64(c.to_f64() * (1.0 - opacity));
+                }
+                for a in alpha {
+                    *a = S::from_f64(a.to_f64() + (S::MAX - a.to_f64()) * opacity);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    convert::TryInto,
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use anyhow::{anyhow, ensure, Context, Result};
+use log::{debug, warn};
+
+use crate::{BitDepth, ColorProfile, ColorType, Image, Samples};
+
+/// The bytes every cache file starts with, changed whenever the layout below changes
+const MAGIC: [u8; 8] = *b"IPCACHE1";
+
+/// A directory of decoded images, so images used run after run only have to be decoded once.
+///
+/// Each image is stored in a file of its own named after a hash of its path, holding its
+/// raw channel values after a header which records the size and modification time of the
+/// file it was decoded from. An entry is only used while the file still has exactly that
+/// size and modification time, otherwise the image is decoded again and the entry replaced.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct DecodeCache {
+    dir: PathBuf,
+}
+
+/// What identifies the version of a file an entry was decoded from
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Key {
+    path: PathBuf,
+    size: u64,
+    modified: (u64, u32),
+}
+
+impl DecodeCache {
+    pub(crate) fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Decodes the image at `path` with `decode`, unless a current copy of it is in the cache,
+    /// storing what it decodes for next time.
+    ///
+    /// Problems with the cache itself are only warned about, as the image can always be
+    /// decoded directly instead.
+    pub(crate) fn load(
+        &self,
+        path: &Path,
+        decode: impl FnOnce() -> Result<Image>,
+    ) -> Result<Image> {
+        let key = match Key::for_file(path) {
+            Ok(key) => key,
+            Err(e) => {
+                warn!("Not caching {:?}: {:#}", path, e);
+                return decode();
+            }
+        };
+        let entry = self.entry_path(&key);
+
+        match fs::read(&entry) {
+            Ok(contents) => match read_entry(&contents, &key) {
+                Ok(Some(image)) => {
+                    debug!("Loaded {:?} from the cache at {:?}", path, entry);
+                    // the color profile is read from the image's own header, which is quick
+                    return Ok(image.with_color_profile(ColorProfile::from_path(path)?));
+                }
+                Ok(None) => debug!("The cached copy of {:?} is out of date", path),
+                Err(e) => warn!("Ignoring the broken cache entry {:?}: {:#}", entry, e),
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("Failed to read the cache entry {:?}: {}", entry, e),
+        }
+
+        let image = decode()?;
+        if let Err(e) = self.store(&entry, &key, &image) {
+            warn!("Failed to cache {:?}: {:#}", path, e);
+        }
+        Ok(image)
+    }
+
+    fn entry_path(&self, key: &Key) -> PathBuf {
+        let mut hasher = DefaultHasher::new();
+        key.path.hash(&mut hasher);
+        self.dir.join(format!("{:016x}.raw", hasher.finish()))
+    }
+
+    fn store(&self, entry: &Path, key: &Key, image: &Image) -> Result<()> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create the cache directory {:?}", self.dir))?;
+
+        let path = key.path.to_string_lossy();
+        let data = image.data.to_be_bytes();
+        let mut contents = Vec::with_capacity(MAGIC.len() + 64 + path.len() + data.len());
+        contents.extend_from_slice(&MAGIC);
+        contents.extend_from_slice(&(path.len() as u64).to_be_bytes());
+        contents.extend_from_slice(path.as_bytes());
+        contents.extend_from_slice(&key.size.to_be_bytes());
+        contents.extend_from_slice(&key.modified.0.to_be_bytes());
+        contents.extend_from_slice(&key.modified.1.to_be_bytes());
+        contents.extend_from_slice(&image.width.to_be_bytes());
+        contents.extend_from_slice(&image.height.to_be_bytes());
+        contents.push(color_type_code(image.color_type));
+        contents.push(image.data.bit_depth().bits() as u8);
+        contents.extend_from_slice(&data);
+
+        // written alongside and then moved into place, so a run reading the entry at the same
+        // time never sees half of it
+        let partial = entry.with_extension(format!("partial-{}", std::process::id()));
+        fs::write(&partial, &contents)
+            .with_context(|| format!("Failed to write the cache entry {:?}", partial))?;
+        fs::rename(&partial, entry)
+            .with_context(|| format!("Failed to move the cache entry into place at {:?}", entry))
+    }
+}
+
+impl Key {
+    fn for_file(path: &Path) -> Result<Self> {
+        let metadata = fs::metadata(path)
+            .with_context(|| format!("Failed to read the metadata of {:?}", path))?;
+        let modified = metadata
+            .modified()
+            .with_context(|| format!("Failed to read the modification time of {:?}", path))?
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Ok(Self {
+            path: fs::canonicalize(path)
+                .with_context(|| format!("Failed to find the full path of {:?}", path))?,
+            size: metadata.len(),
+            modified: (modified.as_secs(), modified.subsec_nanos()),
+        })
+    }
+}
+
+/// Decodes a cache entry, giving `None` when it was decoded from a different version
+/// of the file than the one `key` describes
+fn read_entry(contents: &[u8], key: &Key) -> Result<Option<Image>> {
+    let mut reader = Reader(contents);
+    ensure!(reader.take(MAGIC.len())? == MAGIC, "it isn't a cache file");
+
+    let path_len = reader.u64()? as usize;
+    let path = reader.take(path_len)?;
+    let size = reader.u64()?;
+    let modified = (reader.u64()?, reader.u32()?);
+    if path != key.path.to_string_lossy().as_bytes() || size != key.size || modified != key.modified
+    {
+        return Ok(None);
+    }
+
+    let (width, height) = (reader.u32()?, reader.u32()?);
+    let color_type = match reader.take(1)?[0] {
+        0 => ColorType::Gray,
+        1 => ColorType::GrayAlpha,
+        2 => ColorType::Rgb,
+        3 => ColorType::Rgba,
+        other => return Err(anyhow!("unknown color type {}", other)),
+    };
+    let bit_depth = match reader.take(1)?[0] {
+        8 => BitDepth::Eight,
+        16 => BitDepth::Sixteen,
+        32 => BitDepth::Float,
+        other => return Err(anyhow!("unknown bit depth {}", other)),
+    };
+    let data = Samples::from_be_bytes(reader.0.to_vec(), bit_depth);
+    Image::new_from_parts(data, color_type, width, height).map(Some)
+}
+
+fn color_type_code(color_type: ColorType) -> u8 {
+    match color_type {
+        ColorType::Gray => 0,
+        ColorType::GrayAlpha => 1,
+        ColorType::Rgb => 2,
+        ColorType::Rgba => 3,
+    }
+}
+
+/// Reads big-endian values from the front of a cache entry
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        ensure!(self.0.len() >= n, "it is truncated");
+        let (taken, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into()?))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+}
@@ -0,0 +1,47 @@
+use crate::{ColorType, Image, Sample, Samples};
+
+impl Image {
+    /// Places the image in front of a solid `background` color with source-over
+    /// compositing, giving an image without alpha which looks as this one would on top
+    /// of that color. Partly transparent pixels are mixed with the background by their
+    /// alpha, and images without alpha are returned unchanged.
+    ///
+    /// Grayscale images are composited over the brightness of the background.
+    pub fn composite_over(self, background: [u8; 3]) -> Image {
+        let color_type = match self.color_type {
+            ColorType::GrayAlpha => ColorType::Gray,
+            ColorType::Rgba => ColorType::Rgb,
+            ColorType::Gray | ColorType::Rgb => return self,
+        };
+
+        let [r, g, b] = background.map(|c| c as f64 / u8::MAX as f64);
+        let background = match color_type {
+            ColorType::Gray => vec![0.299 * r + 0.587 * g + 0.114 * b],
+            _ => vec![r, g, b],
+        };
+        let data = match &self.data {
+            Samples::Eight(data) => Samples::Eight(composite(data, &background)),
+            Samples::Sixteen(data) => Samples::Sixteen(composite(data, &background)),
+            Samples::Float(data) => Samples::Float(composite(data, &background)),
+        };
+
+        Image {
+            data,
+            color_type,
+            ..self
+        }
+    }
+}
+
+/// Composites pixels made up of the color channels of `background` followed by alpha
+fn composite<S: Sample>(data: &[S], background: &[f64]) -> Vec<S> {
+    data.chunks_exact(background.len() + 1)
+        .flat_map(|px| {
+            let (color, alpha) = px.split_at(background.len());
+            let alpha = alpha[0].to_normalized();
+            color.iter().zip(background).map(move |(c, bg)| {
+                S::from_normalized(c.to_normalized() * alpha + bg * (1.0 - alpha))
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,49 @@
+use anyhow::{ensure, Result};
+
+use crate::{burn_in::Corner, Filter, Image, ImageInfo, SpriteSheet};
+
+/// A grid of shrunken frames for reviewing a sequence at a glance,
+/// each labelled with its index in the sequence in its top left corner
+#[derive(Debug, Clone)]
+pub struct ContactSheet {
+    sheet: SpriteSheet,
+    cell_width: u32,
+    cell_height: u32,
+}
+
+impl ContactSheet {
+    /// Creates an empty sheet with space for `cells` frames like `info` in rows of `columns`,
+    /// each shrunk or grown to `cell_width` pixels wide keeping its aspect ratio
+    pub fn new(info: ImageInfo, cells: usize, columns: usize, cell_width: u32) -> Result<Self> {
+        ensure!(
+            cell_width > 0,
+            "The cells of a contact sheet must be at least 1 pixel wide."
+        );
+        let cell_height =
+            ((cell_width as f64 * info.height as f64 / info.width as f64).round() as u32).max(1);
+        let cell = ImageInfo {
+            width: cell_width,
+            height: cell_height,
+            ..info
+        };
+        Ok(Self {
+            sheet: SpriteSheet::new(cell, cells, columns)?,
+            cell_width,
+            cell_height,
+        })
+    }
+
+    /// Shrinks `frame` into the cell at index `n` and labels it as frame `index`
+    pub fn insert(&mut self, n: usize, index: usize, frame: &Image) -> Result<()> {
+        let mut cell = frame.resize(self.cell_width, self.cell_height, Filter::Bilinear);
+        // the label takes up around a quarter of the height of the smallest cells
+        let scale = (self.cell_height / 40).max(1);
+        cell.draw_text(&index.to_string(), scale, Corner::TopLeft, 1.0);
+        self.sheet.insert(n, &cell)
+    }
+
+    /// The finished sheet as a single image
+    pub fn into_image(self) -> Image {
+        self.sheet.into_image()
+    }
+}
@@ -0,0 +1,198 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+
+use crate::{ColorType, Image, Sample, Samples};
+
+/// The ways the blended channel values can be reduced to 8 bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dither {
+    /// Round every value to the nearest 8 bit value, which bands on slow gradients
+    None,
+    /// Add a fixed 8x8 Bayer threshold pattern before rounding down, the pattern stays in
+    /// place from frame to frame so it's stable when animated
+    Ordered,
+    /// Diffuse the rounding error of each pixel onto its unvisited neighbours, this is
+    /// the most accurate for a single frame but the pattern shifts between frames
+    FloydSteinberg,
+    /// Add a pseudo-random threshold before rounding down, which leaves no regular pattern.
+    /// The noise is generated from a seed, so it's the same on every frame and every run
+    /// with the same seed
+    Noise,
+}
+
+impl Dither {
+    pub const VARIANTS: [&'static str; 4] = ["none", "ordered", "floyd-steinberg", "noise"];
+}
+
+impl FromStr for Dither {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "none" => Ok(Dither::None),
+            "ordered" => Ok(Dither::Ordered),
+            "floyd-steinberg" => Ok(Dither::FloydSteinberg),
+            "noise" => Ok(Dither::Noise),
+            _ => Err(anyhow!("Unknown dither {:?}.", s)),
+        }
+    }
+}
+
+/// The 8x8 Bayer index matrix, each entry is its threshold out of 64
+const BAYER: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+impl Image {
+    /// The image with its channels scaled up to 16 bits, this is exact and doesn't change
+    /// 16 bit images. Float values are rounded, clipping anything outside `0.0..=1.0`.
+    pub(crate) fn to_sixteen_bit(&self) -> Image {
+        let data = match &self.data {
+            Samples::Eight(data) => {
+                Samples::Sixteen(data.iter().map(|&c| c as u16 * 257).collect())
+            }
+            Samples::Sixteen(data) => Samples::Sixteen(data.clone()),
+            Samples::Float(data) => Samples::Sixteen(
+                data.iter()
+                    .map(|&c| u16::from_normalized(c as f64))
+                    .collect(),
+            ),
+        };
+        Image {
+            data,
+            ..self.clone()
+        }
+    }
+
+    /// The image with its channels as floats from 0.0 for black to 1.0 for white,
+    /// this is exact and doesn't change float images
+    pub(crate) fn to_float(&self) -> Image {
+        fn to_float<S: Sample>(data: &[S]) -> Vec<f32> {
+            data.iter().map(|c| c.to_normalized() as f32).collect()
+        }
+
+        let data = match &self.data {
+            Samples::Eight(data) => Samples::Float(to_float(data)),
+            Samples::Sixteen(data) => Samples::Float(to_float(data)),
+            Samples::Float(data) => Samples::Float(data.clone()),
+        };
+        Image {
+            data,
+            ..self.clone()
+        }
+    }
+
+    /// The image reduced to 8 bits per channel using `dither` on the color channels,
+    /// alpha is always rounded to the nearest value. Noise is generated from `seed`.
+    pub(crate) fn dither_to_eight_bit(&self, dither: Dither, seed: u64) -> Image {
+        let data = match (&self.data, dither) {
+            (Samples::Eight(_), _) | (_, Dither::None) => self.data.to_eight_bit().into_owned(),
+            // floats are dithered from 16 bits, which is far more precise than the result
+            (Samples::Float(_), dither) => {
+                return self.to_sixteen_bit().dither_to_eight_bit(dither, seed)
+            }
+            (Samples::Sixteen(data), Dither::Ordered) => {
+                ordered(data, self.width as usize, self.color_type)
+            }
+            (Samples::Sixteen(data), Dither::FloydSteinberg) => {
+                floyd_steinberg(data, self.width as usize, self.color_type)
+            }
+            (Samples::Sixteen(data), Dither::Noise) => noise(data, self.color_type, seed),
+        };
+        Image {
+            data: Samples::Eight(data),
+            ..self.clone()
+        }
+    }
+}
+
+/// Reduces 16 bit samples to 8 bits by adding the Bayer threshold for each pixel's position
+/// and rounding down
+fn ordered(data: &[u16], width: usize, color_type: ColorType) -> Vec<u8> {
+    let (channels, color_channels) = (color_type.channels(), color_type.color_channels());
+    data.iter()
+        .enumerate()
+        .map(|(i, &c)| {
+            let value = c as f64 / 257.0;
+            if i % channels >= color_channels {
+                return value.round() as u8;
+            }
+
+            let pixel = i / channels;
+            let threshold = (BAYER[pixel / width % 8][pixel % width % 8] as f64 + 0.5) / 64.0;
+            (value + threshold).floor().min(255.0) as u8
+        })
+        .collect()
+}
+
+/// Reduces 16 bit samples to 8 bits by adding a threshold in [0.0..1.0) generated from `seed`
+/// and the sample's position and rounding down, which rounds each sample up as often as
+/// it is closer to the value above
+fn noise(data: &[u16], color_type: ColorType, seed: u64) -> Vec<u8> {
+    let (channels, color_channels) = (color_type.channels(), color_type.color_channels());
+    let seed = splitmix64(seed);
+    data.iter()
+        .enumerate()
+        .map(|(i, &c)| {
+            let value = c as f64 / 257.0;
+            if i % channels >= color_channels {
+                return value.round() as u8;
+            }
+
+            // the top 53 bits fill the mantissa of a double in [0.0..1.0)
+            let threshold = (splitmix64(seed ^ i as u64) >> 11) as f64 / (1u64 << 53) as f64;
+            (value + threshold).floor().min(255.0) as u8
+        })
+        .collect()
+}
+
+/// The SplitMix64 finaliser, which scrambles `x` so that nearby inputs give unrelated outputs
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Reduces 16 bit samples to 8 bits by rounding each one and spreading the error
+/// over the following pixels with the Floyd-Steinberg weights
+fn floyd_steinberg(data: &[u16], width: usize, color_type: ColorType) -> Vec<u8> {
+    let (channels, color_channels) = (color_type.channels(), color_type.color_channels());
+    let row_len = width * channels;
+    let mut values: Vec<f64> = data.iter().map(|&c| c as f64 / 257.0).collect();
+    let mut out = Vec::with_capacity(data.len());
+
+    for i in 0..values.len() {
+        let value = values[i];
+        let rounded = value.round().clamp(0.0, 255.0);
+        out.push(rounded as u8);
+        if i % channels >= color_channels {
+            continue;
+        }
+
+        let error = value - rounded;
+        let x = (i % row_len) / channels;
+        let mut spread = |offset: usize, weight: f64| {
+            if let Some(v) = values.get_mut(offset) {
+                *v += error * weight;
+            }
+        };
+        if x + 1 < width {
+            spread(i + channels, 7.0 / 16.0);
+            spread(i + row_len + channels, 1.0 / 16.0);
+        }
+        if x > 0 {
+            spread(i + row_len - channels, 3.0 / 16.0);
+        }
+        spread(i + row_len, 5.0 / 16.0);
+    }
+    out
+}
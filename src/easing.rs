@@ -0,0 +1,170 @@
+use std::{fmt::Debug, fs, path::Path, str::FromStr};
+
+use anyhow::{anyhow, ensure, Context, Result};
+
+/// The curves which can be used to map linear progress through a transition
+/// onto the weight given to the end image
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Easing {
+    /// `f(t) = t`
+    Linear,
+    /// `f(t) = t^2`
+    EaseInQuad,
+    /// `f(t) = 1 - (1 - t)^2`
+    EaseOutQuad,
+    /// `f(t) = 4t^3` for `t < 0.5`, otherwise `1 - (-2t + 2)^3 / 2`
+    EaseInOutCubic,
+    /// `f(t) = 3t^2 - 2t^3`
+    SmoothStep,
+    /// Backs away from the start image before the transition and overshoots the end image
+    /// before settling on it, by around 10% each way
+    BackInOut,
+    /// `f(t) = 2^(-10t) sin((10t - 0.75) 2π/3) + 1`, springing past the end image and
+    /// oscillating around it as it settles
+    ElasticOut,
+}
+
+impl Easing {
+    pub const VARIANTS: [&'static str; 7] = [
+        "linear",
+        "ease-in-quad",
+        "ease-out-quad",
+        "ease-in-out-cubic",
+        "smooth-step",
+        "back-in-out",
+        "elastic-out",
+    ];
+
+    /// Maps a progress value `t` in [0.0..1.0] onto the eased progress,
+    /// every curve satisfies `f(0) = 0` and `f(1) = 1`.
+    ///
+    /// The curves which overshoot go outside of [0.0..1.0] in between, extrapolating the
+    /// blend past the images either end of the transition.
+    pub fn apply(self, t: f64) -> f64 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseInQuad => t * t,
+            Easing::EaseOutQuad => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::EaseInOutCubic => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Easing::SmoothStep => t * t * (3.0 - 2.0 * t),
+            Easing::BackInOut => {
+                // the standard amount of overshoot, scaled up for the two halves of the curve
+                let c = 1.70158 * 1.525;
+                if t < 0.5 {
+                    (2.0 * t).powi(2) * ((c + 1.0) * 2.0 * t - c) / 2.0
+                } else {
+                    ((2.0 * t - 2.0).powi(2) * ((c + 1.0) * (2.0 * t - 2.0) + c) + 2.0) / 2.0
+                }
+            }
+            Easing::ElasticOut => {
+                if t <= 0.0 {
+                    0.0
+                } else if t >= 1.0 {
+                    1.0
+                } else {
+                    let period = 2.0 * std::f64::consts::PI / 3.0;
+                    2f64.powf(-10.0 * t) * ((10.0 * t - 0.75) * period).sin() + 1.0
+                }
+            }
+        }
+    }
+}
+
+/// An easing curve given as a table of eased progress values, sampled evenly from the
+/// start of a transition to its end, with the progress between samples following
+/// the straight line joining them
+#[derive(Debug, Clone, PartialEq)]
+pub struct EasingTable {
+    values: Vec<f64>,
+}
+
+impl EasingTable {
+    /// Creates a curve from at least two `values`, the first giving the eased progress at
+    /// the start of a transition and the last at its end.
+    ///
+    /// The values have to increase, or at least never decrease, unless `allow_non_monotonic`
+    /// is set to allow curves which turn back on themselves to rewind part of a transition.
+    pub fn new(values: Vec<f64>, allow_non_monotonic: bool) -> Result<Self> {
+        ensure!(
+            values.len() >= 2,
+            "An easing curve needs at least 2 values, got {}.",
+            values.len()
+        );
+        ensure!(
+            values.iter().all(|v| v.is_finite()),
+            "The values of an easing curve must all be numbers."
+        );
+        if !allow_non_monotonic {
+            if let Some(i) = values.windows(2).position(|w| w[1] < w[0]) {
+                return Err(anyhow!(
+                    "The easing curve goes back from {} to {} at value {}, rewinding like this has to be allowed explicitly.",
+                    values[i],
+                    values[i + 1],
+                    i + 2
+                ));
+            }
+        }
+        Ok(Self { values })
+    }
+
+    /// Reads a curve from the file at `p`, which has one value on each line,
+    /// ignoring blank lines
+    pub fn from_path<P>(p: P, allow_non_monotonic: bool) -> Result<Self>
+    where
+        P: AsRef<Path> + Debug,
+    {
+        let path = p.as_ref();
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read the easing curve {:?}", path))?;
+        let values = contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                line.trim().parse().map_err(|_| {
+                    anyhow!(
+                        "Line {} of the easing curve {:?} isn't a number: {:?}",
+                        i + 1,
+                        path,
+                        line
+                    )
+                })
+            })
+            .collect::<Result<_>>()?;
+        Self::new(values, allow_non_monotonic)
+            .with_context(|| format!("Failed to load the easing curve {:?}", path))
+    }
+
+    /// Maps a progress value `t` in [0.0..1.0] onto the eased progress,
+    /// looking it up between the two nearest values in the table
+    pub fn apply(&self, t: f64) -> f64 {
+        let last = self.values.len() - 1;
+        let position = t.clamp(0.0, 1.0) * last as f64;
+        let i = (position.floor() as usize).min(last - 1);
+        let mu = position - i as f64;
+        self.values[i] * (1.0 - mu) + self.values[i + 1] * mu
+    }
+}
+
+impl FromStr for Easing {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "linear" => Ok(Easing::Linear),
+            "ease-in-quad" => Ok(Easing::EaseInQuad),
+            "ease-out-quad" => Ok(Easing::EaseOutQuad),
+            "ease-in-out-cubic" => Ok(Easing::EaseInOutCubic),
+            "smooth-step" => Ok(Easing::SmoothStep),
+            "back-in-out" => Ok(Easing::BackInOut),
+            "elastic-out" => Ok(Easing::ElasticOut),
+            _ => Err(anyhow!("Unknown easing function {:?}.", s)),
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::{error::Error, fs::File, io, path::Path, path::PathBuf};
+
+use thiserror::Error;
+
+use crate::ImageInfo;
+
+/// The kinds of failure the library reports which callers may want to tell apart.
+///
+/// This is never returned directly: errors are still [`anyhow::Error`]s, carrying one of
+/// these when it applies with any context added on top of it, see the crate docs. Find it
+/// with `error.downcast_ref::<InterpolError>()`, which looks through the context.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum InterpolError {
+    /// The image file couldn't be opened, most often because it doesn't exist
+    #[error("Failed to open image file {path:?}")]
+    Open {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    /// The image file has nothing in it
+    #[error("{path:?} is empty, there is no image in it.")]
+    Empty { path: PathBuf },
+    /// The image file ends before all of the image has been read, `ends` saying where
+    #[error("{path:?} is truncated, it ends {ends}.")]
+    Truncated { path: PathBuf, ends: String },
+    /// The decoder for the image's format couldn't make sense of it
+    #[error("{message}")]
+    Decode {
+        path: PathBuf,
+        message: String,
+        #[source]
+        source: Box<dyn Error + Send + Sync>,
+    },
+    /// An image doesn't have the same dimensions, color type and bit depth as the
+    /// first image it is to be interpolated with
+    #[error("{name} is {found}, but {expected_name} is {expected}, all of the images must have the same width, height, color type and bit depth.")]
+    Mismatch {
+        name: String,
+        found: ImageInfo,
+        expected_name: String,
+        expected: ImageInfo,
+    },
+}
+
+/// Opens the image file at `path` for reading
+pub(crate) fn open_image(path: &Path) -> Result<File, InterpolError> {
+    File::open(path).map_err(|source| InterpolError::Open {
+        path: path.to_owned(),
+        source,
+    })
+}
+
+/// Turns the error of an image decoder into an [`InterpolError::Decode`], much as
+/// [`anyhow::Context::with_context`] adds context to it
+#[cfg(any(feature = "exr", feature = "gif", feature = "jpeg", feature = "tiff"))]
+pub(crate) trait DecodeContext<T> {
+    fn decode_context<F>(self, path: &Path, message: F) -> Result<T, InterpolError>
+    where
+        F: FnOnce() -> String;
+}
+
+#[cfg(any(feature = "exr", feature = "gif", feature = "jpeg", feature = "tiff"))]
+impl<T, E> DecodeContext<T> for Result<T, E>
+where
+    E: Into<Box<dyn Error + Send + Sync>>,
+{
+    fn decode_context<F>(self, path: &Path, message: F) -> Result<T, InterpolError>
+    where
+        F: FnOnce() -> String,
+    {
+        self.map_err(|e| InterpolError::Decode {
+            path: path.to_owned(),
+            message: message(),
+            source: e.into(),
+        })
+    }
+}
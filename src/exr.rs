@@ -0,0 +1,170 @@
+use std::{io::Read, path::Path};
+
+#[cfg(not(feature = "exr"))]
+use anyhow::anyhow;
+use anyhow::Result;
+#[cfg(feature = "exr")]
+use anyhow::{ensure, Context};
+#[cfg(feature = "exr")]
+use exr::{
+    meta::MetaData,
+    prelude::{read_first_rgba_layer_from_file, write_rgb_file, write_rgba_file},
+};
+#[cfg(feature = "exr")]
+use log::debug;
+
+use crate::{error::open_image, Image, ImageInfo};
+#[cfg(feature = "exr")]
+use crate::{error::DecodeContext, BitDepth, ColorType, Samples};
+
+/// The bytes an OpenEXR file starts with
+const SIGNATURE: [u8; 4] = [0x76, 0x2f, 0x31, 0x01];
+
+/// Checks the first bytes of the file at `path` to see whether it is an OpenEXR image
+pub(crate) fn is_exr(path: &Path) -> Result<bool> {
+    let mut file = open_image(path)?;
+    let mut signature = [0; 4];
+    match file.read_exact(&mut signature) {
+        Ok(()) => Ok(signature == SIGNATURE),
+        // files too short to be an EXR are left for the PNG decoder to report
+        Err(_) => Ok(false),
+    }
+}
+
+/// Reads just the header of the EXR at `path`, describing the first layer with red, green
+/// and blue channels which is the one decoded
+#[cfg(feature = "exr")]
+pub(crate) fn read_info(path: &Path) -> Result<ImageInfo> {
+    let meta = MetaData::read_from_file(path, false).decode_context(path, || {
+        format!("Decoder failed to read information from {:?}", path)
+    })?;
+    let has = |header: &exr::meta::header::Header, name: &str| {
+        header
+            .channels
+            .list
+            .iter()
+            .any(|channel| channel.name.eq_case_insensitive(name))
+    };
+    let header = meta
+        .headers
+        .iter()
+        .find(|header| ["R", "G", "B"].iter().all(|&name| has(header, name)))
+        .with_context(|| {
+            format!(
+                "{:?} has no layer with red, green and blue channels, only RGB and RGBA EXRs are supported.",
+                path
+            )
+        })?;
+
+    Ok(ImageInfo {
+        width: header.layer_size.width() as u32,
+        height: header.layer_size.height() as u32,
+        color_type: if has(header, "A") {
+            ColorType::Rgba
+        } else {
+            ColorType::Rgb
+        },
+        bit_depth: BitDepth::Float,
+    })
+}
+
+/// Decodes the first layer with red, green and blue channels of the EXR at `path` as
+/// floats, whatever precision they are stored at, with alpha if the layer has it
+#[cfg(feature = "exr")]
+pub(crate) fn decode(path: &Path) -> Result<Image> {
+    // the pixels are filled in along with the number of channels each has
+    let create = |size: exr::math::Vec2<usize>, channels: &exr::image::RgbaChannels| {
+        let channels = if channels.3.is_some() { 4 } else { 3 };
+        (vec![0.0; size.area() * channels], size.width(), channels)
+    };
+    let set = |(data, width, channels): &mut (Vec<f32>, usize, usize),
+               position: exr::math::Vec2<usize>,
+               (r, g, b, a): (f32, f32, f32, f32)| {
+        let i = (position.y() * *width + position.x()) * *channels;
+        data[i..i + 3].copy_from_slice(&[r, g, b]);
+        if *channels == 4 {
+            data[i + 3] = a;
+        }
+    };
+    let image = read_first_rgba_layer_from_file(path, create, set)
+        .decode_context(path, || format!("Decoder failed to decode {:?}", path))?;
+
+    let size = image.layer_data.size;
+    let (data, _, channels) = image.layer_data.channel_data.pixels;
+    let color_type = if channels == 4 {
+        ColorType::Rgba
+    } else {
+        ColorType::Rgb
+    };
+    debug!("Decoded the EXR {:?}", path);
+    Image::new_from_parts(
+        Samples::Float(data),
+        color_type,
+        size.width() as u32,
+        size.height() as u32,
+    )
+}
+
+/// Saves `image` to `path` as an EXR of 32 bit floats, integer images being scaled so
+/// their brightest value is 1.0. Grayscale is saved as RGB with the gray value in every
+/// channel.
+#[cfg(feature = "exr")]
+pub(crate) fn save(image: &Image, path: &Path) -> Result<()> {
+    ensure!(
+        image.width() > 0 && image.height() > 0,
+        "An EXR must have a non-zero width and height."
+    );
+    let float = image.to_float();
+    let data = match float.data() {
+        Samples::Float(data) => data,
+        _ => unreachable!("the image was just converted to floats"),
+    };
+
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let channels = image.color_type().channels();
+    let px = |x: usize, y: usize| &data[(y * width + x) * channels..][..channels];
+    let written = match image.color_type() {
+        ColorType::Gray => write_rgb_file(path, width, height, |x, y| {
+            let px = px(x, y);
+            (px[0], px[0], px[0])
+        }),
+        ColorType::GrayAlpha => write_rgba_file(path, width, height, |x, y| {
+            let px = px(x, y);
+            (px[0], px[0], px[0], px[1])
+        }),
+        ColorType::Rgb => write_rgb_file(path, width, height, |x, y| {
+            let px = px(x, y);
+            (px[0], px[1], px[2])
+        }),
+        ColorType::Rgba => write_rgba_file(path, width, height, |x, y| {
+            let px = px(x, y);
+            (px[0], px[1], px[2], px[3])
+        }),
+    };
+    written.with_context(|| format!("Failed to save image file: {:?}", path))
+}
+
+// EXRs are still recognised without the decoder, so they can be reported clearly
+// rather than failing as broken PNGs
+#[cfg(not(feature = "exr"))]
+pub(crate) fn read_info(path: &Path) -> Result<ImageInfo> {
+    Err(disabled(path))
+}
+
+#[cfg(not(feature = "exr"))]
+pub(crate) fn decode(path: &Path) -> Result<Image> {
+    Err(disabled(path))
+}
+
+#[cfg(not(feature = "exr"))]
+pub(crate) fn save(_image: &Image, path: &Path) -> Result<()> {
+    Err(disabled(path))
+}
+
+#[cfg(not(feature = "exr"))]
+fn disabled(path: &Path) -> anyhow::Error {
+    anyhow!(
+        "{:?} is an EXR, which can only be decoded or saved when interpol is built with the \"exr\" feature.",
+        path
+    )
+}
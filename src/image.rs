@@ -0,0 +1,596 @@
+use std::{
+    fmt::{self, Debug, Display},
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use log::debug;
+
+use crate::{
+    animation, bmp,
+    error::{open_image, InterpolError},
+    exr, jpeg,
+    orientation::Orientation,
+    tiff, BitDepth, ColorProfile, PngOptions, Sample, Samples,
+};
+
+/// The channels stored for each pixel of an image
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColorType {
+    Gray,
+    GrayAlpha,
+    Rgb,
+    Rgba,
+}
+
+impl ColorType {
+    /// The number of channels making up a single pixel
+    pub fn channels(self) -> usize {
+        match self {
+            ColorType::Gray => 1,
+            ColorType::GrayAlpha => 2,
+            ColorType::Rgb => 3,
+            ColorType::Rgba => 4,
+        }
+    }
+
+    /// The number of channels making up the color of a pixel, which come before any alpha
+    pub fn color_channels(self) -> usize {
+        match self {
+            ColorType::Gray | ColorType::GrayAlpha => 1,
+            ColorType::Rgb | ColorType::Rgba => 3,
+        }
+    }
+
+    pub fn has_alpha(self) -> bool {
+        self.channels() > self.color_channels()
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorType::Gray => "grayscale",
+            ColorType::GrayAlpha => "grayscale with alpha",
+            ColorType::Rgb => "RGB",
+            ColorType::Rgba => "RGBA",
+        }
+    }
+}
+
+/// This structure represents an image
+/// underneath its just a vector of channel values
+/// plus the color type describing how they make up pixels,
+/// a width and height, and how the colors should be displayed
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Image {
+    pub(crate) data: Samples,
+    pub(crate) color_type: ColorType,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) profile: ColorProfile,
+}
+
+/// The properties of an image which must match for it to be interpolated with another
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ImageInfo {
+    pub width: u32,
+    pub height: u32,
+    pub color_type: ColorType,
+    pub bit_depth: BitDepth,
+}
+
+/// Describes the image as e.g. `640x480 RGB 8 bit`, images can only be interpolated
+/// together when they're described the same way
+impl Display for ImageInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}x{} {} {} bit",
+            self.width,
+            self.height,
+            self.color_type.name(),
+            self.bit_depth.bits()
+        )
+    }
+}
+
+impl ImageInfo {
+    /// Reads just the header of the image at `p` without decoding any of its pixels,
+    /// giving the dimensions it has once turned upright according to its EXIF orientation
+    pub fn from_path<P>(p: P) -> Result<Self>
+    where
+        P: AsRef<Path> + Debug,
+    {
+        let path = p.as_ref();
+        if animation::is_gif(path)? {
+            return animation::gif_info(path);
+        }
+        if bmp::is_bmp(path)? {
+            return bmp::read_info(path);
+        }
+        if exr::is_exr(path)? {
+            return exr::read_info(path);
+        }
+        let info = if jpeg::is_jpeg(path)? {
+            jpeg::read_info(path)?
+        } else if tiff::is_tiff(path)? {
+            tiff::read_info(path)?
+        } else {
+            open_png(path)?.1
+        };
+        Ok(Orientation::from_path(path)?.apply_to_info(info))
+    }
+}
+
+/// Opens the PNG file at `path` and reads its header,
+/// checking that the image is in a format which can be interpolated
+pub(crate) fn open_png(path: &Path) -> Result<(png::Reader<File>, ImageInfo)> {
+    let read_info = |transformations| {
+        let file = open_image(path)?;
+        let size = file
+            .metadata()
+            .with_context(|| format!("Failed to read the metadata of {:?}", path))?
+            .len();
+        if size == 0 {
+            return Err(InterpolError::Empty {
+                path: path.to_owned(),
+            }
+            .into());
+        }
+        let mut decoder = png::Decoder::new(file);
+        decoder.set_transformations(transformations);
+        decoder.read_info().map_err(|e| {
+            png_error(e, path, "before any of its pixels", || {
+                format!("Decoder failed to read information from {:?}", path)
+            })
+        })
+    };
+
+    let mut reader = read_info(png::Transformations::IDENTITY)?;
+    // palette images are decoded with each index looked up in the PLTE chunk, becoming
+    // RGB or RGBA depending on whether a tRNS chunk gives the palette transparency,
+    // and 1, 2 and 4 bit grayscale images are scaled up to 8 bits
+    let info = reader.info();
+    if info.color_type == png::ColorType::Indexed
+        || (info.color_type == png::ColorType::Grayscale
+            && matches!(
+                info.bit_depth,
+                png::BitDepth::One | png::BitDepth::Two | png::BitDepth::Four
+            ))
+    {
+        reader = read_info(png::Transformations::EXPAND)?;
+    }
+
+    let (png_color_type, png_bit_depth) = reader.output_color_type();
+    let color_type = match png_color_type {
+        png::ColorType::Grayscale => ColorType::Gray,
+        png::ColorType::GrayscaleAlpha => ColorType::GrayAlpha,
+        png::ColorType::Rgb => ColorType::Rgb,
+        png::ColorType::Rgba => ColorType::Rgba,
+        other => {
+            return Err(anyhow!(
+                "Unsupported color type {:?} in {:?}, only grayscale, palette, RGB and RGBA images are supported.",
+                other,
+                path
+            ))
+        }
+    };
+    let bit_depth = match png_bit_depth {
+        png::BitDepth::Eight => BitDepth::Eight,
+        png::BitDepth::Sixteen => BitDepth::Sixteen,
+        other => {
+            return Err(anyhow!(
+                "Unsupported bit depth {:?} in {:?}, only 8 and 16 bit images are supported.",
+                other,
+                path
+            ))
+        }
+    };
+
+    let info = ImageInfo {
+        width: reader.info().width,
+        height: reader.info().height,
+        color_type,
+        bit_depth,
+    };
+    ensure!(
+        info.width > 0 && info.height > 0,
+        "{:?} has no pixels, it is {}x{}.",
+        path,
+        info.width,
+        info.height
+    );
+    Ok((reader, info))
+}
+
+/// Replaces the decoder's error for a PNG which ends early with one saying the file is
+/// truncated, any other error being reported with `message`
+pub(crate) fn png_error(
+    error: png::DecodingError,
+    path: &Path,
+    ends: &str,
+    message: impl FnOnce() -> String,
+) -> anyhow::Error {
+    match error {
+        png::DecodingError::IoError(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+            InterpolError::Truncated {
+                path: path.to_owned(),
+                ends: ends.to_string(),
+            }
+            .into()
+        }
+        other => InterpolError::Decode {
+            path: path.to_owned(),
+            message: message(),
+            source: Box::new(other),
+        }
+        .into(),
+    }
+}
+
+/// The number of channel values in a `width` by `height` image of `color_type`,
+/// or an error when there are too many to address
+pub(crate) fn sample_count(width: u32, height: u32, color_type: ColorType) -> Result<usize> {
+    (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(color_type.channels()))
+        .ok_or_else(|| {
+            anyhow!(
+                "A {}x{} {} image is too large, it has more channel values than can be addressed.",
+                width,
+                height,
+                color_type.name()
+            )
+        })
+}
+
+/// Decodes the whole of the PNG image at `path`
+fn decode_png(path: &Path) -> Result<Image> {
+    let (mut reader, info) = open_png(path)?;
+    let profile = ColorProfile::from_png_info(reader.info());
+
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let output = reader.next_frame(&mut buf).map_err(|e| {
+        png_error(e, path, "part way through its pixels", || {
+            format!("Reader failed to read any frames from {:?}", path)
+        })
+    })?;
+    ensure!(
+        output.buffer_size() == buf.len(),
+        "{:?} only decoded to {} bytes, but a {}x{} {} {} bit image takes {}.",
+        path,
+        output.buffer_size(),
+        info.width,
+        info.height,
+        info.color_type.name(),
+        info.bit_depth.bits(),
+        buf.len()
+    );
+
+    // PNG stores 16 bit channels big-endian regardless of the platform
+    let data = Samples::from_be_bytes(buf, info.bit_depth);
+    let image = Image::new_from_parts(data, info.color_type, info.width, info.height)?;
+    Ok(image.with_color_profile(profile))
+}
+
+impl Image {
+    /// Decodes the PNG, JPEG, BMP, TIFF or EXR image at `p`, recognising each by its
+    /// contents rather than its extension. JPEGs are always loaded as RGB, as are BMPs
+    /// unless they have an alpha channel, and only the first page of a TIFF is decoded.
+    /// EXRs are loaded as floats, as RGB or RGBA with their values as they are stored.
+    ///
+    /// Images with an EXIF orientation are flipped and rotated to be upright as they load.
+    pub fn new_from_path<P>(p: P) -> Result<Self>
+    where
+        P: AsRef<Path> + Debug,
+    {
+        let path = p.as_ref();
+        // GIFs have no EXIF data to say which way up they are
+        if animation::is_gif(path)? {
+            return animation::decode_frame(path, 0);
+        }
+        // nor do BMPs or EXRs
+        if bmp::is_bmp(path)? {
+            return bmp::decode(path);
+        }
+        if exr::is_exr(path)? {
+            return exr::decode(path);
+        }
+        let image = if jpeg::is_jpeg(path)? {
+            jpeg::decode(path)?
+        } else if tiff::is_tiff(path)? {
+            tiff::decode(path, 0)?
+        } else {
+            decode_png(path)?
+        };
+        let orientation = Orientation::from_path(path)?;
+        if orientation != Orientation::Normal {
+            debug!(
+                "Turning {:?} upright from its orientation {:?}",
+                path, orientation
+            );
+        }
+        let image = image.oriented(orientation);
+
+        debug!(
+            "Decoded {:?}: {}x{} {} {} bit",
+            path,
+            image.width,
+            image.height,
+            image.color_type.name(),
+            image.data.bit_depth().bits()
+        );
+        Ok(image)
+    }
+
+    /// Creates an image from its channel values, which can be a `Vec<u8>`
+    /// for 8 bit images, a `Vec<u16>` for 16 bit images or a `Vec<f32>`
+    /// for float images like EXRs, with no color profile
+    pub fn new_from_parts<D>(
+        data: D,
+        color_type: ColorType,
+        width: u32,
+        height: u32,
+    ) -> Result<Self>
+    where
+        D: Into<Samples>,
+    {
+        let data = data.into();
+        ensure!(
+            data.len() == sample_count(width, height, color_type)?,
+            "Data must match the dimensions given in width and height."
+        );
+
+        Ok(Self {
+            data,
+            color_type,
+            width,
+            height,
+            profile: ColorProfile::default(),
+        })
+    }
+
+    /// Creates an 8 bit RGB image by copying a buffer of `width * height` pixels,
+    /// each given as its red, green and blue values in turn
+    pub fn from_rgb_buffer(buf: &[u8], width: u32, height: u32) -> Result<Self> {
+        let expected = sample_count(width, height, ColorType::Rgb)?;
+        ensure!(
+            buf.len() == expected,
+            "An RGB buffer for a {}x{} image must hold {} bytes, not {}.",
+            width,
+            height,
+            expected,
+            buf.len()
+        );
+
+        Self::new_from_parts(buf.to_vec(), ColorType::Rgb, width, height)
+    }
+
+    /// Borrows the pixels of an 8 bit RGB image as the flat buffer taken by
+    /// [`Image::from_rgb_buffer`], or `None` when the image has any other format
+    pub fn to_rgb_buffer(&self) -> Option<&[u8]> {
+        match &self.data {
+            Samples::Eight(data) if self.color_type == ColorType::Rgb => Some(data),
+            _ => None,
+        }
+    }
+
+    /// The mean absolute difference between the channel values of two images of the same
+    /// size, color type and bit depth, scaled into [0.0..1.0]
+    pub(crate) fn mean_difference(&self, other: &Image) -> f64 {
+        fn mean<S: Sample>(a: &[S], b: &[S]) -> f64 {
+            let total: f64 = a
+                .iter()
+                .zip(b)
+                .map(|(a, b)| (a.to_normalized() - b.to_normalized()).abs())
+                .sum();
+            total / a.len().max(1) as f64
+        }
+
+        match (&self.data, &other.data) {
+            (Samples::Eight(a), Samples::Eight(b)) => mean(a, b),
+            (Samples::Sixteen(a), Samples::Sixteen(b)) => mean(a, b),
+            (Samples::Float(a), Samples::Float(b)) => mean(a, b),
+            _ => unreachable!("images of differing bit depths are rejected by the Interpolator"),
+        }
+    }
+
+    /// An image of how much each pixel of this image differs from the same pixel of `other`,
+    /// which must have the same size, color type and bit depth. Each channel is the absolute
+    /// difference between the two images' values multiplied by `amplify`, so that small
+    /// changes can be seen, up to the largest value a channel can hold.
+    ///
+    /// The image has no alpha, instead each of its color channels shows any larger difference
+    /// in alpha, so pixels which only fade in or out still show up.
+    pub fn diff(&self, other: &Image, amplify: f64) -> Result<Image> {
+        fn diff<S: Sample>(a: &[S], b: &[S], color_type: ColorType, amplify: f64) -> Vec<S> {
+            let (channels, color_channels) = (color_type.channels(), color_type.color_channels());
+            a.chunks_exact(channels)
+                .zip(b.chunks_exact(channels))
+                .flat_map(|(a, b)| {
+                    let difference = move |c: usize| (a[c].to_f64() - b[c].to_f64()).abs();
+                    let alpha = (color_channels..channels)
+                        .map(difference)
+                        .fold(0.0, f64::max);
+                    (0..color_channels)
+                        .map(move |c| S::from_f64(difference(c).max(alpha) * amplify))
+                })
+                .collect()
+        }
+
+        ensure!(
+            self.info() == other.info(),
+            "Only images of the same size and format can be compared, not {} and {}.",
+            self.info(),
+            other.info()
+        );
+        ensure!(
+            amplify.is_finite() && amplify >= 0.0,
+            "Differences can only be amplified by a positive number, not {}.",
+            amplify
+        );
+
+        let color_type = match self.color_type {
+            ColorType::GrayAlpha => ColorType::Gray,
+            ColorType::Rgba => ColorType::Rgb,
+            color_type => color_type,
+        };
+        let data = match (&self.data, &other.data) {
+            (Samples::Eight(a), Samples::Eight(b)) => {
+                Samples::Eight(diff(a, b, self.color_type, amplify))
+            }
+            (Samples::Sixteen(a), Samples::Sixteen(b)) => {
+                Samples::Sixteen(diff(a, b, self.color_type, amplify))
+            }
+            (Samples::Float(a), Samples::Float(b)) => {
+                Samples::Float(diff(a, b, self.color_type, amplify))
+            }
+            _ => unreachable!("the images were checked to have the same bit depth"),
+        };
+        Ok(Image {
+            data,
+            color_type,
+            width: self.width,
+            height: self.height,
+            profile: self.profile.clone(),
+        })
+    }
+
+    /// Replaces the color profile written out with the image
+    pub fn with_color_profile(self, profile: ColorProfile) -> Self {
+        Self { profile, ..self }
+    }
+
+    /// The channel values of the image, row by row
+    pub fn data(&self) -> &Samples {
+        &self.data
+    }
+
+    pub fn info(&self) -> ImageInfo {
+        ImageInfo {
+            width: self.width,
+            height: self.height,
+            color_type: self.color_type,
+            bit_depth: self.data.bit_depth(),
+        }
+    }
+
+    pub fn color_type(&self) -> ColorType {
+        self.color_type
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The color chunks read from the image's PNG, and written back out when it is saved
+    pub fn color_profile(&self) -> &ColorProfile {
+        &self.profile
+    }
+
+    /// Saves the image to `p`, as an EXR of floats when `p` ends in `.exr` and as a PNG
+    /// otherwise. Float images can only be saved as EXRs.
+    pub fn save<P>(&self, p: P) -> Result<()>
+    where
+        P: AsRef<Path> + Debug,
+    {
+        self.save_with(p, &PngOptions::default())
+    }
+
+    /// Saves the image in the same way as `save`, encoding PNGs with `options`
+    pub fn save_with<P>(&self, p: P, options: &PngOptions) -> Result<()>
+    where
+        P: AsRef<Path> + Debug,
+    {
+        let path = p.as_ref();
+        let is_exr = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("exr"));
+        if is_exr {
+            return exr::save(self, path);
+        }
+        let file = File::create(path).with_context(|| {
+            format!("Failed to create file at {:?} to save the image to.", path)
+        })?;
+
+        self.write_png_with(BufWriter::new(file), options)
+            .with_context(|| format!("Failed to save image file: {:?}", path))
+    }
+
+    /// Writes the channel values of the image into `w` with no header or compression,
+    /// row by row with 16 bit and float values big-endian as in a PNG
+    pub fn write_raw<W: Write>(&self, mut w: W) -> Result<()> {
+        w.write_all(&self.data.to_be_bytes())
+            .context("Failed to write the raw image data.")
+    }
+
+    /// Encodes the image as a PNG into `w`
+    pub fn write_png<W: Write>(&self, w: W) -> Result<()> {
+        self.write_png_with(w, &PngOptions::default())
+    }
+
+    /// Encodes the image as a PNG into `w` with `options`
+    pub fn write_png_with<W: Write>(&self, w: W, options: &PngOptions) -> Result<()> {
+        let mut writer = png_encoder(w, self.info(), &self.profile, options)?
+            .write_header()
+            .context("Failed to write the PNG header.")?;
+
+        let data = self.data.to_be_bytes();
+        match options.chunk_size {
+            // the stream writer starts a new IDAT chunk each time its buffer fills
+            Some(chunk_size) => {
+                let mut stream = writer
+                    .stream_writer_with_size(chunk_size)
+                    .context("Failed to start writing the PNG image data.")?;
+                stream
+                    .write_all(&data)
+                    .context("Failed to write the PNG image data.")?;
+                stream.finish().context("Failed to finish writing the PNG.")
+            }
+            None => {
+                writer
+                    .write_image_data(&data)
+                    .context("Failed to write the PNG image data.")?;
+                writer.finish().context("Failed to finish writing the PNG.")
+            }
+        }
+    }
+
+    /// Creates a PNG encoder set up for images with the same dimensions,
+    /// color type, bit depth and color profile as this one
+    pub(crate) fn png_encoder<W: Write>(&self, w: W) -> Result<png::Encoder<'static, W>> {
+        png_encoder(w, self.info(), &self.profile, &PngOptions::default())
+    }
+}
+
+/// Creates a PNG encoder for images described by `info` and `profile`, writing with `options`
+pub(crate) fn png_encoder<W: Write>(
+    w: W,
+    info: ImageInfo,
+    profile: &ColorProfile,
+    options: &PngOptions,
+) -> Result<png::Encoder<'static, W>> {
+    let mut png_info = png::Info::with_size(info.width, info.height);
+    png_info.color_type = match info.color_type {
+        ColorType::Gray => png::ColorType::Grayscale,
+        ColorType::GrayAlpha => png::ColorType::GrayscaleAlpha,
+        ColorType::Rgb => png::ColorType::Rgb,
+        ColorType::Rgba => png::ColorType::Rgba,
+    };
+    png_info.bit_depth = match info.bit_depth {
+        BitDepth::Eight => png::BitDepth::Eight,
+        BitDepth::Sixteen => png::BitDepth::Sixteen,
+        BitDepth::Float => bail!("Float images can't be saved as PNGs, they can be saved as EXRs."),
+    };
+    profile.apply_to(&mut png_info);
+
+    let mut encoder =
+        png::Encoder::with_info(w, png_info).context("Failed to set up the PNG encoder.")?;
+    options.apply_to(&mut encoder);
+    Ok(encoder)
+}
@@ -0,0 +1,1468 @@
+use std::{
+    fmt::Debug,
+    ops::Range,
+    path::Path,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use anyhow::{anyhow, bail, ensure, Result};
+use log::{debug, trace};
+use rayon::prelude::*;
+
+use crate::{
+    animation::{self, Animations},
+    blend::{
+        interpolate, interpolate_catmull_rom, interpolate_per_channel, interpolate_premultiplied,
+        Mean,
+    },
+    burn_in::{BurnIn, Corner},
+    ken_burns::KenBurns,
+    keyframes::{Keyframes, Source, CACHE_SIZE},
+    morph::{morph, FlowCache},
+    profile::shared_profile,
+    region::Region,
+    strips::render_in_strips,
+    BitDepth, BlendMode, ColorProfile, ColorSpace, ColorType, Dither, Easing, EasingTable, Image,
+    ImageInfo, ImageLoader, InterpolError, Interpolation, PngOptions, SolidColor, Transition,
+};
+
+/// Generates the frames interpolating between a sequence of images,
+/// starting on the first image and ending on the last.
+///
+/// Each transition starts on its first image at `mu = 0` and produces
+/// `steps_per_interpolation` frames, the `mu = 1` end of one transition being the start
+/// of the next. The last image is then emitted as one extra frame, so N images produce
+/// `(N - 1) * steps_per_interpolation + 1` frames in total, plus `N * hold` frames when
+/// each keyframe is held.
+///
+/// At least two images and one step per transition are needed, so the smallest sequence
+/// is just the two images themselves.
+#[derive(Debug, Clone)]
+pub struct Interpolator {
+    keyframes: Keyframes,
+    /// The properties shared by every keyframe, and so every frame
+    info: ImageInfo,
+    /// The color profile given to every frame, taken from the keyframes which have one
+    profile: ColorProfile,
+    steps_per_interpolation: usize,
+    /// The number of frames for each transition between adjacent images,
+    /// overriding `steps_per_interpolation`
+    steps_between: Option<Vec<usize>>,
+    /// The number of extra frames each keyframe is shown for before its transition
+    hold: usize,
+    ping_pong: bool,
+    looping: bool,
+    /// The transitions making up the sequence, in the order they are played
+    segments: Vec<Segment>,
+    easing: Easing,
+    /// A table giving the easing curve, which replaces `easing` when set
+    easing_table: Option<Arc<EasingTable>>,
+    /// Easing curves for the red, green and blue channels which replace `easing`
+    channel_easing: [Option<Easing>; 3],
+    blend_mode: BlendMode,
+    transition: Transition,
+    /// The width in pixels over which the edges of wipes and irises are blended
+    feather: u32,
+    interpolation: Interpolation,
+    color_space: ColorSpace,
+    gamma_correct: bool,
+    /// Whether the colors of images with alpha are weighted by it while blending
+    premultiplied: bool,
+    dither: Dither,
+    /// The seed the noise of noise dithering is generated from
+    seed: u64,
+    /// The bit depth of the frames when it differs from that of the keyframes
+    output_bit_depth: Option<BitDepth>,
+    /// The color frames with alpha are composited over
+    background: Option<[u8; 3]>,
+    /// The motion estimated between pairs of keyframes when morphing
+    flows: Arc<FlowCache>,
+    /// A second stack of keyframes every frame is blended with
+    stack: Option<Stack>,
+    /// The only part of each frame which is interpolated, the rest coming from the start
+    /// keyframe of its transition
+    region: Option<Region>,
+    /// The pan and zoom every frame is sampled through once it has been blended
+    ken_burns: Option<KenBurns>,
+    /// The number of blends averaged into each frame, more than one blurring the motion
+    motion_blur: usize,
+    /// The label stamped on to each frame, in `burn_in_corner`
+    burn_in: BurnIn,
+    burn_in_corner: Corner,
+    /// The frame rate timecodes are counted at
+    fps: f64,
+    /// The index of the next frame to generate
+    frame_no: usize,
+}
+
+/// A stack of keyframes matching the interpolator's own image for image, interpolated
+/// in the same way and blended with its frames by `weight`
+#[derive(Debug, Clone)]
+struct Stack {
+    keyframes: Keyframes,
+    flows: Arc<FlowCache>,
+    weight: f64,
+}
+
+impl Interpolator {
+    /// Creates an interpolator which will produce `steps_per_interpolation` frames
+    /// for each pair of adjacent images, plus one final frame of the last image
+    pub fn new(images: Vec<Image>, steps_per_interpolation: usize) -> Result<Self> {
+        let infos: Vec<_> = images.iter().map(Image::info).collect();
+        check_consistent(
+            infos
+                .iter()
+                .enumerate()
+                .map(|(n, info)| (format!("Keyframe {}", n), *info)),
+        )?;
+        let profile = shared_profile(
+            images
+                .iter()
+                .enumerate()
+                .map(|(n, image)| (format!("Keyframe {}", n), image.profile.clone())),
+        )?;
+
+        let keyframes = Keyframes::Loaded(images.into_iter().map(Arc::new).collect());
+        Self::with_keyframes(keyframes, infos[0], profile, steps_per_interpolation)
+    }
+
+    /// Creates an interpolator in the same way as `new`, except the images are only
+    /// decoded from `paths` when they are needed, with at most two held in memory at once.
+    ///
+    /// Only the image headers are read up front to check the images are consistent.
+    pub fn from_paths<P>(paths: Vec<P>, steps_per_interpolation: usize) -> Result<Self>
+    where
+        P: AsRef<Path> + Debug,
+    {
+        Self::from_paths_with_loader(paths, steps_per_interpolation, ImageLoader::new())
+    }
+
+    /// Creates an interpolator in the same way as `from_paths`,
+    /// using `loader` to turn the images into keyframes as they are decoded.
+    ///
+    /// Any of the paths can instead be a [`SolidColor`] such as `#000000`, which takes
+    /// the size and format of the nearest image file unless it gives its own size.
+    ///
+    /// Animated GIFs and APNGs become one keyframe for each of their frames, in place
+    /// of the single path, see [`Image::frames_from_path`].
+    pub fn from_paths_with_loader<P>(
+        paths: Vec<P>,
+        steps_per_interpolation: usize,
+        loader: ImageLoader,
+    ) -> Result<Self>
+    where
+        P: AsRef<Path> + Debug,
+    {
+        // the headers are read several at a time, as with many images on a slow disk
+        // the time spent waiting for each in turn adds up
+        let paths: Vec<&Path> = paths.iter().map(AsRef::as_ref).collect();
+        let frame_counts = collect_in_order(paths.par_iter().map(|path| {
+            if SolidColor::is_solid_color(path) {
+                Ok(None)
+            } else {
+                animation::frame_count(path)
+            }
+        }))?;
+
+        // animations are spread out into their frames, each with the frame's index
+        let mut entries = Vec::with_capacity(paths.len());
+        for (&path, frames) in paths.iter().zip(frame_counts) {
+            match frames {
+                Some(0) => bail!("The animation {:?} has no frames.", path),
+                Some(frames) => entries.extend((0..frames).map(|i| (path, Some(i)))),
+                None => entries.push((path, None)),
+            }
+        }
+
+        let colors: Vec<_> = entries
+            .iter()
+            .map(|(path, _)| SolidColor::from_path(path))
+            .collect::<Result<_>>()?;
+        // image files come first, then the colors with their own size, which take on the
+        // format of the nearest file, and then the rest of the colors from whatever is nearest
+        let file_infos = collect_in_order(entries.par_iter().zip(colors.par_iter()).map(
+            |((p, _), color)| match color {
+                Some(_) => Ok(None),
+                None => loader.info(p).map(Some),
+            },
+        ))?;
+        let sized: Vec<_> = colors
+            .iter()
+            .enumerate()
+            .map(|(i, color)| match color {
+                Some(SolidColor {
+                    size: Some((width, height)),
+                    ..
+                }) => Some(loader.loaded_info(ImageInfo {
+                    width: *width,
+                    height: *height,
+                    // with no image files at all the colors are 8 bit RGB
+                    ..nearest_info(&file_infos, i).unwrap_or(ImageInfo {
+                        width: *width,
+                        height: *height,
+                        color_type: ColorType::Rgb,
+                        bit_depth: BitDepth::Eight,
+                    })
+                })),
+                _ => file_infos[i],
+            })
+            .collect();
+        let infos: Vec<_> = entries
+            .iter()
+            .enumerate()
+            .map(|(i, (p, _))| {
+                sized[i].or_else(|| nearest_info(&sized, i)).ok_or_else(|| {
+                    anyhow!(
+                        "{:?} needs a size such as x640x480, there is no other keyframe to take one from.",
+                        p
+                    )
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        let sources: Vec<_> = entries
+            .iter()
+            .zip(&colors)
+            .zip(&infos)
+            .map(|(((p, frame), color), info)| match (color, frame) {
+                (Some(color), _) => Source::Solid(*color, *info),
+                (None, Some(frame)) => Source::Frame(p.to_path_buf(), *frame),
+                (None, None) => Source::File(p.to_path_buf()),
+            })
+            .collect();
+        check_consistent(entries.iter().zip(&infos).map(|((p, frame), info)| {
+            let name = match frame {
+                Some(frame) => format!("Frame {} of {:?}", frame, p),
+                None => format!("{:?}", p),
+            };
+            (name, *info)
+        }))?;
+        let profile_paths: Vec<_> = sources
+            .iter()
+            .filter_map(|source| match source {
+                // every frame of an animation shares the chunks of its file
+                Source::File(p) | Source::Frame(p, 0) => Some(p),
+                Source::Frame(..) | Source::Solid(..) => None,
+            })
+            .collect();
+        let profile = shared_profile(collect_in_order(
+            profile_paths
+                .par_iter()
+                .map(|p| Ok((format!("{:?}", p), ColorProfile::from_path(p)?))),
+        )?)?;
+
+        let keyframes = Keyframes::Lazy {
+            sources,
+            loader,
+            cache: Mutex::new(Vec::new()),
+            capacity: CACHE_SIZE,
+            animations: Animations::default(),
+        };
+        Self::with_keyframes(keyframes, infos[0], profile, steps_per_interpolation)
+    }
+
+    fn with_keyframes(
+        keyframes: Keyframes,
+        info: ImageInfo,
+        profile: ColorProfile,
+        steps_per_interpolation: usize,
+    ) -> Result<Self> {
+        ensure!(
+            steps_per_interpolation > 0,
+            "Every transition must have at least one frame."
+        );
+
+        let mut interpolator = Self {
+            keyframes,
+            info,
+            profile,
+            steps_per_interpolation,
+            steps_between: None,
+            hold: 0,
+            ping_pong: false,
+            looping: false,
+            segments: Vec::new(),
+            easing: Easing::Linear,
+            easing_table: None,
+            channel_easing: [None; 3],
+            blend_mode: BlendMode::Mix,
+            transition: Transition::Dissolve,
+            feather: 0,
+            interpolation: Interpolation::Linear,
+            color_space: ColorSpace::Rgb,
+            gamma_correct: false,
+            premultiplied: false,
+            dither: Dither::None,
+            seed: 0,
+            output_bit_depth: None,
+            background: None,
+            flows: Arc::default(),
+            stack: None,
+            region: None,
+            ken_burns: None,
+            motion_blur: 1,
+            burn_in: BurnIn::None,
+            burn_in_corner: Corner::TopLeft,
+            fps: 30.0,
+            frame_no: 0,
+        };
+        interpolator.plan_segments();
+        Ok(interpolator)
+    }
+
+    /// Works out the transitions making up the sequence from the keyframes and settings
+    fn plan_segments(&mut self) {
+        let last = self.keyframes.len() - 1;
+        let steps = |pair: usize| {
+            self.steps_between
+                .as_ref()
+                .and_then(|steps| steps.get(pair).copied())
+                .unwrap_or(self.steps_per_interpolation)
+        };
+
+        // each transition is paired with the index used to look up its number of frames
+        let mut pairs: Vec<_> = (0..last).map(|i| (i, i + 1, i)).collect();
+        if self.ping_pong {
+            // the reverse leg starts on the last image, which the forward leg ends on,
+            // and each transition takes as long as it did going forwards
+            pairs.extend((1..=last).rev().map(|i| (i, i - 1, i - 1)));
+        } else if self.looping {
+            // close the loop with one more transition treating the images as circular
+            pairs.push((last, 0, last));
+        }
+
+        let mut first_frame = 0;
+        self.segments = pairs
+            .into_iter()
+            .map(|(start, end, pair)| {
+                let segment = Segment {
+                    start,
+                    end,
+                    hold: self.hold,
+                    steps: steps(pair),
+                    first_frame,
+                };
+                first_frame += segment.len();
+                segment
+            })
+            .collect();
+    }
+
+    /// Whether the sequence ends on the image it started on
+    fn is_cyclic(&self) -> bool {
+        self.ping_pong || self.looping
+    }
+
+    /// Sets the number of frames for each transition between adjacent images individually,
+    /// so `steps[i]` is the number of frames going from image `i` to image `i + 1`.
+    ///
+    /// There must be one count for each pair of adjacent images, the transition closing a
+    /// loop can be given one more count and otherwise uses `steps_per_interpolation`.
+    pub fn frames_between(mut self, steps: Vec<usize>) -> Result<Self> {
+        let pairs = self.keyframes.len() - 1;
+        ensure!(
+            steps.len() == pairs || steps.len() == pairs + 1,
+            "Expected {} frame counts for the transitions between {} images, or {} when looping, but got {}.",
+            pairs,
+            pairs + 1,
+            pairs + 1,
+            steps.len()
+        );
+        ensure!(
+            steps.iter().all(|&n| n > 0),
+            "Every transition must have at least one frame."
+        );
+
+        self.steps_between = Some(steps);
+        self.plan_segments();
+        Ok(self)
+    }
+
+    /// Sets the number of frames for each transition from the times in seconds at which each
+    /// image is fully shown, at `fps` frames per second, so `times[i]` is the time of image `i`.
+    ///
+    /// As with `frames_between` a loop can be closed at one extra time. Each time is rounded to
+    /// the nearest frame and the transitions fill the frames between them, so the rounding
+    /// never adds up and the sequence always lasts `times[last] - times[0]` to the nearest frame.
+    pub fn timeline(self, times: &[f64], fps: f64) -> Result<Self> {
+        ensure!(
+            fps.is_finite() && fps > 0.0,
+            "The frame rate must be a positive number, got {}.",
+            fps
+        );
+        ensure!(
+            times.iter().all(|t| t.is_finite()),
+            "The times in the timeline must all be numbers."
+        );
+        ensure!(
+            times.windows(2).all(|w| w[0] < w[1]),
+            "The times in the timeline must be increasing."
+        );
+
+        let boundaries: Vec<_> = times
+            .iter()
+            .map(|t| ((t - times[0]) * fps).round() as usize)
+            .collect();
+        let steps: Vec<_> = boundaries.windows(2).map(|w| w[1] - w[0]).collect();
+        if let Some(pair) = steps.iter().position(|&n| n == 0) {
+            return Err(anyhow!(
+                "The images at {}s and {}s are less than a frame apart at {} frames per second.",
+                times[pair],
+                times[pair + 1],
+                fps
+            ));
+        }
+        self.frames_between(steps)
+    }
+
+    /// Shares out the frames of the transitions between adjacent images by how much the
+    /// images change, so transitions between very different images get more frames and
+    /// those between similar ones fewer, keeping the same number of frames in total.
+    ///
+    /// The change is the mean absolute difference between the two images' channel values,
+    /// every transition keeps at least one frame and when none of the images differ the
+    /// frames are shared out evenly instead. Any frame counts set before are only used for
+    /// the total, so this should come after `frames_between`, `timeline` and `looping`.
+    pub fn distribute_by_motion(self) -> Result<Self> {
+        let last = self.keyframes.len() - 1;
+        // ping pong reuses the counts of the forward transitions on the way back
+        let mut pairs: Vec<_> = (0..last).map(|i| (i, i + 1)).collect();
+        if self.looping && !self.ping_pong {
+            pairs.push((last, 0));
+        }
+        if pairs.is_empty() {
+            return Ok(self);
+        }
+
+        let budget: usize = (0..pairs.len())
+            .map(|pair| {
+                self.steps_between
+                    .as_ref()
+                    .and_then(|steps| steps.get(pair).copied())
+                    .unwrap_or(self.steps_per_interpolation)
+            })
+            .sum();
+        let differences = pairs
+            .iter()
+            .map(|&(start, end)| -> Result<_> {
+                let (start, end) = (self.keyframes.get(start)?, self.keyframes.get(end)?);
+                Ok(start.mean_difference(&end))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        debug!("Differences between adjacent images: {:?}", differences);
+
+        let steps = share_out(budget, &differences);
+        self.frames_between(steps)
+    }
+
+    /// Shows each keyframe for `frames` more frames before the transition away from it starts,
+    /// including the last image at the end of a sequence which isn't cyclic
+    pub fn hold(mut self, frames: usize) -> Self {
+        self.hold = frames;
+        self.plan_segments();
+        self
+    }
+
+    /// Plays the sequence forwards through the images and then backwards to the start.
+    ///
+    /// The sequence then ends on the first image, so the final frame is left out
+    /// as it would be identical to the first, letting the frames loop seamlessly.
+    pub fn ping_pong(mut self, ping_pong: bool) -> Self {
+        self.ping_pong = ping_pong;
+        self.plan_segments();
+        self
+    }
+
+    /// Adds one more transition from the last image back to the first, with the same
+    /// number of frames as the others, so the frames loop seamlessly.
+    ///
+    /// As with `ping_pong` the final frame is left out, and this has no effect when
+    /// combined with `ping_pong` as that already returns to the first image.
+    pub fn looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self.plan_segments();
+        self
+    }
+
+    /// Sets the easing curve applied to the progress through each transition
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Sets the easing curve to one sampled in a table, such as one exported from an
+    /// animation tool, in place of the curve set by `easing`
+    pub fn easing_table(mut self, table: EasingTable) -> Self {
+        self.easing_table = Some(Arc::new(table));
+        self
+    }
+
+    /// Sets separate easing curves for the red, green and blue channels,
+    /// any channel left as `None` keeps to the curve set by `easing` or `easing_table`
+    /// as does alpha.
+    ///
+    /// This only changes linear interpolation of RGB or RGBA images in the RGB color space.
+    pub fn channel_easing(mut self, easing: [Option<Easing>; 3]) -> Self {
+        self.channel_easing = easing;
+        self
+    }
+
+    /// Sets how the colors of the keyframes are combined during each transition.
+    ///
+    /// Like `channel_easing` this only changes linear interpolation in the RGB color space.
+    pub fn blend_mode(mut self, blend_mode: BlendMode) -> Self {
+        self.blend_mode = blend_mode;
+        self
+    }
+
+    /// Sets how the end image of each transition replaces the start image, every transition
+    /// other than a dissolve moving a region of the end image across the frame.
+    ///
+    /// This only changes linear interpolation, the colors in the soft edge of a feathered
+    /// wipe or iris being mixed in RGB.
+    pub fn transition(mut self, transition: Transition) -> Self {
+        self.transition = transition;
+        self
+    }
+
+    /// Sets the width in pixels over which the edges of wipes and irises blend from one
+    /// image into the other, for a soft edge rather than a hard one
+    pub fn feather(mut self, feather: u32) -> Self {
+        self.feather = feather;
+        self
+    }
+
+    /// Sets how the frames between keyframes are calculated.
+    ///
+    /// Catmull-Rom splines always blend in RGB, the color space is only used by linear
+    /// interpolation, and keep up to four lazily loaded images in memory rather than two.
+    /// At the ends of a sequence which isn't cyclic the end images stand in for
+    /// their missing neighbours.
+    ///
+    /// Morphing estimates the motion between each pair of keyframes the first time one of
+    /// their frames is generated, and keeps it until the interpolator is dropped.
+    pub fn interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.interpolation = interpolation;
+        let capacity = self.keyframe_capacity();
+        self.keyframes.set_capacity(capacity);
+        if let Some(stack) = &mut self.stack {
+            stack.keyframes.set_capacity(capacity);
+        }
+        self
+    }
+
+    /// The number of lazily loaded images kept in memory for each stack of keyframes
+    fn keyframe_capacity(&self) -> usize {
+        match self.interpolation {
+            Interpolation::Linear | Interpolation::Morph => CACHE_SIZE,
+            Interpolation::CatmullRom => 4,
+        }
+    }
+
+    /// Sets the color space the color channels are blended in
+    pub fn color_space(mut self, color_space: ColorSpace) -> Self {
+        self.color_space = color_space;
+        self
+    }
+
+    /// Sets whether the color channels are blended in linear light
+    pub fn gamma_correct(mut self, gamma_correct: bool) -> Self {
+        self.gamma_correct = gamma_correct;
+        self
+    }
+
+    /// Sets whether images with alpha are blended with their colors premultiplied by their
+    /// alpha, which stops the colors of transparent pixels showing through as dark fringes
+    /// around the edges of transparent areas during a fade.
+    ///
+    /// Like `channel_easing` this only changes linear interpolation in the RGB color space,
+    /// and then only with the mix blend mode and a single easing curve.
+    pub fn premultiplied(mut self, premultiplied: bool) -> Self {
+        self.premultiplied = premultiplied;
+        self
+    }
+
+    /// Sets how frames blended at 16 bits are reduced to 8 bits, which happens to the blends
+    /// of 8 bit images and to every frame of 16 bit images when the output is 8 bit.
+    /// This has no effect when the frames are 16 bit.
+    pub fn dither(mut self, dither: Dither) -> Self {
+        self.dither = dither;
+        self
+    }
+
+    /// Sets the seed the noise of [`Dither::Noise`] is generated from, the same seed always
+    /// giving the same noise so the frames are identical from one run to the next. It's 0
+    /// unless set.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Sets the bit depth of the frames, whatever the bit depth of the images.
+    ///
+    /// 8 bit values `x` become `x * 257` at 16 bits, which is exact, and 8 bit images are
+    /// blended at 16 bits so the extra precision of the blend is kept. 16 bit values are
+    /// dithered down to 8 bits as set by `dither`, or without dithering are rounded to
+    /// the nearest value with `(x + 128) / 257`.
+    ///
+    /// Float frames scale integer values so their brightest is 1.0, and are blended as
+    /// floats without clipping, so the values of float images above 1.0 are kept. Floats
+    /// become integers by clipping to `0.0..=1.0` first.
+    pub fn output_bit_depth(mut self, bit_depth: BitDepth) -> Self {
+        self.output_bit_depth = Some(bit_depth);
+        self
+    }
+
+    /// Composites every frame over a solid `background` color as it is generated,
+    /// so frames of images with alpha are generated without it
+    pub fn background(mut self, background: [u8; 3]) -> Self {
+        self.background = Some(background);
+        self
+    }
+
+    /// Stamps every frame with `burn_in` in `corner`, white on a box which darkens the frame
+    /// behind it, with timecodes counted at `fps` frames a second
+    pub fn burn_in(mut self, burn_in: BurnIn, corner: Corner, fps: f64) -> Self {
+        self.burn_in = burn_in;
+        self.burn_in_corner = corner;
+        self.fps = fps;
+        self
+    }
+
+    /// Blends every frame with the matching frame interpolated between the keyframes of
+    /// `other`, `weight` of the way across from these keyframes at `0.0` to its at `1.0`.
+    ///
+    /// This interpolates between two stacks of images at once, such as two volumes sampled
+    /// slice by slice, with each frame coming from the four keyframes around it. Only the
+    /// keyframes of `other` are used, they are interpolated with these settings, and there
+    /// must be the same number of them in the same format as these.
+    pub fn stack(mut self, other: Interpolator, weight: f64) -> Result<Self> {
+        ensure!(
+            other.keyframes.len() == self.keyframes.len(),
+            "The second stack has {} images, but there are {} to match them with.",
+            other.keyframes.len(),
+            self.keyframes.len()
+        );
+        ensure!(
+            other.info == self.info,
+            "The second stack is {}, but its images must be {} like the first stack.",
+            other.info,
+            self.info
+        );
+        ensure!(
+            weight.is_finite(),
+            "The weight of the second stack must be a number, not {}.",
+            weight
+        );
+
+        let mut keyframes = other.keyframes;
+        keyframes.set_capacity(self.keyframe_capacity());
+        self.stack = Some(Stack {
+            keyframes,
+            flows: Arc::default(),
+            weight,
+        });
+        Ok(self)
+    }
+
+    /// Interpolates only the pixels within `region`, every pixel outside it being copied
+    /// from the image each transition starts on, so only that part of the frame changes
+    /// and the rest of it is never blended.
+    ///
+    /// Transitions which move the end image across the frame move it across the region
+    /// alone. The region must lie within the images.
+    pub fn region(mut self, region: Region) -> Result<Self> {
+        region.check_fits(self.info)?;
+        self.region = Some(region);
+        Ok(self)
+    }
+
+    /// Pans and zooms across the frames, each one showing only what lies within a rectangle
+    /// moving from `ken_burns.start` on the first frame to `ken_burns.end` on the last, which
+    /// is scaled to the size given by `ken_burns`. The rectangle moves along the easing curve
+    /// over the whole sequence.
+    ///
+    /// The rectangle is sampled bilinearly at fractions of a pixel, so slow pans and zooms
+    /// move smoothly. Both rectangles must lie within the images.
+    pub fn ken_burns(mut self, ken_burns: KenBurns) -> Result<Self> {
+        ken_burns.check_fits(self.info)?;
+        self.ken_burns = Some(ken_burns);
+        Ok(self)
+    }
+
+    /// Blurs the motion of each frame like a camera's shutter, averaging `samples` blends
+    /// spread evenly over the time from the frame up to the next one, starting with the
+    /// frame's own. The easing curve applies to each of them, so the blur follows the speed
+    /// of the transition, and they are averaged in the color space being blended in.
+    ///
+    /// This takes `samples` times as long as blending each frame once, and a single sample
+    /// is no blur at all. The final frame showing the last image is never blurred.
+    pub fn motion_blur(mut self, samples: usize) -> Result<Self> {
+        ensure!(
+            samples > 0,
+            "Motion blur needs at least one sample per frame."
+        );
+        self.motion_blur = samples;
+        Ok(self)
+    }
+
+    /// The transitions making up the sequence in the order they are played, without the
+    /// final frames showing the last image at the end of a sequence which isn't cyclic.
+    ///
+    /// This is worked out from the images and frame counts alone, so it's cheap enough to
+    /// call before generating anything. Ping pong sequences include the transitions back
+    /// to the first image and looping ones the transition closing the loop.
+    pub fn segments(&self) -> impl Iterator<Item = Segment> + '_ {
+        self.segments.iter().copied()
+    }
+
+    /// The dimensions, color type and bit depth every frame is generated with
+    pub fn frame_info(&self) -> ImageInfo {
+        let color_type = match (self.background, self.info.color_type) {
+            (Some(_), ColorType::GrayAlpha) => ColorType::Gray,
+            (Some(_), ColorType::Rgba) => ColorType::Rgb,
+            (_, color_type) => color_type,
+        };
+        let (width, height) = match self.ken_burns {
+            Some(ken_burns) => (ken_burns.width, ken_burns.height),
+            None => (self.info.width, self.info.height),
+        };
+        ImageInfo {
+            color_type,
+            bit_depth: self.output_depth(),
+            width,
+            height,
+        }
+    }
+
+    /// Decodes every keyframe up front, several at once, rather than each one as the
+    /// transitions reach it, which shortens the start of runs with many large images
+    /// at the cost of holding them all in memory.
+    ///
+    /// When several images fail to decode the error is for the first of them.
+    pub fn preload(mut self) -> Result<Self> {
+        let images = self.keyframes.load_all()?;
+        debug!("Decoded all {} keyframes up front", images.len());
+        self.keyframes = Keyframes::Loaded(images);
+        if let Some(stack) = &mut self.stack {
+            stack.keyframes = Keyframes::Loaded(stack.keyframes.load_all()?);
+        }
+        Ok(self)
+    }
+
+    /// The number of keyframes currently decoded in memory, including any of a second stack
+    pub fn resident_keyframes(&self) -> usize {
+        let stack = self
+            .stack
+            .as_ref()
+            .map_or(0, |stack| stack.keyframes.resident());
+        self.keyframes.resident() + stack
+    }
+
+    /// Generates every frame of the sequence one after another, holding them all in memory,
+    /// this doesn't depend on or advance the state of the iterator.
+    ///
+    /// The first frame which fails to generate stops the rest, and its error is returned.
+    pub fn render_all(&self) -> Result<Vec<Image>> {
+        (0..self.total_frames()).map(|n| self.frame(n)).collect()
+    }
+
+    /// Generates every frame of the sequence in parallel in the same way as
+    /// `par_for_each_frame`, giving them back in order and holding them all in memory
+    pub fn render_all_par(&self) -> Result<Vec<Image>> {
+        let frames: Vec<OnceLock<Image>> =
+            (0..self.total_frames()).map(|_| OnceLock::new()).collect();
+        self.par_for_each_frame(|n, image| {
+            frames[n]
+                .set(image)
+                .map_err(|_| anyhow!("Frame {} was generated twice.", n))
+        })?;
+        Ok(frames
+            .into_iter()
+            .map(|frame| frame.into_inner().expect("every frame has been generated"))
+            .collect())
+    }
+
+    /// Generates every frame in parallel, passing each one to `f` along with its index
+    /// in the sequence, this doesn't depend on or advance the state of the iterator.
+    ///
+    /// The transitions are worked through in order, with every frame between one pair
+    /// of images finished before moving on to the next pair,
+    /// so lazily loaded images are each only decoded once.
+    pub fn par_for_each_frame<F>(&self, f: F) -> Result<()>
+    where
+        F: Fn(usize, Image) -> Result<()> + Sync,
+    {
+        self.par_for_each_frame_filtered(|_| true, f)
+    }
+
+    /// Generates frames in parallel in the same way as `par_for_each_frame`, except only the
+    /// frames whose index `keep` returns true for are generated and passed to `f`.
+    ///
+    /// Images are only decoded for the transitions with frames being kept.
+    pub fn par_for_each_frame_filtered<K, F>(&self, keep: K, f: F) -> Result<()>
+    where
+        K: Fn(usize) -> bool + Sync,
+        F: Fn(usize, Image) -> Result<()> + Sync,
+    {
+        self.par_for_each_frame_with_progress(keep, f, |_, _| {})
+    }
+
+    /// Generates frames in parallel in the same way as `par_for_each_frame_filtered`,
+    /// calling `progress` with the number of frames done so far and the total number of
+    /// frames each time another is finished, for driving a progress bar.
+    ///
+    /// Even though the frames finish out of order `progress` is called in frame order,
+    /// each count meaning every frame before it is done, so a frame finishing early is only
+    /// counted once all of the frames before it are. Frames which aren't kept are counted
+    /// as soon as they are reached.
+    pub fn par_for_each_frame_with_progress<K, F, P>(
+        &self,
+        keep: K,
+        f: F,
+        progress: P,
+    ) -> Result<()>
+    where
+        K: Fn(usize) -> bool + Sync,
+        F: Fn(usize, Image) -> Result<()> + Sync,
+        P: FnMut(usize, usize) + Send,
+    {
+        let progress = OrderedProgress::new(self.total_frames(), progress);
+        let frame = |n| {
+            if keep(n) {
+                f(n, self.frame(n)?)?;
+            }
+            progress.finish(n);
+            Ok(())
+        };
+
+        for segment in &self.segments {
+            segment.frames().into_par_iter().try_for_each(frame)?;
+        }
+
+        (self.transition_frames()..self.total_frames())
+            .into_par_iter()
+            .try_for_each(frame)
+    }
+
+    /// The number of frames produced over the whole sequence, including the final frame
+    /// showing just the last image, and any frames holding it, unless the sequence is cyclic
+    pub fn total_frames(&self) -> usize {
+        self.transition_frames() + if self.is_cyclic() { 0 } else { 1 + self.hold }
+    }
+
+    /// The number of frames produced by the transitions, without the final frames
+    fn transition_frames(&self) -> usize {
+        self.segments.iter().map(Segment::len).sum()
+    }
+
+    /// The index of an earlier frame identical to frame `n`, when it's known to be a copy
+    /// without generating either of them, such as the frames holding a keyframe.
+    /// Frames stamped with a burn-in are never copies, as each has its own label, and nor
+    /// are frames panned and zoomed across, which each show a different part of the image.
+    pub fn duplicate_of(&self, n: usize) -> Option<usize> {
+        if self.burn_in != BurnIn::None || self.ken_burns.is_some() {
+            return None;
+        }
+        let transition_frames = self.transition_frames();
+        if n > transition_frames && n < self.total_frames() {
+            return Some(transition_frames);
+        }
+
+        let segment = &self.segments[self.segments.partition_point(|s| s.first_frame <= n) - 1];
+        let offset = n - segment.first_frame;
+        if offset > 0 && offset <= segment.hold {
+            Some(segment.first_frame)
+        } else {
+            None
+        }
+    }
+
+    /// Generates the frame at index `n` in the sequence, or `None` when `n` is past the last
+    /// frame, this doesn't depend on or advance the state of the iterator
+    pub fn frame_at(&self, n: usize) -> Option<Result<Image>> {
+        if n < self.total_frames() {
+            Some(self.frame(n))
+        } else {
+            None
+        }
+    }
+
+    /// Generates the frame at index `n`, which must be less than `total_frames`
+    fn frame(&self, n: usize) -> Result<Image> {
+        let (pos, progress) = self.locate(n);
+        let segment = &self.segments[pos];
+        if n == segment.first_frame {
+            debug!(
+                "Segment {} starts at frame {}: keyframe {} to {} over {} frames",
+                pos,
+                n,
+                segment.start,
+                segment.end,
+                segment.len()
+            );
+        }
+        let image = self.blend_segment(pos, progress)?;
+        // the pan covers every frame, including any holding the last image
+        let position = n as f64 / (self.total_frames() - 1).max(1) as f64;
+        let mut image = self.pan_and_zoom(image, position);
+        if let Some(label) = self.burn_in.label(n, self.fps) {
+            // the label takes up around 4% of the height of the frame
+            let scale = (image.height / 240).max(1);
+            image.draw_text(&label, scale, self.burn_in_corner, 0.5);
+        }
+        Ok(image)
+    }
+
+    /// The index of the segment frame `n` belongs to, and the progress through it before
+    /// easing, `n` must be less than `total_frames`
+    fn locate(&self, n: usize) -> (usize, f64) {
+        // the final frame is the image at the end of the last transition
+        let pos = self.segments.partition_point(|s| s.first_frame <= n) - 1;
+        let segment = &self.segments[pos];
+        let offset = (n - segment.first_frame).saturating_sub(segment.hold);
+        (pos, offset as f64 / segment.steps as f64)
+    }
+
+    /// Saves the frame at index `n` as a PNG at `p` like `frame_at`, except the keyframes
+    /// are decoded, blended and encoded `strip_rows` rows at a time so only a few strips are
+    /// ever held in memory rather than whole images.
+    ///
+    /// This needs the keyframes to be non-interlaced PNGs loaded from their paths
+    /// without resizing, and only supports linear interpolation without dithering.
+    pub fn save_frame_in_strips<P>(&self, n: usize, p: P, strip_rows: u32) -> Result<()>
+    where
+        P: AsRef<Path> + Debug,
+    {
+        self.save_frame_in_strips_with(n, p, strip_rows, &PngOptions::default())
+    }
+
+    /// Saves the frame at index `n` in strips in the same way as `save_frame_in_strips`,
+    /// encoded with `options`
+    pub fn save_frame_in_strips_with<P>(
+        &self,
+        n: usize,
+        p: P,
+        strip_rows: u32,
+        options: &PngOptions,
+    ) -> Result<()>
+    where
+        P: AsRef<Path> + Debug,
+    {
+        ensure!(
+            n < self.total_frames(),
+            "Frame {} is past the end of the {} frames.",
+            n,
+            self.total_frames()
+        );
+        ensure!(
+            self.interpolation == Interpolation::Linear && self.dither == Dither::None,
+            "Frames can only be generated in strips with linear interpolation and no dithering."
+        );
+        ensure!(
+            self.background.is_none(),
+            "Frames can't be generated in strips when composited over a background."
+        );
+        ensure!(
+            self.output_depth() == self.info.bit_depth,
+            "Frames can only be generated in strips at the bit depth of the images."
+        );
+        ensure!(
+            self.transition == Transition::Dissolve,
+            "Frames can only be generated in strips with the dissolve transition."
+        );
+        ensure!(
+            self.stack.is_none(),
+            "Frames can't be generated in strips when blended with a second stack."
+        );
+        ensure!(
+            self.burn_in == BurnIn::None,
+            "Frames can't be generated in strips with a burn-in."
+        );
+        ensure!(
+            self.region.is_none(),
+            "Frames can't be generated in strips when only a region is interpolated."
+        );
+        ensure!(
+            self.ken_burns.is_none(),
+            "Frames can't be generated in strips while panning and zooming across them."
+        );
+        ensure!(
+            self.motion_blur == 1,
+            "Frames can't be generated in strips with motion blur."
+        );
+
+        let (pos, progress) = self.locate(n);
+        let segment = &self.segments[pos];
+        let source = |i| {
+            self.keyframes.unmodified_path(i).ok_or_else(|| {
+                anyhow!("Frames can only be generated in strips from image files loaded without resizing or promotion.")
+            })
+        };
+        let (path, info, profile) = (p.as_ref(), self.info, &self.profile);
+        let render = |sources: &[&Path], combine: &dyn Fn(Vec<Image>) -> Image| {
+            render_in_strips(sources, info, profile, options, path, strip_rows, combine)
+        };
+        let copy = |mut strips: Vec<Image>| strips.remove(0);
+        if progress >= 1.0 {
+            render(&[source(segment.end)?], &copy)
+        } else if progress <= 0.0 {
+            render(&[source(segment.start)?], &copy)
+        } else {
+            let mu = self.ease(progress);
+            render(&[source(segment.start)?, source(segment.end)?], &|strips| {
+                self.blend_linear(progress, mu, &strips[0], &strips[1])
+            })
+        }
+    }
+
+    /// Generates the image at `position` through the whole sequence, from `0.0` on the
+    /// first image to `1.0` on the image the sequence ends on.
+    ///
+    /// This isn't limited to the frames the sequence is split into, each transition taking
+    /// up a share of the sequence in proportion to its number of frames, so the image
+    /// isn't stamped with any burn-in.
+    pub fn frame_at_position(&self, position: f64) -> Result<Image> {
+        ensure!(
+            (0.0..=1.0).contains(&position),
+            "The position must be between 0 and 1, got {}.",
+            position
+        );
+
+        let frame = position * self.transition_frames() as f64;
+        let pos = self
+            .segments
+            .partition_point(|s| s.first_frame as f64 <= frame)
+            - 1;
+        let segment = &self.segments[pos];
+        let offset = (frame - segment.first_frame as f64 - segment.hold as f64).max(0.0);
+        let image = self.blend_segment(pos, (offset / segment.steps as f64).min(1.0))?;
+        Ok(self.pan_and_zoom(image, position))
+    }
+
+    /// Samples `image` through the pan and zoom at `position` through the whole sequence,
+    /// if there is one
+    fn pan_and_zoom(&self, image: Image, position: f64) -> Image {
+        match &self.ken_burns {
+            Some(ken_burns) => image.pan_and_zoom(ken_burns, self.ease(position)),
+            None => image,
+        }
+    }
+
+    /// Generates the image `progress` of the way through the segment at index `pos`,
+    /// before easing, from `0.0` on its start image to `1.0` on its end image
+    fn blend_segment(&self, pos: usize, progress: f64) -> Result<Image> {
+        let image = self.blend_segment_data(pos, progress)?;
+        let image = match self.background {
+            Some(background) => image.composite_over(background),
+            None => image,
+        };
+        let image = match (image.data.bit_depth(), self.output_depth()) {
+            (BitDepth::Sixteen | BitDepth::Float, BitDepth::Eight) => {
+                image.dither_to_eight_bit(self.dither, self.seed)
+            }
+            (BitDepth::Eight | BitDepth::Float, BitDepth::Sixteen) => image.to_sixteen_bit(),
+            (BitDepth::Eight | BitDepth::Sixteen, BitDepth::Float) => image.to_float(),
+            _ => image,
+        };
+        Ok(image.with_color_profile(self.profile.clone()))
+    }
+
+    /// The bit depth frames are generated at
+    fn output_depth(&self) -> BitDepth {
+        self.output_bit_depth.unwrap_or(self.info.bit_depth)
+    }
+
+    /// Generates the pixels of a frame for `blend_segment`, averaging blends spread across
+    /// the frame's share of the transition when there's motion blur
+    fn blend_segment_data(&self, pos: usize, progress: f64) -> Result<Image> {
+        // dithering needs the blend at more than 8 bits of precision, so 8 bit keyframes
+        // are blended at 16 bits and the result dithered back down by `blend_segment`,
+        // as they are for 16 bit or float output to keep the precision of the blend, and when
+        // blending with a second stack or averaging motion blur so the frames are only
+        // rounded once
+        let widen = self.info.bit_depth == BitDepth::Eight
+            && (self.dither != Dither::None
+                || self.output_depth() != BitDepth::Eight
+                || self.stack.is_some()
+                || self.motion_blur > 1);
+
+        let image = self.blend_stacks(pos, progress, widen)?;
+        // the sequence comes to rest on the last image, so there's nothing to blur
+        if self.motion_blur == 1 || progress >= 1.0 {
+            return Ok(image);
+        }
+
+        // the samples are spread evenly from this frame up to the next one, and averaged
+        // in the color space being blended in
+        let widened = |image: Image| match image.data.bit_depth() {
+            BitDepth::Eight if widen => image.to_sixteen_bit(),
+            _ => image,
+        };
+        let slice = 1.0 / (self.segments[pos].steps * self.motion_blur) as f64;
+        let mut mean = Mean::new(widened(image), self.color_space, self.gamma_correct);
+        for k in 1..self.motion_blur {
+            let sample_progress = (progress + k as f64 * slice).min(1.0);
+            mean.add(&widened(self.blend_stacks(pos, sample_progress, widen)?));
+        }
+        Ok(mean.finish())
+    }
+
+    /// Generates the pixels `progress` of the way through the segment at index `pos` for
+    /// `blend_segment_data`, blended with those of the second stack if there is one
+    fn blend_stacks(&self, pos: usize, progress: f64, widen: bool) -> Result<Image> {
+        let image = self.blend_keyframes(&self.keyframes, &self.flows, pos, progress, widen)?;
+        let stack = match &self.stack {
+            Some(stack) => stack,
+            None => return Ok(image),
+        };
+        let other = self.blend_keyframes(&stack.keyframes, &stack.flows, pos, progress, widen)?;
+        // the keyframes themselves come back as they are, at their own bit depth
+        let (image, other) = if widen {
+            (image.to_sixteen_bit(), other.to_sixteen_bit())
+        } else {
+            (image, other)
+        };
+        Ok(interpolate(
+            stack.weight,
+            &image,
+            &other,
+            self.color_space,
+            self.gamma_correct,
+        ))
+    }
+
+    /// Generates the pixels of a frame `progress` of the way through the segment at index
+    /// `pos` from one stack of `keyframes`, at 16 bits when `widen` is set unless it's
+    /// one of the keyframes
+    fn blend_keyframes(
+        &self,
+        keyframes: &Keyframes,
+        flows: &FlowCache,
+        pos: usize,
+        progress: f64,
+        widen: bool,
+    ) -> Result<Image> {
+        let image = self.blend_region(keyframes, flows, pos, progress, widen)?;
+        let region = match self.region {
+            Some(region) => region,
+            None => return Ok(image),
+        };
+
+        let start = keyframes.get(self.segments[pos].start)?;
+        let mut frame = if image.data.bit_depth() == start.data.bit_depth() {
+            Image::clone(&start)
+        } else {
+            start.to_sixteen_bit()
+        };
+        frame.paste(&image, region.x, region.y);
+        Ok(frame)
+    }
+
+    /// Generates the pixels within the region being interpolated for `blend_keyframes`,
+    /// which is the whole frame unless a region is set
+    fn blend_region(
+        &self,
+        keyframes: &Keyframes,
+        flows: &FlowCache,
+        pos: usize,
+        progress: f64,
+        widen: bool,
+    ) -> Result<Image> {
+        let get = |i| -> Result<Arc<Image>> {
+            let image = keyframes.get(i)?;
+            Ok(match self.region {
+                Some(region) => Arc::new(image.crop(region)),
+                None => image,
+            })
+        };
+
+        let segment = &self.segments[pos];
+        if progress >= 1.0 {
+            let end = get(segment.end)?;
+            return Ok(Image::clone(&end));
+        }
+
+        let start = get(segment.start)?;
+        if progress <= 0.0 {
+            return Ok(Image::clone(&start));
+        }
+
+        // a transition between two identical images is that image all the way through,
+        // unless the curve is bent by the images either side, a blend mode combines
+        // the image with itself or the image slides across the frame
+        let end = get(segment.end)?;
+        if self.interpolation != Interpolation::CatmullRom
+            && self.blend_mode == BlendMode::Mix
+            && !self.transition.moves_pixels()
+            && (Arc::ptr_eq(&start, &end) || start == end)
+        {
+            return Ok(Image::clone(&start));
+        }
+
+        let prepare = |image: Arc<Image>| {
+            if widen {
+                Arc::new(image.to_sixteen_bit())
+            } else {
+                image
+            }
+        };
+
+        let start = prepare(start);
+        let end = prepare(end);
+        let mu = self.ease(progress);
+        let image = match self.interpolation {
+            Interpolation::Linear if self.transition != Transition::Dissolve => {
+                self.transition.compose(mu, &start, &end, self.feather)
+            }
+            Interpolation::Linear => self.blend_linear(progress, mu, &start, &end),
+            Interpolation::CatmullRom => {
+                let (before, after) = self.neighbours(pos);
+                let before = prepare(get(before)?);
+                let after = prepare(get(after)?);
+                interpolate_catmull_rom(mu, [&before, &start, &end, &after], self.gamma_correct)
+            }
+            Interpolation::Morph => {
+                let flow = flows.get((segment.start, segment.end), &start, &end);
+                morph(
+                    mu,
+                    &start,
+                    &end,
+                    &flow,
+                    self.color_space,
+                    self.gamma_correct,
+                )
+            }
+        };
+
+        Ok(image)
+    }
+
+    /// The eased progress `progress` of the way through a transition
+    fn ease(&self, progress: f64) -> f64 {
+        match &self.easing_table {
+            Some(table) => table.apply(progress),
+            None => self.easing.apply(progress),
+        }
+    }
+
+    /// Blends straight from `start` to `end`, `progress` of the way through the transition
+    /// which eases to `mu`
+    fn blend_linear(&self, progress: f64, mu: f64, start: &Image, end: &Image) -> Image {
+        let per_channel = self.channel_easing.iter().any(Option::is_some)
+            && self.info.color_type.color_channels() == 3;
+        if self.premultiplied
+            && self.color_space == ColorSpace::Rgb
+            && self.blend_mode == BlendMode::Mix
+            && !per_channel
+            && self.info.color_type.has_alpha()
+        {
+            interpolate_premultiplied(mu, start, end, self.gamma_correct)
+        } else if self.color_space == ColorSpace::Rgb
+            && (per_channel || self.blend_mode != BlendMode::Mix)
+        {
+            let mus = if per_channel {
+                self.channel_easing
+                    .map(|easing| easing.map_or_else(|| self.ease(progress), |e| e.apply(progress)))
+            } else {
+                [mu; 3]
+            };
+            interpolate_per_channel(self.blend_mode, mus, mu, start, end, self.gamma_correct)
+        } else {
+            interpolate(mu, start, end, self.color_space, self.gamma_correct)
+        }
+    }
+
+    /// The keyframes played just before and just after the segment at index `pos`,
+    /// wrapping around cyclic sequences and repeating the end images of the others
+    fn neighbours(&self, pos: usize) -> (usize, usize) {
+        let segment = &self.segments[pos];
+        let last = self.segments.len() - 1;
+        let before = match pos.checked_sub(1) {
+            Some(prev) => self.segments[prev].start,
+            None if self.is_cyclic() => self.segments[last].start,
+            None => segment.start,
+        };
+        let after = if pos < last {
+            self.segments[pos + 1].end
+        } else if self.is_cyclic() {
+            self.segments[0].end
+        } else {
+            segment.end
+        };
+        (before, after)
+    }
+}
+
+/// Reports the frames finished in parallel to a callback in frame order
+struct OrderedProgress<P> {
+    state: Mutex<ProgressState<P>>,
+}
+
+struct ProgressState<P> {
+    /// Which frames have finished, including those not yet reported
+    finished: Vec<bool>,
+    /// The number of frames reported, which all come before the first unfinished frame
+    done: usize,
+    callback: P,
+}
+
+impl<P: FnMut(usize, usize)> OrderedProgress<P> {
+    fn new(total: usize, callback: P) -> Self {
+        Self {
+            state: Mutex::new(ProgressState {
+                finished: vec![false; total],
+                done: 0,
+                callback,
+            }),
+        }
+    }
+
+    /// Marks frame `n` as finished, reporting it and any finished frames waiting on it
+    fn finish(&self, n: usize) {
+        let mut state = self.state.lock().unwrap();
+        let ProgressState {
+            finished,
+            done,
+            callback,
+        } = &mut *state;
+        finished[n] = true;
+        while finished.get(*done) == Some(&true) {
+            *done += 1;
+            callback(*done, finished.len());
+        }
+    }
+}
+
+/// A single transition from one keyframe to another
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Segment {
+    /// The index of the keyframe the transition starts on
+    pub start: usize,
+    /// The index of the keyframe the transition ends on
+    pub end: usize,
+    /// The number of extra frames showing the start keyframe before blending begins
+    pub hold: usize,
+    /// The number of frames in the transition
+    pub steps: usize,
+    /// The index of the transition's first frame in the whole sequence
+    pub first_frame: usize,
+}
+
+impl Segment {
+    /// The number of frames in the transition including those holding its start keyframe
+    pub fn len(&self) -> usize {
+        self.hold + self.steps
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The indices of the transition's frames in the whole sequence
+    pub fn frames(&self) -> Range<usize> {
+        self.first_frame..self.first_frame + self.len()
+    }
+}
+
+/// The info of the keyframe nearest to index `i` which has any
+fn nearest_info(infos: &[Option<ImageInfo>], i: usize) -> Option<ImageInfo> {
+    infos
+        .iter()
+        .enumerate()
+        .filter_map(|(j, info)| info.map(|info| (i.abs_diff(j), info)))
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, info)| info)
+}
+
+/// Checks that there are enough images to interpolate between and that they all have
+/// the same dimensions, color type and bit depth, naming the first image which doesn't
+/// match the first one
+fn check_consistent(infos: impl IntoIterator<Item = (String, ImageInfo)>) -> Result<()> {
+    let infos: Vec<_> = infos.into_iter().collect();
+    ensure!(
+        infos.len() >= 2,
+        "At least two images are needed to interpolate between."
+    );
+
+    // blending images of different shapes, channels or depths makes no sense
+    let (first, expected) = &infos[0];
+    if let Some((name, info)) = infos[1..].iter().find(|(_, info)| info != expected) {
+        return Err(InterpolError::Mismatch {
+            name: name.clone(),
+            found: *info,
+            expected_name: first.clone(),
+            expected: *expected,
+        }
+        .into());
+    }
+
+    Ok(())
+}
+
+impl Iterator for Interpolator {
+    type Item = Result<Image>;
+
+    /// Generates the next frame, a keyframe which fails to load giving an error for that
+    /// frame rather than a panic. The frame is still passed over, so iterating can carry
+    /// on to the frames after it.
+    fn next(&mut self) -> Option<Result<Image>> {
+        trace!("Generating frame {}", self.frame_no);
+        let image = self.frame_at(self.frame_no)?;
+        self.frame_no += 1;
+        Some(image)
+    }
+
+    /// Skips straight to the frame `n` frames ahead without generating the ones in between
+    fn nth(&mut self, n: usize) -> Option<Result<Image>> {
+        self.frame_no = self.frame_no.saturating_add(n).min(self.total_frames());
+        self.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.total_frames().saturating_sub(self.frame_no);
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for Interpolator {}
+
+/// Splits `budget` frames between transitions in proportion to their `weights`, after
+/// giving each one a frame, with the frames left over from rounding going to those which
+/// lost the most to it. Equal weights all round, including all zero, split it evenly.
+fn share_out(budget: usize, weights: &[f64]) -> Vec<usize> {
+    let total: f64 = weights.iter().sum();
+    let weights: Vec<_> = if total > 0.0 {
+        weights.iter().map(|w| w / total).collect()
+    } else {
+        vec![1.0 / weights.len() as f64; weights.len()]
+    };
+
+    let spare = budget.saturating_sub(weights.len());
+    let shares: Vec<_> = weights.iter().map(|w| w * spare as f64).collect();
+    let mut steps: Vec<_> = shares
+        .iter()
+        .map(|share| 1 + share.floor() as usize)
+        .collect();
+
+    let leftover = spare
+        - shares
+            .iter()
+            .map(|share| share.floor() as usize)
+            .sum::<usize>();
+    let mut by_remainder: Vec<_> = (0..steps.len()).collect();
+    by_remainder.sort_by(|&a, &b| shares[b].fract().total_cmp(&shares[a].fract()));
+    for &i in &by_remainder[..leftover] {
+        steps[i] += 1;
+    }
+    steps
+}
+
+/// Collects the results of a parallel iterator in order, failing with the error
+/// of the earliest item which fails rather than whichever happened to fail first
+fn collect_in_order<T: Send>(
+    iter: impl IndexedParallelIterator<Item = Result<T>>,
+) -> Result<Vec<T>> {
+    let results: Vec<_> = iter.collect();
+    results.into_iter().collect()
+}
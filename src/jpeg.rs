@@ -0,0 +1,108 @@
+#[cfg(feature = "jpeg")]
+use std::{fs::File, io::BufReader};
+use std::{io::Read, path::Path};
+
+use anyhow::{anyhow, Result};
+#[cfg(feature = "jpeg")]
+use jpeg_decoder::{Decoder, PixelFormat};
+
+use crate::{error::open_image, Image, ImageInfo};
+#[cfg(feature = "jpeg")]
+use crate::{error::DecodeContext, BitDepth, ColorType, Samples};
+
+/// The bytes every JPEG file starts with
+const SIGNATURE: [u8; 3] = [0xFF, 0xD8, 0xFF];
+
+/// Checks the first bytes of the file at `path` to see whether it is a JPEG
+pub(crate) fn is_jpeg(path: &Path) -> Result<bool> {
+    let mut file = open_image(path)?;
+    let mut signature = [0; 3];
+    match file.read_exact(&mut signature) {
+        Ok(()) => Ok(signature == SIGNATURE),
+        // files too short to be a JPEG are left for the PNG decoder to report
+        Err(_) => Ok(false),
+    }
+}
+
+/// Opens the JPEG file at `path` and reads its header,
+/// checking that the image is in a format which can be interpolated
+#[cfg(feature = "jpeg")]
+fn open_jpeg(path: &Path) -> Result<(Decoder<BufReader<File>>, ImageInfo)> {
+    let file = open_image(path)?;
+    let mut decoder = Decoder::new(BufReader::new(file));
+    decoder.read_info().decode_context(path, || {
+        format!("Decoder failed to read information from {:?}", path)
+    })?;
+    let info = decoder
+        .info()
+        .ok_or_else(|| anyhow!("Decoder failed to read information from {:?}", path))?;
+
+    // grayscale images are expanded to RGB so they blend with color ones
+    let bit_depth = match info.pixel_format {
+        PixelFormat::L8 | PixelFormat::RGB24 => BitDepth::Eight,
+        PixelFormat::L16 => BitDepth::Sixteen,
+        PixelFormat::CMYK32 => {
+            return Err(anyhow!(
+                "Unsupported color type CMYK in {:?}, only grayscale and RGB JPEGs are supported.",
+                path
+            ))
+        }
+    };
+
+    let info = ImageInfo {
+        width: info.width as u32,
+        height: info.height as u32,
+        color_type: ColorType::Rgb,
+        bit_depth,
+    };
+    Ok((decoder, info))
+}
+
+/// Reads just the header of the JPEG at `path`
+#[cfg(feature = "jpeg")]
+pub(crate) fn read_info(path: &Path) -> Result<ImageInfo> {
+    let (_, info) = open_jpeg(path)?;
+    Ok(info)
+}
+
+/// Decodes the JPEG at `path` into an RGB image
+#[cfg(feature = "jpeg")]
+pub(crate) fn decode(path: &Path) -> Result<Image> {
+    let (mut decoder, info) = open_jpeg(path)?;
+    let pixels = decoder
+        .decode()
+        .decode_context(path, || format!("Decoder failed to decode {:?}", path))?;
+
+    let data = match decoder.info().map(|info| info.pixel_format) {
+        Some(PixelFormat::L8) => Samples::Eight(pixels.iter().flat_map(|&l| [l; 3]).collect()),
+        // the decoder produces 16 bit luma in the platform's byte order
+        Some(PixelFormat::L16) => Samples::Sixteen(
+            pixels
+                .chunks_exact(2)
+                .flat_map(|b| [u16::from_ne_bytes([b[0], b[1]]); 3])
+                .collect(),
+        ),
+        _ => Samples::Eight(pixels),
+    };
+    Image::new_from_parts(data, info.color_type, info.width, info.height)
+}
+
+// JPEGs are still recognised without the decoder, so they can be reported clearly
+// rather than failing as broken PNGs
+#[cfg(not(feature = "jpeg"))]
+pub(crate) fn read_info(path: &Path) -> Result<ImageInfo> {
+    Err(disabled(path))
+}
+
+#[cfg(not(feature = "jpeg"))]
+pub(crate) fn decode(path: &Path) -> Result<Image> {
+    Err(disabled(path))
+}
+
+#[cfg(not(feature = "jpeg"))]
+fn disabled(path: &Path) -> anyhow::Error {
+    anyhow!(
+        "{:?} is a JPEG, which can only be decoded when interpol is built with the \"jpeg\" feature.",
+        path
+    )
+}
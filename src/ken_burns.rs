@@ -0,0 +1,48 @@
+use anyhow::{ensure, Result};
+
+use crate::{Filter, Image, ImageInfo, Region};
+
+/// A pan and zoom over the frames, each frame showing only the part of itself within a
+/// rectangle which moves from `start` to `end` over the sequence, scaled to `width` by
+/// `height` pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KenBurns {
+    /// The part of the first frame which is shown
+    pub start: Region,
+    /// The part of the last frame which is shown
+    pub end: Region,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl KenBurns {
+    /// Checks that both rectangles lie within images like `info` and that the frames
+    /// they are scaled to have some pixels
+    pub fn check_fits(&self, info: ImageInfo) -> Result<()> {
+        self.start.check_fits(info)?;
+        self.end.check_fits(info)?;
+        ensure!(
+            self.width > 0 && self.height > 0,
+            "The frames panned and zoomed across must have a non-zero width and height."
+        );
+        Ok(())
+    }
+
+    /// The rectangle `mu` of the way from `start` to `end`, as its left and top edges then
+    /// its width and height, with each moving in a straight line between the two
+    fn rect_at(&self, mu: f64) -> [f64; 4] {
+        let rect = |r: Region| [r.x, r.y, r.width, r.height].map(|v| v as f64);
+        let (start, end) = (rect(self.start), rect(self.end));
+        [0, 1, 2, 3].map(|i| start[i] + (end[i] - start[i]) * mu)
+    }
+}
+
+impl Image {
+    /// Samples the rectangle `mu` of the way through the pan and zoom `ken_burns` from the
+    /// image, blending the pixels either side of its edges so that they move smoothly
+    /// rather than jumping a whole pixel at a time
+    pub(crate) fn pan_and_zoom(&self, ken_burns: &KenBurns, mu: f64) -> Image {
+        let rect = ken_burns.rect_at(mu);
+        self.sample_rect(rect, ken_burns.width, ken_burns.height, Filter::Bilinear)
+    }
+}
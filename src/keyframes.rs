@@ -0,0 +1,255 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{anyhow, Result};
+use rayon::prelude::*;
+
+use crate::{animation::Animations, Image, ImageInfo, ImageLoader, SolidColor};
+
+/// The number of lazily loaded keyframes kept decoded at any one time by default,
+/// which is enough to hold both ends of the current transition
+pub(crate) const CACHE_SIZE: usize = 2;
+
+/// A lazily loaded keyframe in the cache, empty until it has been decoded and locked while
+/// it is being decoded, so threads wanting the same keyframe wait for it rather than
+/// decoding it again
+type Slot = Mutex<Option<Arc<Image>>>;
+
+/// The images an `Interpolator` blends between
+#[derive(Debug)]
+pub(crate) enum Keyframes {
+    /// Every image decoded up front
+    Loaded(Vec<Arc<Image>>),
+    /// Images decoded from their paths on demand,
+    /// only the most recently used are kept around
+    Lazy {
+        sources: Vec<Source>,
+        loader: ImageLoader,
+        /// The decoded images along with their index, most recently used last. This is
+        /// only locked to find an image's slot, which is locked in turn to decode it, so
+        /// decoding one keyframe never holds up fetching another.
+        cache: Mutex<Vec<(usize, Arc<Slot>)>>,
+        /// The maximum number of images kept in the cache
+        capacity: usize,
+        /// The animations `Source::Frame` keyframes are drawn from
+        animations: Animations,
+    },
+}
+
+/// Where a lazily loaded keyframe comes from
+#[derive(Debug, Clone)]
+pub(crate) enum Source {
+    /// An image file, transformed by the loader as it is decoded
+    File(PathBuf),
+    /// A frame of an animated image file, given by its index in the animation
+    Frame(PathBuf, usize),
+    /// A flat color, generated at the size and format of the other keyframes
+    Solid(SolidColor, ImageInfo),
+}
+
+impl Keyframes {
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Keyframes::Loaded(images) => images.len(),
+            Keyframes::Lazy { sources, .. } => sources.len(),
+        }
+    }
+
+    /// The file keyframe `i` is decoded from, if it is used exactly as it is stored there
+    pub(crate) fn unmodified_path(&self, i: usize) -> Option<&Path> {
+        match self {
+            Keyframes::Loaded(_) => None,
+            Keyframes::Lazy {
+                sources, loader, ..
+            } => match &sources[i] {
+                Source::File(path) => Some(path.as_path()).filter(|_| loader.is_identity()),
+                Source::Frame(..) | Source::Solid(..) => None,
+            },
+        }
+    }
+
+    /// Fetches the keyframe at index `i`, decoding it if it isn't already in memory
+    pub(crate) fn get(&self, i: usize) -> Result<Arc<Image>> {
+        match self {
+            Keyframes::Loaded(images) => Ok(Arc::clone(&images[i])),
+            Keyframes::Lazy {
+                sources,
+                loader,
+                cache,
+                capacity,
+                animations,
+            } => {
+                let slot = {
+                    let mut cache = cache.lock().unwrap();
+                    match cache.iter().position(|(n, _)| *n == i) {
+                        Some(pos) => {
+                            let entry = cache.remove(pos);
+                            let slot = Arc::clone(&entry.1);
+                            cache.push(entry);
+                            slot
+                        }
+                        None => {
+                            // evict before decoding so the cache never exceeds its size
+                            if cache.len() >= *capacity {
+                                cache.remove(0);
+                            }
+                            let slot = Arc::default();
+                            cache.push((i, Arc::clone(&slot)));
+                            slot
+                        }
+                    }
+                };
+
+                // a slot left empty by a failed decode is tried again by the next caller
+                let mut slot = slot.lock().unwrap();
+                if let Some(image) = &*slot {
+                    return Ok(Arc::clone(image));
+                }
+                let image = Arc::new(sources[i].decode(loader, animations)?);
+                *slot = Some(Arc::clone(&image));
+                Ok(image)
+            }
+        }
+    }
+
+    /// Decodes every keyframe at once, spread across threads, giving them in order.
+    ///
+    /// When several fail the error is the one for the earliest of them.
+    pub(crate) fn load_all(&self) -> Result<Vec<Arc<Image>>> {
+        match self {
+            Keyframes::Loaded(images) => Ok(images.clone()),
+            Keyframes::Lazy {
+                sources,
+                loader,
+                animations,
+                ..
+            } => {
+                // the frames of an animation are drawn one after another, so each animation
+                // is decoded in a single pass rather than a frame at a time
+                let mut paths: Vec<&Path> = sources
+                    .iter()
+                    .filter_map(|source| match source {
+                        Source::Frame(path, _) => Some(path.as_path()),
+                        Source::File(_) | Source::Solid(..) => None,
+                    })
+                    .collect();
+                paths.dedup();
+                let (mut frames, images): (HashMap<_, _>, Vec<_>) = rayon::join(
+                    || {
+                        paths
+                            .par_iter()
+                            .map(|&path| {
+                                let frames = Image::frames_from_path(path).map(|frames| {
+                                    frames
+                                        .into_par_iter()
+                                        .map(|frame| Arc::new(loader.transform(frame)))
+                                        .collect::<Vec<_>>()
+                                });
+                                (path, frames)
+                            })
+                            .collect()
+                    },
+                    || {
+                        sources
+                            .par_iter()
+                            .map(|source| match source {
+                                Source::Frame(..) => None,
+                                source => Some(source.decode(loader, animations).map(Arc::new)),
+                            })
+                            .collect()
+                    },
+                );
+
+                sources
+                    .iter()
+                    .zip(images)
+                    .map(|(source, image)| match (source, image) {
+                        (_, Some(image)) => image,
+                        (Source::Frame(path, index), None) => match &frames[path.as_path()] {
+                            Ok(images) => images
+                                .get(*index)
+                                .cloned()
+                                .ok_or_else(|| anyhow!("{:?} has no frame {}.", path, index)),
+                            // only the first of its frames reports the error
+                            Err(_) => Err(frames
+                                .remove(path.as_path())
+                                .expect("the animation was just found")
+                                .unwrap_err()),
+                        },
+                        (_, None) => unreachable!("only frames are decoded separately"),
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Sets how many lazily loaded keyframes are kept decoded at once,
+    /// this has no effect when every image is already loaded
+    pub(crate) fn set_capacity(&mut self, n: usize) {
+        if let Keyframes::Lazy {
+            cache, capacity, ..
+        } = self
+        {
+            let cache = cache.get_mut().unwrap();
+            let excess = cache.len().saturating_sub(n);
+            cache.drain(..excess);
+            *capacity = n;
+        }
+    }
+
+    /// The number of keyframes currently decoded in memory
+    pub(crate) fn resident(&self) -> usize {
+        match self {
+            Keyframes::Loaded(images) => images.len(),
+            Keyframes::Lazy { cache, .. } => cache
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(_, slot)| slot.lock().unwrap().is_some())
+                .count(),
+        }
+    }
+}
+
+impl Source {
+    fn decode(&self, loader: &ImageLoader, animations: &Animations) -> Result<Image> {
+        match self {
+            Source::File(path) => loader.load(path),
+            Source::Frame(path, index) => Ok(loader.transform(animations.frame(path, *index)?)),
+            Source::Solid(color, info) => Ok(color.image(*info)),
+        }
+    }
+}
+
+impl Clone for Keyframes {
+    fn clone(&self) -> Self {
+        match self {
+            Keyframes::Loaded(images) => Keyframes::Loaded(images.clone()),
+            Keyframes::Lazy {
+                sources,
+                loader,
+                cache,
+                capacity,
+                ..
+            } => Keyframes::Lazy {
+                sources: sources.clone(),
+                loader: loader.clone(),
+                // the clone gets its own slots, so the two never wait on each other
+                cache: Mutex::new(
+                    cache
+                        .lock()
+                        .unwrap()
+                        .iter()
+                        .map(|(n, slot)| (*n, Arc::new(Mutex::new(slot.lock().unwrap().clone()))))
+                        .collect(),
+                ),
+                capacity: *capacity,
+                // as do its animations, which start again from their first frames
+                animations: Animations::default(),
+            },
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! A small library for interpolating between two or more images,
+//! producing the intermediate frames for making into a video.
+//!
+//! # Errors
+//!
+//! Every fallible function returns [`anyhow::Result`] rather than a typed error, so the
+//! messages can carry the context of where a failure happened, e.g. which frame was being
+//! saved. The failures a caller may want to handle differently, such as a missing, empty
+//! or truncated file, carry an [`InterpolError`] under that context, so they don't show in
+//! any signature and have to be looked for with `downcast_ref`:
+//!
+//! ```no_run
+//! use interpol::{Image, InterpolError};
+//!
+//! match Image::new_from_path("frame.png") {
+//!     Ok(image) => println!("{}x{}", image.width(), image.height()),
+//!     Err(e) => match e.downcast_ref::<InterpolError>() {
+//!         Some(InterpolError::Open { path, .. }) => eprintln!("{:?} couldn't be opened", path),
+//!         _ => eprintln!("{:#}", e),
+//!     },
+//! }
+//! ```
+
+mod animation;
+mod blend;
+mod bmp;
+mod burn_in;
+mod cache;
+mod composite;
+mod contact_sheet;
+mod dither;
+mod easing;
+mod error;
+mod exr;
+mod image;
+mod interpolator;
+mod jpeg;
+mod ken_burns;
+mod keyframes;
+mod loader;
+mod morph;
+mod name_pattern;
+mod orientation;
+mod output;
+#[cfg(feature = "gif")]
+mod palette;
+mod png_options;
+mod profile;
+mod promote;
+mod region;
+mod resize;
+mod sample;
+mod solid;
+mod spritesheet;
+mod strips;
+mod tiff;
+mod transition;
+#[cfg(feature = "gui")]
+mod window;
+
+pub use crate::{
+    blend::{smooth, smooth_in, BlendMode, ColorSpace, Interpolation},
+    burn_in::{BurnIn, Corner},
+    contact_sheet::ContactSheet,
+    dither::Dither,
+    easing::{Easing, EasingTable},
+    error::InterpolError,
+    image::{ColorType, Image, ImageInfo},
+    interpolator::{Interpolator, Segment},
+    ken_burns::KenBurns,
+    loader::ImageLoader,
+    name_pattern::NamePattern,
+    output::{save_apng, save_raw, Format, FrameDelay},
+    png_options::{Compression, PngFilter, PngOptions},
+    profile::ColorProfile,
+    promote::Promote,
+    region::Region,
+    resize::{Filter, Fit, Gravity, ResizeTarget},
+    sample::{Accumulator, BitDepth, Sample, Samples},
+    solid::SolidColor,
+    spritesheet::SpriteSheet,
+    transition::Transition,
+};
+
+#[cfg(feature = "video")]
+pub use crate::output::save_video;
+#[cfg(feature = "webp")]
+pub use crate::output::save_webp;
+#[cfg(feature = "gui")]
+pub use crate::window::PreviewWindow;
+#[cfg(feature = "gif")]
+pub use crate::{
+    output::{save_gif, save_gif_with_palette},
+    palette::Palette,
+};
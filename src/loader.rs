@@ -0,0 +1,107 @@
+use std::{
+    fmt::Debug,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+
+use crate::{cache::DecodeCache, Filter, Fit, Gravity, Image, ImageInfo, Promote};
+
+/// Describes how images are turned into keyframes as they are loaded
+#[derive(Debug, Clone, Default)]
+pub struct ImageLoader {
+    resize: Option<(u32, u32, Filter)>,
+    fit: Option<(Fit, Gravity, [u8; 3])>,
+    promote: Option<Promote>,
+    cache: Option<DecodeCache>,
+}
+
+impl ImageLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Resizes every image to `width` by `height` pixels as it is loaded
+    pub fn resize(mut self, width: u32, height: u32, filter: Filter) -> Self {
+        self.resize = Some((width, height, filter));
+        self
+    }
+
+    /// Fits images of a different aspect ratio to the size they are resized to by `fit`
+    /// rather than stretching them, see [`Image::resize_fit`]
+    pub fn fit(mut self, fit: Fit, gravity: Gravity, background: [u8; 3]) -> Self {
+        self.fit = Some((fit, gravity, background));
+        self
+    }
+
+    /// Promotes every image to a common color type as it is loaded, see [`Promote`],
+    /// so that images with different channels can be interpolated between
+    pub fn promote(mut self, promote: Promote) -> Self {
+        self.promote = Some(promote);
+        self
+    }
+
+    /// Keeps a copy of every image decoded in `dir`, which later loads of the same file
+    /// use instead of decoding it again for as long as the file isn't modified.
+    ///
+    /// The copies are of the images as they are decoded, before any resizing or promotion.
+    pub fn cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache = Some(DecodeCache::new(dir.into()));
+        self
+    }
+
+    /// Whether images are loaded exactly as they are stored
+    pub(crate) fn is_identity(&self) -> bool {
+        self.resize.is_none() && self.promote.is_none()
+    }
+
+    /// Decodes the image at `p` and applies any transformations to it
+    pub fn load<P>(&self, p: P) -> Result<Image>
+    where
+        P: AsRef<Path> + Debug,
+    {
+        let image = match &self.cache {
+            Some(cache) => cache.load(p.as_ref(), || Image::new_from_path(&p))?,
+            None => Image::new_from_path(p)?,
+        };
+        Ok(self.transform(image))
+    }
+
+    /// Applies the transformations to an image which has already been decoded
+    pub(crate) fn transform(&self, image: Image) -> Image {
+        let image = match self.resize {
+            Some((width, height, filter)) => match self.fit {
+                Some((fit, gravity, background)) => {
+                    image.resize_fit(width, height, filter, fit, gravity, background)
+                }
+                None => image.resize(width, height, filter),
+            },
+            None => image,
+        };
+        match self.promote {
+            Some(promote) => image.promote(promote),
+            None => image,
+        }
+    }
+
+    /// Reads the header of the image at `p`, giving the properties the image
+    /// will have once it has been loaded
+    pub fn info<P>(&self, p: P) -> Result<ImageInfo>
+    where
+        P: AsRef<Path> + Debug,
+    {
+        Ok(self.loaded_info(ImageInfo::from_path(p)?))
+    }
+
+    /// The properties an image described by `info` has once it has been loaded
+    pub(crate) fn loaded_info(&self, mut info: ImageInfo) -> ImageInfo {
+        if let Some((width, height, _)) = self.resize {
+            info.width = width;
+            info.height = height;
+        }
+        if let Some(promote) = self.promote {
+            info.color_type = promote.color_type(info.color_type);
+        }
+        info
+    }
+}
@@ -1,170 +1,2137 @@
+#[cfg(feature = "gui")]
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::{
-    fmt::Debug,
-    fs::{self, File},
-    io::BufWriter,
+    collections::HashMap,
+    env, fs,
+    io::{self, BufRead, IsTerminal},
+    panic,
     path::{Path, PathBuf},
+    str::FromStr,
+    sync::{mpsc, Mutex},
+    thread,
 };
 
-use anyhow::{anyhow, ensure, Context, Result};
-use rayon::prelude::*;
-use rgb::{ComponentBytes, FromSlice};
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use indicatif::{ProgressBar, ProgressStyle};
+#[cfg(feature = "gif")]
+use interpol::Palette;
+#[cfg(feature = "gui")]
+use interpol::PreviewWindow;
+use interpol::{
+    BitDepth, BlendMode, BurnIn, ColorSpace, ColorType, Compression, ContactSheet, Corner, Dither,
+    Easing, EasingTable, Filter, Fit, Format, FrameDelay, Gravity, Image, ImageInfo, ImageLoader,
+    Interpolation, Interpolator, KenBurns, NamePattern, PngFilter, PngOptions, Promote, Region,
+    ResizeTarget, Samples, SolidColor, SpriteSheet, Transition,
+};
+use log::{debug, warn, LevelFilter};
+use rayon::{prelude::*, ThreadPool};
+use serde::{Deserialize, Serialize};
 use structopt::StructOpt;
 
-type Pixel = rgb::RGB<u8>;
-
 fn main() -> Result<()> {
-    let opt = Opt::from_args();
+    let opt = match parse_command() {
+        Command::Interpolate(opt) => opt,
+        Command::Info(info) => {
+            init_logging(0);
+            return print_info(&info);
+        }
+        Command::Concat(concat) => {
+            init_logging(concat.verbose);
+            return concat_frames(&concat);
+        }
+        Command::Check => {
+            init_logging(0);
+            return self_check();
+        }
+    };
+    init_logging(opt.verbose);
+
+    // the pool is only used for this run, without --threads it has a thread per core
+    ensure!(opt.threads != Some(0), "--threads must be at least 1.");
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(opt.threads.unwrap_or(0))
+        .build()
+        .context("Failed to start the thread pool")?;
+
+    match &opt.batch {
+        Some(batch) => pool.install(|| run_batch(batch, &opt, &pool)),
+        None => pool.install(|| run(opt)),
+    }
+}
+
+/// Parses the command line, which without a subcommand is taken to be `interpolate`
+/// as it was before there were any others
+fn parse_command() -> Command {
+    let first = env::args_os().nth(1);
+    match first.as_ref().and_then(|arg| arg.to_str()) {
+        Some("interpolate" | "info" | "concat" | "check" | "help") => Command::from_args(),
+        _ => Command::Interpolate(Opt::from_args()),
+    }
+}
+
+fn init_logging(verbose: u8) {
+    // RUST_LOG can still pick out individual modules or levels
+    env_logger::Builder::new()
+        .filter_level(match verbose {
+            0 => LevelFilter::Warn,
+            1 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        })
+        .parse_default_env()
+        .init();
+}
+
+/// Prints the properties of each image the way they will be loaded
+fn print_info(opt: &InfoOpt) -> Result<()> {
+    for path in &opt.images {
+        println!("{:?}: {}", path, ImageInfo::from_path(path)?);
+    }
+    Ok(())
+}
+
+/// Copies the frames out of each of the directories in turn into one sequence,
+/// numbering them afresh from the start of the first directory
+fn concat_frames(opt: &ConcatOpt) -> Result<()> {
+    let mut frames = Vec::new();
+    for dir in &opt.dirs {
+        let context = || format!("Failed to read the frames directory {:?}.", dir);
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(dir).with_context(context)? {
+            let path = entry.with_context(context)?.path();
+            if path.is_file() {
+                entries.push(path);
+            }
+        }
+        // the frames of a run are named in order, their numbers padded to the same width
+        entries.sort();
+        ensure!(!entries.is_empty(), "There are no frames in {:?}.", dir);
+        // each run but the first starts on the frame the one before it ended on
+        let skip = usize::from(opt.skip_repeated && !frames.is_empty());
+        frames.extend(entries.into_iter().skip(skip));
+    }
+
+    prepare_outdir(&opt.outdir, opt.force, false)?;
+    let total_frames = frames.len();
+    for (n, frame) in frames.iter().enumerate() {
+        let path = opt.outdir.join(opt.name_pattern.format(n, total_frames));
+        link_frame(frame, &path)?;
+        debug!("Copied {:?} to {:?}", frame, path);
+    }
+    println!("Copied {} frames into {:?}", total_frames, opt.outdir);
+    Ok(())
+}
+
+/// Interpolates between two horizontal gradients, at 8 and then 16 bits, with the default
+/// settings and checks that there are as many frames as there should be, that they start
+/// and end on the gradients and that they brighten steadily, with the middle frame half way
+fn self_check() -> Result<()> {
+    const WIDTH: usize = 64;
+    const HEIGHT: usize = 4;
+    const STEPS: usize = 50;
+
+    // the end gradient is brighter than the start at every pixel
+    let gradient = |offset: usize| -> Vec<u8> {
+        (0..WIDTH * HEIGHT)
+            .flat_map(|i| {
+                let value = (offset + i % WIDTH * 2) as u8;
+                [value, value / 2, value / 4]
+            })
+            .collect()
+    };
+    let checks = || -> Result<()> {
+        for bit_depth in [BitDepth::Eight, BitDepth::Sixteen, BitDepth::Float] {
+            let image = |data: Vec<u8>| match bit_depth {
+                BitDepth::Eight => {
+                    Image::new_from_parts(data, ColorType::Rgb, WIDTH as u32, HEIGHT as u32)
+                }
+                BitDepth::Sixteen => Image::new_from_parts(
+                    data.iter().map(|&c| c as u16 * 257).collect::<Vec<_>>(),
+                    ColorType::Rgb,
+                    WIDTH as u32,
+                    HEIGHT as u32,
+                ),
+                BitDepth::Float => Image::new_from_parts(
+                    data.iter().map(|&c| c as f32 / 255.0).collect::<Vec<_>>(),
+                    ColorType::Rgb,
+                    WIDTH as u32,
+                    HEIGHT as u32,
+                ),
+            };
+            let (start, end) = (image(gradient(0))?, image(gradient(128))?);
+            let interpolator = Interpolator::new(vec![start.clone(), end.clone()], STEPS)?;
+            let total_frames = interpolator.total_frames();
+            ensure!(
+                total_frames == STEPS + 1
+                    && interpolator.size_hint() == (total_frames, Some(total_frames)),
+                "{} bit: expected {} frames, but the interpolator reports {}.",
+                bit_depth.bits(),
+                STEPS + 1,
+                total_frames
+            );
+            let frames = interpolator.collect::<Result<Vec<_>>>()?;
+            ensure!(
+                frames.len() == total_frames,
+                "{} bit: {} frames were generated instead of {}.",
+                bit_depth.bits(),
+                frames.len(),
+                total_frames
+            );
+            ensure!(
+                frames[0].data() == start.data() && frames[STEPS].data() == end.data(),
+                "{} bit: the frames don't start and end on the images.",
+                bit_depth.bits()
+            );
+
+            let brightness: Vec<f64> = frames
+                .iter()
+                .map(|frame| channel_values(frame).iter().sum::<f64>())
+                .collect();
+            if let Some(n) = (1..brightness.len()).find(|&n| brightness[n] < brightness[n - 1]) {
+                bail!(
+                    "{} bit: frame {} is darker than frame {}.",
+                    bit_depth.bits(),
+                    n,
+                    n - 1
+                );
+            }
+
+            let middle = channel_values(&frames[STEPS / 2]);
+            let halfway: Vec<f64> = channel_values(&start)
+                .into_iter()
+                .zip(channel_values(&end))
+                .map(|(a, b)| match bit_depth {
+                    BitDepth::Eight => interpol::smooth(0.5, a as u8, b as u8) as f64,
+                    BitDepth::Sixteen => interpol::smooth(0.5, a as u16, b as u16) as f64,
+                    BitDepth::Float => interpol::smooth(0.5, a as f32, b as f32) as f64,
+                })
+                .collect();
+            ensure!(
+                middle == halfway,
+                "{} bit: the middle frame isn't half way between the images.",
+                bit_depth.bits()
+            );
+        }
+        Ok(())
+    };
+
+    panic::catch_unwind(checks)
+        .map_err(|_| anyhow!("Interpolating panicked, the build is broken."))??;
+    println!(
+        "Interpolated {} frames between two gradients at 8, 16 and 32 bits, everything works.",
+        STEPS + 1
+    );
+    Ok(())
+}
+
+/// Every channel value of `image`
+fn channel_values(image: &Image) -> Vec<f64> {
+    match image.data() {
+        Samples::Eight(data) => data.iter().map(|&c| c as f64).collect(),
+        Samples::Sixteen(data) => data.iter().map(|&c| c as f64).collect(),
+        Samples::Float(data) => data.iter().map(|&c| c as f64).collect(),
+    }
+}
+
+/// Generates and saves the frames of a single sequence
+fn run(mut opt: Opt) -> Result<()> {
+    if let Some(manifest) = &opt.from_file {
+        let images = read_manifest(manifest)?;
+        opt.images.extend(images);
+    }
+    if opt.stdin {
+        opt.images.extend(read_stdin_paths()?);
+        // the list being piped in means there is likely more of the pipeline sharing the
+        // terminal, which the progress bar would only get in the way of
+        opt.quiet |= !io::stdin().is_terminal();
+    }
+    if opt.skip_bad_inputs {
+        opt.images = drop_bad_inputs(&opt)?;
+    }
+    opt.format = output_format(&mut opt)?;
+    // panning and zooming across one image interpolates between it and itself
+    if !opt.ken_burns.is_empty() && opt.images.len() == 1 {
+        opt.images.push(opt.images[0].clone());
+    }
+
+    let mut second_stack = match &opt.second_stack {
+        Some(manifest) => read_manifest(manifest)?,
+        None => Vec::new(),
+    };
+
+    let mut loader = ImageLoader::new();
+    if opt.fit != Fit::Stretch && opt.resize_to.is_none() {
+        bail!("--fit only applies to images being resized with --resize-to.");
+    }
+    if opt.gravity != Gravity::Center && opt.fit == Fit::Stretch {
+        bail!("--gravity only applies to --fit contain or cover, stretching keeps every part of the image.");
+    }
+    if let Some(target) = opt.resize_to {
+        let infos: Vec<_> = opt
+            .images
+            .iter()
+            .filter(|p| !SolidColor::is_solid_color(p))
+            .map(ImageInfo::from_path)
+            .collect::<Result<_>>()?;
+        let (width, height) = target.resolve(&infos)?;
+        let background = opt.background.map_or([0; 3], |background| background.rgb);
+        loader =
+            loader
+                .resize(width, height, opt.resize_filter)
+                .fit(opt.fit, opt.gravity, background);
+    }
+    if let Some(promote) = opt.promote {
+        loader = loader.promote(promote);
+    }
+    if let Some(dir) = &opt.cache_dir {
+        loader = loader.cache_dir(dir);
+    }
 
-    let outdir = opt.outdir.to_str().ok_or(anyhow!(
-        "Failed to convert output directory {:?} to a string.",
-        opt.outdir.clone()
-    ))?;
+    // the transitions are reversed along with the images so each keeps its own length,
+    // apart from an extra one closing a loop, which still comes last to go from the new
+    // last image back to the new first. This comes after --resize-to first is resolved,
+    // so that still means the first image given.
+    if opt.reverse {
+        let pairs = opt.images.len().saturating_sub(1);
+        opt.images.reverse();
+        second_stack.reverse();
+        let closes_loop = opt.looping && opt.frames_between.len() == pairs + 1;
+        let reversed = if closes_loop {
+            pairs
+        } else {
+            opt.frames_between.len()
+        };
+        opt.frames_between[..reversed].reverse();
+        if opt.looping && opt.timeline.len() == pairs + 2 {
+            // the loop is closed after as long again as it was from the last image
+            let (last, end) = (opt.timeline[pairs], opt.timeline[pairs + 1]);
+            opt.timeline = opt.timeline[..=pairs]
+                .iter()
+                .rev()
+                .map(|t| last - t)
+                .collect();
+            opt.timeline.push(opt.timeline[pairs] + end - last);
+        } else if let Some(&end) = opt.timeline.last() {
+            opt.timeline = opt.timeline.iter().rev().map(|t| end - t).collect();
+        }
+    }
+
+    // a single count applies to every transition just like --n-frames
+    let n_frames = match (opt.frames_between.as_slice(), opt.duration) {
+        ([n_frames], _) => *n_frames,
+        (_, Some(duration)) => frames_for_duration(duration, opt.fps)?,
+        _ => opt.n_frames,
+    };
+    if opt.interpolation == Interpolation::CatmullRom && opt.color_space != ColorSpace::Rgb {
+        bail!("Catmull-Rom interpolation can only blend in the rgb color space.");
+    }
+    let channel_easing = [opt.easing_r, opt.easing_g, opt.easing_b];
+    if channel_easing.iter().any(Option::is_some)
+        && (opt.interpolation != Interpolation::Linear || opt.color_space != ColorSpace::Rgb)
+    {
+        bail!("Per-channel easing only works with linear interpolation in the rgb color space.");
+    }
+    if opt.blend_mode != BlendMode::Mix
+        && (opt.interpolation != Interpolation::Linear || opt.color_space != ColorSpace::Rgb)
+    {
+        bail!("Blend modes other than mix only work with linear interpolation in the rgb color space.");
+    }
+    if opt.premultiplied
+        && (opt.interpolation != Interpolation::Linear
+            || opt.color_space != ColorSpace::Rgb
+            || opt.blend_mode != BlendMode::Mix
+            || channel_easing.iter().any(Option::is_some))
+    {
+        bail!("--premultiplied only works with linear interpolation in the rgb color space, with the mix blend mode and a single easing curve.");
+    }
+    if opt.transition != Transition::Dissolve && opt.interpolation != Interpolation::Linear {
+        bail!("Transitions other than dissolve only work with linear interpolation.");
+    }
+    if n_frames == 0 {
+        bail!("Each transition needs at least 1 frame, 0 frames per transition would never reach the next image.");
+    }
 
-    // first ensure the output directory is empty so we don't do more work than we need to
-    fs::create_dir(outdir).with_context(|| format!("Failed to create output directory {:?}.", outdir))?;
+    // the images are only decoded as they are needed, which keeps memory usage down
+    let stack = if opt.second_stack.is_some() {
+        Some(Interpolator::from_paths_with_loader(
+            second_stack.clone(),
+            n_frames,
+            loader.clone(),
+        )?)
+    } else {
+        None
+    };
+    let mut interpolator =
+        Interpolator::from_paths_with_loader(opt.images.clone(), n_frames, loader)?
+            .easing(opt.easing)
+            .channel_easing(channel_easing)
+            .blend_mode(opt.blend_mode)
+            .transition(opt.transition)
+            .feather(opt.feather)
+            .interpolation(opt.interpolation)
+            .color_space(opt.color_space)
+            .gamma_correct(opt.gamma_correct)
+            .premultiplied(opt.premultiplied)
+            .dither(opt.dither)
+            .seed(opt.seed)
+            .ping_pong(opt.ping_pong)
+            .looping(opt.looping)
+            .hold(opt.hold)
+            .burn_in(opt.burn_in, opt.burn_in_pos, opt.fps);
+    if let Some(stack) = stack {
+        interpolator = interpolator.stack(stack, opt.stack_weight())?;
+    }
+    if let Some(bit_depth) = opt.output_bit_depth {
+        interpolator = interpolator.output_bit_depth(bit_depth);
+    }
+    if let Some(roi) = opt.roi {
+        interpolator = interpolator.region(roi)?;
+    }
+    if let Some(samples) = opt.motion_blur {
+        interpolator = interpolator.motion_blur(samples)?;
+    }
+    match opt.ken_burns[..] {
+        [] => {}
+        [start, end] => {
+            let info = interpolator.frame_info();
+            let (width, height) = match opt.ken_burns_size {
+                Some(size) => size.resolve(&[info])?,
+                None => (info.width, info.height),
+            };
+            interpolator = interpolator.ken_burns(KenBurns {
+                start,
+                end,
+                width,
+                height,
+            })?;
+        }
+        _ => bail!("--ken-burns takes a single START and END rectangle."),
+    }
+    if let Some(background) = opt.background {
+        if background.size.is_some() {
+            bail!("The --background color can't be given a size, it fills every frame.");
+        }
+        interpolator = interpolator.background(background.rgb);
+    }
+    if opt.frames_between.len() > 1 {
+        interpolator = interpolator.frames_between(opt.frames_between.clone())?;
+    }
+    if !opt.timeline.is_empty() {
+        interpolator = interpolator.timeline(&opt.timeline, opt.fps)?;
+    }
+    if let Some(path) = &opt.mu_curve_from_file {
+        let table = EasingTable::from_path(path, opt.allow_non_monotonic_curve)?;
+        interpolator = interpolator.easing_table(table);
+    }
+    if opt.preload {
+        interpolator = interpolator.preload()?;
+    }
+    if opt.distribute_by_motion {
+        interpolator = interpolator.distribute_by_motion()?;
+    }
 
-    // read in all the images and fail if any of them failed to load
-    let images: Vec<_> = opt
+    if let Some(position) = opt.at {
+        let image = interpolator.frame_at_position(position)?;
+        return image
+            .write_png_with(io::stdout().lock(), &opt.png_options())
+            .context("Failed to write the frame to stdout.");
+    }
+
+    // the inputs are hashed up front so the record matches the files the frames came from
+    let metadata = opt
+        .metadata
+        .as_ref()
+        .map(|_| Metadata::new(&opt, &second_stack, &interpolator))
+        .transpose()?;
+
+    let total_frames = interpolator.total_frames();
+    let start_frame = opt.start_frame.unwrap_or(0);
+    if start_frame >= total_frames {
+        bail!(
+            "--start-frame {} is past the end of the sequence, which has {} frames.",
+            start_frame,
+            total_frames
+        );
+    }
+    if opt.skip_existing && opt.format != Format::Png {
+        bail!(
+            "--skip-existing only applies to --format png, which saves each frame to its own file."
+        );
+    }
+    if let Some(feature) = opt.format.missing_feature() {
+        bail!(
+            "--format {0} needs interpol to be built with the {0:?} feature, e.g. --features {0}.",
+            feature
+        );
+    }
+    let float_frames = interpolator.frame_info().bit_depth == BitDepth::Float;
+    if float_frames && matches!(opt.format, Format::Png | Format::Apng | Format::Spritesheet) {
+        bail!("The frames are 32 bit floats, which can't be saved as PNGs, save them with --format exr or give an --output-bit-depth of 8 or 16.");
+    }
+    if opt.chunk_size == Some(0) {
+        bail!("--chunk-size must be at least 1 byte.");
+    }
+    if opt.strip_rows.is_some() && opt.format != Format::Png {
+        bail!("--strip-rows only applies to --format png, the other formats need whole frames.");
+    }
+    if opt.diff_frames && opt.format != Format::Png {
+        bail!("--diff-frames saves its images next to the frames, so it only applies to --format png.");
+    }
+    if !(opt.diff_amplify.is_finite() && opt.diff_amplify >= 0.0) {
+        bail!(
+            "--diff-amplify must be a positive number, not {}.",
+            opt.diff_amplify
+        );
+    }
+    if !opt.sizes.is_empty() && opt.format != Format::Png {
+        bail!("--sizes saves its frames next to the full size ones, so it only applies to --format png.");
+    }
+    let frame_width = interpolator.frame_info().width;
+    for (i, &width) in opt.sizes.iter().enumerate() {
+        if width == 0 || width > frame_width {
+            bail!(
+                "--sizes {} must be between 1 and the width of the frames, {}.",
+                width,
+                frame_width
+            );
+        }
+        if opt.sizes[..i].contains(&width) {
+            bail!("--sizes {} is given more than once.", width);
+        }
+    }
+    if opt.strip_rows.is_some() && opt.burn_in != BurnIn::None {
+        bail!("--burn-in can't be used with --strip-rows, which never has a whole frame to stamp.");
+    }
+
+    if opt.dry_run {
+        print_plan(&opt, &interpolator, start_frame);
+        return Ok(());
+    }
+    if let Some(count) = opt.preview {
+        if opt.format != Format::Png {
+            bail!("--preview saves its frames as PNGs, so it only applies to --format png.");
+        }
+        if count == 0 {
+            bail!("--preview needs at least 1 frame to save.");
+        }
+        return save_preview(&opt, &interpolator, count);
+    }
+    if let Some(path) = &opt.contact_sheet {
+        return save_contact_sheet(&opt, &interpolator, path);
+    }
+    if opt.preview_window {
+        #[cfg(feature = "gui")]
+        return play_in_window(&opt, &interpolator, start_frame);
+        #[cfg(not(feature = "gui"))]
+        bail!("--preview-window needs interpol to be built with the gui feature, e.g. --features gui.");
+    }
+    confirm_output_size(&opt, &interpolator, start_frame)?;
+
+    let progress = if opt.quiet {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new(total_frames as u64).with_style(
+            ProgressStyle::with_template(
+                "{wide_bar} {pos}/{len} frames [{elapsed_precise}<{eta_precise}, {per_sec}]",
+            )
+            .context("Failed to create the progress bar style.")?,
+        )
+    };
+    progress.set_position(start_frame as u64);
+
+    let result = match opt.format {
+        Format::Png => {
+            // first ensure the output directory is empty so we don't do more work than we need to,
+            // resuming a run is expected to write into the frames already there
+            let resuming = start_frame > 0 || opt.skip_existing;
+            prepare_outdir(&opt.outdir, opt.force, opt.append || resuming)?;
+
+            // generate and save all the frames in parallel, each frame's file name comes from
+            // its index so the output doesn't depend on the order they finish in
+            let paths = frame_paths(&opt.outdir, &opt.name_pattern, total_frames)?;
+            // every frame is saved full size then at each of the --sizes, in that order
+            let mut outputs = vec![paths];
+            for width in &opt.sizes {
+                let dir = opt.outdir.join(width.to_string());
+                fs::create_dir_all(&dir)
+                    .with_context(|| format!("Failed to create output directory {:?}.", dir))?;
+                outputs.push(frame_paths(&dir, &opt.name_pattern, total_frames)?);
+            }
+            let frame_path = |n: usize| &outputs[0][n];
+            let skip_existing = opt.skip_existing;
+            let png_options = opt.png_options();
+            let skipped = |n| skip_existing && frame_path(n).exists();
+            // frames known to be copies of another being written are linked to it afterwards
+            let copy_of = |n| interpolator.duplicate_of(n).filter(|&m| m >= start_frame);
+            let keep = |n| n >= start_frame && copy_of(n).is_none() && !skipped(n);
+            match opt.strip_rows {
+                // each frame is streamed straight from the keyframe files to its own
+                Some(strip_rows) => (0..total_frames)
+                    .into_par_iter()
+                    .filter(|&n| n >= start_frame)
+                    .try_for_each(|n| {
+                        if keep(n) {
+                            interpolator.save_frame_in_strips_with(
+                                n,
+                                frame_path(n),
+                                strip_rows,
+                                &png_options,
+                            )?;
+                            debug!("Wrote frame {} to {:?}", n, frame_path(n));
+                        }
+                        progress.inc(1);
+                        Ok::<_, anyhow::Error>(())
+                    })?,
+                // the frames are encoded in parallel, but written one at a time in order
+                None => thread::scope(|s| {
+                    let (sender, receiver) = mpsc::sync_channel(WRITE_QUEUE);
+                    let kept: Vec<_> = (0..total_frames).filter(|&n| keep(n)).collect();
+                    let writer = s.spawn(|| write_in_order(receiver, kept, &outputs));
+                    let generated = interpolator.par_for_each_frame_with_progress(
+                        keep,
+                        |n, image| {
+                            let encode = |image: &Image| {
+                                let mut png = Vec::new();
+                                image.write_png_with(&mut png, &png_options).map(|_| png)
+                            };
+                            let mut pngs = vec![encode(&image)?];
+                            for &width in &opt.sizes {
+                                pngs.push(encode(&image.resize_to_width(width, opt.sizes_filter))?);
+                            }
+                            // the writer only hangs up when it has failed, reporting why itself
+                            sender
+                                .send((n, pngs))
+                                .map_err(|_| anyhow!("The frames stopped being written."))
+                        },
+                        |done, _| progress.set_position(done as u64),
+                    );
+                    drop(sender);
+                    let written = writer.join().expect("the frame writer doesn't panic");
+                    written.and(generated)
+                })?,
+            }
+
+            // the copies were counted by the progress bar as they were passed over
+            for n in start_frame..total_frames {
+                if let Some(m) = copy_of(n) {
+                    if !skipped(n) {
+                        for paths in &outputs {
+                            link_frame(&paths[m], &paths[n])?;
+                        }
+                        debug!("Linked frame {} to frame {}", n, m);
+                    }
+                }
+            }
+            if opt.diff_frames {
+                save_diff_frames(
+                    &outputs[0],
+                    start_frame.max(1),
+                    opt.diff_amplify,
+                    &png_options,
+                )?;
+            }
+            Ok(())
+        }
+        #[cfg(feature = "gif")]
+        Format::Gif => {
+            let outfile = opt
+                .outfile
+                .ok_or(anyhow!("An --outfile must be given to save a GIF to."))?;
+
+            // a palette file is read first so a bad one fails before generating anything
+            let palette = match &opt.palette_from {
+                PaletteFrom::File(path) => Some(Palette::from_path(path)?),
+                PaletteFrom::All | PaletteFrom::First => None,
+            };
+            // the palette can be computed across every frame so they all have to be generated first
+            let frames = collect_frames(&interpolator, start_frame, &progress)?;
+            let palette = match (palette, &opt.palette_from) {
+                (Some(palette), _) => palette,
+                (None, PaletteFrom::First) => Palette::median_cut(&frames[..1])?,
+                (None, _) => Palette::median_cut(&frames)?,
+            };
+            interpol::save_gif_with_palette(&frames, outfile, opt.delay, &palette)
+        }
+        #[cfg(feature = "webp")]
+        Format::Webp => {
+            let outfile = opt
+                .outfile
+                .ok_or(anyhow!("An --outfile must be given to save a WebP to."))?;
+
+            let frames = collect_frames(&interpolator, start_frame, &progress)?;
+            interpol::save_webp(&frames, outfile, opt.webp_delay, opt.webp_quality)
+        }
+        Format::Apng => {
+            let outfile = opt
+                .outfile
+                .ok_or(anyhow!("An --outfile must be given to save an APNG to."))?;
+
+            let frames = interpolator.skip(start_frame).inspect(|_| progress.inc(1));
+            interpol::save_apng(frames, outfile, opt.apng_delay)
+        }
+        Format::Raw => {
+            let outfile = opt
+                .outfile
+                .ok_or(anyhow!("An --outfile must be given to save raw frames to."))?;
+
+            let frames = interpolator.skip(start_frame).inspect(|_| progress.inc(1));
+            interpol::save_raw(frames, outfile)
+        }
+        #[cfg(feature = "video")]
+        Format::Video => {
+            let outfile = opt
+                .outfile
+                .ok_or(anyhow!("An --outfile must be given to save a video to."))?;
+
+            let frames = interpolator.skip(start_frame).inspect(|_| progress.inc(1));
+            interpol::save_video(frames, outfile, opt.fps)
+        }
+        Format::Spritesheet => {
+            let png_options = opt.png_options();
+            let outfile = opt.outfile.ok_or(anyhow!(
+                "An --outfile must be given to save a sprite sheet to."
+            ))?;
+
+            // the frames are laid out in a single row unless a number of columns is given
+            let frames = total_frames - start_frame;
+            let info = interpolator.frame_info();
+            let sheet = SpriteSheet::new(info, frames, opt.columns.unwrap_or(frames))?;
+            let sheet = Mutex::new(sheet);
+            interpolator.par_for_each_frame_with_progress(
+                |n| n >= start_frame,
+                |n, image| sheet.lock().unwrap().insert(n - start_frame, &image),
+                |done, _| progress.set_position(done as u64),
+            )?;
+            let sheet = sheet.into_inner().unwrap().into_image();
+            sheet.save_with(outfile, &png_options)
+        }
+        #[cfg(feature = "exr")]
+        Format::Exr => {
+            prepare_outdir(&opt.outdir, opt.force, opt.append || start_frame > 0)?;
+
+            let paths = exr_frame_paths(&opt.outdir, &opt.name_pattern, total_frames)?;
+            let copy_of = |n| interpolator.duplicate_of(n).filter(|&m| m >= start_frame);
+            interpolator.par_for_each_frame_with_progress(
+                |n| n >= start_frame && copy_of(n).is_none(),
+                |n, image| image.save(&paths[n]),
+                |done, _| progress.set_position(done as u64),
+            )?;
+            for n in start_frame..total_frames {
+                if let Some(m) = copy_of(n) {
+                    link_frame(&paths[m], &paths[n])?;
+                    debug!("Linked frame {} to frame {}", n, m);
+                }
+            }
+            Ok(())
+        }
+        #[allow(unreachable_patterns)]
+        format => unreachable!(
+            "{:?} is checked for its feature before generating frames",
+            format
+        ),
+    };
+
+    progress.finish();
+    result?;
+
+    if let (Some(path), Some(metadata)) = (&opt.metadata, metadata) {
+        let contents = serde_json::to_string_pretty(&metadata)
+            .context("Failed to describe the run as JSON.")?;
+        fs::write(path, contents + "\n")
+            .with_context(|| format!("Failed to write the run's metadata to {:?}", path))?;
+    }
+    Ok(())
+}
+
+/// Plays the frames from `start_frame` on in a window as they are generated, a batch of
+/// them at once to keep every thread busy, saving each one too unless --no-save is given
+#[cfg(feature = "gui")]
+fn play_in_window(opt: &Opt, interpolator: &Interpolator, start_frame: usize) -> Result<()> {
+    if !opt.no_save && opt.format != Format::Png {
+        bail!("--preview-window can only save the frames it plays as PNGs, add --no-save to just watch them.");
+    }
+    let total_frames = interpolator.total_frames();
+    let (info, fps) = (interpolator.frame_info(), opt.max_fps.unwrap_or(opt.fps));
+
+    // the frames are generated on this thread, which is in the run's thread pool, and
+    // played on one of their own
+    let failed = AtomicBool::new(false);
+    thread::scope(|s| {
+        let (sender, receiver) = mpsc::sync_channel::<(usize, Image)>(WRITE_QUEUE);
+        let failed = &failed;
+        let player = s.spawn(move || {
+            let mut window = PreviewWindow::new(info, fps)?;
+            // the window is redrawn while waiting for frames so it keeps responding
+            while window.is_open() {
+                match receiver.recv_timeout(window.frame_time()) {
+                    Ok((n, image)) => {
+                        window.set_title(&format!("interpol - frame {}/{}", n + 1, total_frames));
+                        window.show(&image)?;
+                    }
+                    Err(mpsc::RecvTimeoutError::Timeout) => window.redraw()?,
+                    Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                }
+            }
+            let closed = !window.is_open();
+            drop(receiver);
+
+            // the last frame stays up until the window is closed
+            while !closed && !failed.load(Ordering::SeqCst) && window.is_open() {
+                thread::sleep(window.frame_time());
+                window.redraw()?;
+            }
+            Ok::<_, anyhow::Error>(closed)
+        });
+
+        let generated = (|| -> Result<()> {
+            if !opt.no_save {
+                confirm_output_size(opt, interpolator, start_frame)?;
+                prepare_outdir(&opt.outdir, opt.force, opt.append || start_frame > 0)?;
+            }
+            let paths = frame_paths(&opt.outdir, &opt.name_pattern, total_frames)?;
+            let png_options = opt.png_options();
+
+            let frames: Vec<_> = (start_frame..total_frames).collect();
+            for batch in frames.chunks(rayon::current_num_threads()) {
+                let images = batch
+                    .par_iter()
+                    .map(|&n| {
+                        let image = interpolator
+                            .frame_at(n)
+                            .expect("the frames are all in the sequence")?;
+                        if !opt.no_save {
+                            image.save_with(&paths[n], &png_options)?;
+                            debug!("Wrote frame {} to {:?}", n, paths[n]);
+                        }
+                        Ok(image)
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                for (&n, image) in batch.iter().zip(images) {
+                    // the window only hangs up when it has been closed
+                    if sender.send((n, image)).is_err() {
+                        return Ok(());
+                    }
+                }
+            }
+            Ok(())
+        })();
+        // a failed run closes the window rather than leaving the last frame up
+        failed.store(generated.is_err(), Ordering::SeqCst);
+        drop(sender);
+
+        let closed = player.join().expect("the preview window doesn't panic")?;
+        generated?;
+        if closed {
+            warn!("The preview window was closed before every frame was shown, stopping the run.");
+        }
+        Ok(())
+    })
+}
+
+/// The indices of `count` frames spread evenly through `total_frames`,
+/// always including the first and last frames when there are at least two
+fn preview_frames(count: usize, total_frames: usize) -> Vec<usize> {
+    if count == 1 {
+        return vec![0];
+    }
+
+    let (gaps, span) = (count - 1, total_frames - 1);
+    let mut frames: Vec<_> = (0..count).map(|i| (i * span + gaps / 2) / gaps).collect();
+    // asking for more frames than there are gives every frame once
+    frames.dedup();
+    frames
+}
+
+/// Generates and saves only the frames picked by `preview_frames`,
+/// each named after its index in the full sequence
+fn save_preview(opt: &Opt, interpolator: &Interpolator, count: usize) -> Result<()> {
+    let total_frames = interpolator.total_frames();
+    prepare_outdir(&opt.outdir, opt.force, opt.append)?;
+
+    // the frames are generated a transition at a time, so each image is only decoded once
+    let frames = preview_frames(count, total_frames);
+    interpolator.par_for_each_frame_filtered(
+        |n| frames.binary_search(&n).is_ok(),
+        |n, image| {
+            let path = opt.outdir.join(opt.name_pattern.format(n, total_frames));
+            image.save_with(&path, &opt.png_options())?;
+            debug!("Wrote frame {} to {:?}", n, path);
+            Ok(())
+        },
+    )?;
+
+    if !opt.quiet {
+        println!(
+            "Saved {} of the {} frames to {:?}",
+            frames.len(),
+            total_frames,
+            opt.outdir
+        );
+    }
+    Ok(())
+}
+
+/// Generates every `--contact-every`th frame, and the last frame, and saves them shrunk
+/// down and labelled with their indices in a grid at `path`
+fn save_contact_sheet(opt: &Opt, interpolator: &Interpolator, path: &Path) -> Result<()> {
+    ensure!(opt.contact_every > 0, "--contact-every must be at least 1.");
+    let total_frames = interpolator.total_frames();
+    let mut frames: Vec<_> = (0..total_frames).step_by(opt.contact_every).collect();
+    if frames.last() != Some(&(total_frames - 1)) {
+        frames.push(total_frames - 1);
+    }
+
+    // as close to square as possible unless a number of columns is given
+    let columns = opt
+        .columns
+        .unwrap_or_else(|| (frames.len() as f64).sqrt().ceil() as usize);
+    let sheet = ContactSheet::new(
+        interpolator.frame_info(),
+        frames.len(),
+        columns,
+        opt.contact_width,
+    )?;
+    let sheet = Mutex::new(sheet);
+    // each frame's cell is its place in the sorted list of frames
+    let cell = |n| frames.binary_search(&n);
+    interpolator.par_for_each_frame_filtered(
+        |n| cell(n).is_ok(),
+        |n, image| {
+            let cell = cell(n).expect("only the frames on the sheet are generated");
+            sheet.lock().unwrap().insert(cell, n, &image)
+        },
+    )?;
+    save_sheet(opt, sheet.into_inner().unwrap().into_image(), path)?;
+
+    if !opt.quiet {
+        println!(
+            "Saved {} of the {} frames to the contact sheet {:?}",
+            frames.len(),
+            total_frames,
+            path
+        );
+    }
+    Ok(())
+}
+
+/// Saves a contact sheet as a single frame WebP when `path` ends in .webp, or a PNG otherwise
+fn save_sheet(opt: &Opt, sheet: Image, path: &Path) -> Result<()> {
+    let webp = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("webp"));
+    if webp {
+        #[cfg(feature = "webp")]
+        return interpol::save_webp(&[sheet], path, opt.webp_delay, opt.webp_quality);
+        #[cfg(not(feature = "webp"))]
+        bail!(
+            "Contact sheets can only be saved as WebP when interpol is built with the \"webp\" feature."
+        );
+    }
+    sheet.save_with(path, &opt.png_options())
+}
+
+/// Decodes every image to find the ones which can't be, leaving them out of the images
+/// and reporting how that changes the transitions between the remaining ones
+fn drop_bad_inputs(opt: &Opt) -> Result<Vec<PathBuf>> {
+    let errors: Vec<_> = opt
         .images
-        .into_iter()
-        .map(Image::new_from_path)
-        .collect::<Result<_>>()?;
+        .par_iter()
+        .map(|path| {
+            if SolidColor::is_solid_color(path) {
+                SolidColor::from_path(path).err()
+            } else {
+                Image::new_from_path(path).err()
+            }
+        })
+        .collect();
+    if errors.iter().all(Option::is_none) {
+        return Ok(opt.images.clone());
+    }
+
+    // which pair of images each transition count or time belongs to would be lost
+    if opt.frames_between.len() > 1 || !opt.timeline.is_empty() {
+        bail!("Images can't be skipped with --frames-between or --timeline giving each transition its own length.");
+    }
+
+    let mut kept: Vec<&PathBuf> = Vec::new();
+    let mut skipped = Vec::new();
+    for (path, error) in opt.images.iter().zip(errors) {
+        match error {
+            Some(error) => {
+                warn!("Skipping {:?}: {:#}", path, error);
+                skipped.push(path);
+            }
+            None => {
+                // the kept image before a run of skipped ones now blends straight into this one
+                match (kept.last(), skipped.is_empty()) {
+                    (_, true) => {}
+                    (Some(previous), false) => warn!(
+                        "{:?} now transitions straight to {:?}, leaving out {:?}",
+                        previous, path, skipped
+                    ),
+                    (None, false) => warn!("The sequence now starts on {:?}", path),
+                }
+                skipped.clear();
+                kept.push(path);
+            }
+        }
+    }
+
+    if let (Some(last), false) = (kept.last(), skipped.is_empty()) {
+        warn!("The sequence now ends on {:?}", last);
+    }
 
-    // ensure all the images are the same shape
     ensure!(
-        images[1..]
-            .iter()
-            .all(|im| im.width == images[0].width && im.height == images[0].height),
-        "All of the images must have the same width and height."
+        kept.len() >= 2,
+        "Only {} of the {} images could be decoded, at least two are needed to interpolate between.",
+        kept.len(),
+        opt.images.len()
     );
+    Ok(kept.into_iter().cloned().collect())
+}
 
-    // setup for the frame generation
-    let total_frames = (images.len() - 1) * opt.n_frames;
-    let n_frames = opt.n_frames;
-
-    // generate all the frames in parallel
-    (0..=total_frames)
-        .into_par_iter()
-        .map(|n| {
-            let image_no = n / n_frames;
-            let frame_no = n % n_frames;
-
-            // this shouldn't panic because image_no is derived from the length of the images
-            // array and the case for the final "end" image is handled
-            let result_image = if frame_no == 0 {
-                images[image_no].clone()
-            } else {
-                let mu = frame_no as f64 / n_frames as f64;
-                interpolate(mu, &images[image_no], &images[image_no + 1])?
-            };
+/// A record of how a set of frames was generated, written by --metadata
+#[derive(Debug, Serialize)]
+struct Metadata {
+    inputs: Vec<Input>,
+    /// The images of the --second-stack, matching `inputs` image for image
+    second_stack: Vec<Input>,
+    stack_weight: Option<f64>,
+    width: u32,
+    height: u32,
+    color_type: &'static str,
+    bit_depth: u32,
+    total_frames: usize,
+    segments: Vec<SegmentMetadata>,
+    easing: &'static str,
+    /// The file the easing curve was loaded from, replacing `easing`
+    easing_curve: Option<PathBuf>,
+    color_space: &'static str,
+    interpolation: &'static str,
+    blend_mode: &'static str,
+    transition: &'static str,
+    gamma_correct: bool,
+    premultiplied: bool,
+    /// The color type every image was promoted to as it was loaded
+    promote: Option<&'static str>,
+    /// The x, y, width and height of the only part of each frame which was interpolated
+    roi: Option<[u32; 4]>,
+    /// The x, y, width and height of the rectangles the frames panned and zoomed from and to
+    ken_burns: Option<[[u32; 4]; 2]>,
+    /// The number of blends averaged into each frame to blur its motion
+    motion_blur: Option<usize>,
+    dither: &'static str,
+    /// The seed any dithering noise was generated from
+    seed: u64,
+    /// The widths every frame was also saved scaled down to
+    sizes: Vec<u32>,
+    format: &'static str,
+}
+
+/// One of the images a run was generated from
+#[derive(Debug, Serialize)]
+struct Input {
+    path: PathBuf,
+    /// The FNV-1a hash of the file's contents in hex, solid colors have none
+    hash: Option<String>,
+}
+
+/// One of a run's transitions between two of its `inputs`
+#[derive(Debug, Serialize)]
+struct SegmentMetadata {
+    start: usize,
+    end: usize,
+    frames: usize,
+}
 
-            result_image.save(&format!("{}/frame_{:09}.png", outdir, n))
+impl Metadata {
+    fn new(opt: &Opt, second_stack: &[PathBuf], interpolator: &Interpolator) -> Result<Self> {
+        let inputs = |paths: &[PathBuf]| {
+            paths
+                .iter()
+                .map(|path| -> Result<_> {
+                    let hash = if SolidColor::is_solid_color(path) {
+                        None
+                    } else {
+                        let contents = fs::read(path)
+                            .with_context(|| format!("Failed to read {:?} to hash it.", path))?;
+                        Some(format!("{:016x}", fnv1a(&contents)))
+                    };
+                    Ok(Input {
+                        path: path.clone(),
+                        hash,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()
+        };
+
+        let info = interpolator.frame_info();
+        Ok(Self {
+            inputs: inputs(&opt.images)?,
+            second_stack: inputs(second_stack)?,
+            stack_weight: opt.second_stack.as_ref().map(|_| opt.stack_weight()),
+            width: info.width,
+            height: info.height,
+            color_type: info.color_type.name(),
+            bit_depth: info.bit_depth.bits(),
+            total_frames: interpolator.total_frames(),
+            segments: interpolator
+                .segments()
+                .map(|segment| SegmentMetadata {
+                    start: segment.start,
+                    end: segment.end,
+                    frames: segment.len(),
+                })
+                .collect(),
+            easing: variant_name(&Easing::VARIANTS, opt.easing),
+            easing_curve: opt.mu_curve_from_file.clone(),
+            color_space: variant_name(&ColorSpace::VARIANTS, opt.color_space),
+            interpolation: variant_name(&Interpolation::VARIANTS, opt.interpolation),
+            blend_mode: variant_name(&BlendMode::VARIANTS, opt.blend_mode),
+            transition: variant_name(&Transition::VARIANTS, opt.transition),
+            gamma_correct: opt.gamma_correct,
+            premultiplied: opt.premultiplied,
+            promote: opt.promote.map(|p| variant_name(&Promote::VARIANTS, p)),
+            roi: opt.roi.map(|roi| [roi.x, roi.y, roi.width, roi.height]),
+            ken_burns: match opt.ken_burns[..] {
+                [start, end] => Some([start, end].map(|r| [r.x, r.y, r.width, r.height])),
+                _ => None,
+            },
+            motion_blur: opt.motion_blur,
+            dither: variant_name(&Dither::VARIANTS, opt.dither),
+            seed: opt.seed,
+            sizes: opt.sizes.clone(),
+            format: variant_name(&Format::VARIANTS, opt.format),
         })
-        .collect()
+    }
 }
 
-fn interpolate(mu: f64, im1: &Image, im2: &Image) -> Result<Image> {
-    let new_image_data: Vec<_> = im1
-        .data
+/// The name `value` is given on the command line, out of its type's `variants`
+fn variant_name<T>(variants: &[&'static str], value: T) -> &'static str
+where
+    T: FromStr + PartialEq,
+{
+    variants
         .iter()
-        .zip(im2.data.iter())
-        .map(|(s, e)| smooth(mu, *s, *e))
+        .find(|name| name.parse::<T>().is_ok_and(|v| v == value))
+        .expect("every value has a name")
+}
+
+/// The 64 bit FNV-1a hash of `bytes`, which unlike the standard library's hasher
+/// is the same from one build to the next
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// One sequence to generate from a `--batch` file,
+/// the settings left out are taken from the command line
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Job {
+    images: Vec<PathBuf>,
+    outdir: Option<PathBuf>,
+    outfile: Option<PathBuf>,
+    n_frames: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Batch {
+    #[serde(default, rename = "job")]
+    jobs: Vec<Job>,
+}
+
+/// Runs every job in the `batch` file with up to `--jobs` at once, a job failing doesn't
+/// stop the others and every failure is reported once they have all finished
+fn run_batch(batch: &Path, opt: &Opt, pool: &ThreadPool) -> Result<()> {
+    let contents = fs::read_to_string(batch)
+        .with_context(|| format!("Failed to read the batch file {:?}.", batch))?;
+    let Batch { jobs } = toml::from_str(&contents)
+        .with_context(|| format!("Failed to parse the batch file {:?}.", batch))?;
+
+    // like a manifest, paths in the batch file are relative to the file itself
+    let base = batch.parent().unwrap_or_else(|| Path::new(""));
+    let total = jobs.len();
+    let jobs = jobs.into_iter().map(|job| {
+        let mut job_opt = opt.clone();
+        job_opt.batch = None;
+        job_opt.from_file = None;
+        job_opt.images = job.images.iter().map(|p| base.join(p)).collect();
+        if let Some(outdir) = job.outdir {
+            job_opt.outdir = base.join(outdir);
+        }
+        if let Some(outfile) = job.outfile {
+            job_opt.outfile = Some(base.join(outfile));
+        }
+        if let Some(n_frames) = job.n_frames {
+            job_opt.n_frames = n_frames;
+        }
+        // progress bars for several jobs at once would draw over each other
+        job_opt.quiet |= opt.jobs > 1;
+        job_opt
+    });
+
+    // each worker takes the next job in order, the frames of every job
+    // are still generated in parallel on the shared thread pool, which the workers
+    // join as they aren't threads of the pool themselves
+    let jobs = Mutex::new(jobs.enumerate());
+    let failures = Mutex::new(Vec::new());
+    thread::scope(|s| {
+        for _ in 0..opt.jobs.max(1) {
+            s.spawn(|| loop {
+                let next = jobs.lock().unwrap().next();
+                let (n, job_opt) = match next {
+                    Some(job) => job,
+                    None => break,
+                };
+                let name = job_opt.outfile.clone().unwrap_or(job_opt.outdir.clone());
+                match pool.install(|| run(job_opt)) {
+                    Ok(()) => println!("Job {} of {} finished: {:?}", n + 1, total, name),
+                    Err(e) => {
+                        eprintln!("Job {} of {} failed: {:?}: {:#}", n + 1, total, name, e);
+                        failures.lock().unwrap().push(n + 1);
+                    }
+                }
+            });
+        }
+    });
+
+    let mut failures = failures.into_inner().unwrap();
+    failures.sort_unstable();
+    println!("{} of {} jobs succeeded", total - failures.len(), total);
+    if !failures.is_empty() {
+        bail!("Jobs {:?} failed, see the errors above.", failures);
+    }
+    Ok(())
+}
+
+/// The whole number of frames closest to `duration` seconds at `fps` frames per second,
+/// rounding halves up
+fn frames_for_duration(duration: f64, fps: f64) -> Result<usize> {
+    if !(duration.is_finite() && duration >= 0.0 && fps.is_finite() && fps > 0.0) {
+        bail!(
+            "--duration {} and --fps {} must both be positive numbers.",
+            duration,
+            fps
+        );
+    }
+    Ok((duration * fps).round() as usize)
+}
+
+/// Prints what would be generated and where it would be written, without decoding any images
+fn print_plan(opt: &Opt, interpolator: &Interpolator, start_frame: usize) {
+    let info = interpolator.frame_info();
+    let total_frames = interpolator.total_frames();
+    println!(
+        "{} frames of {}x{} {} {} bit images",
+        total_frames,
+        info.width,
+        info.height,
+        info.color_type.name(),
+        info.bit_depth.bits()
+    );
+
+    for segment in interpolator.segments() {
+        print!(
+            "  frames {:?}: {:?} -> {:?}, {} frames",
+            segment.frames(),
+            opt.images[segment.start],
+            opt.images[segment.end],
+            segment.steps
+        );
+        if segment.hold > 0 {
+            print!(" after holding for {}", segment.hold);
+        }
+        println!();
+    }
+    let final_frames = interpolator.segments().last().map_or(0, |s| s.frames().end)..total_frames;
+    if !final_frames.is_empty() {
+        let last = interpolator.segments().last().map_or(0, |s| s.end);
+        println!("  frames {:?}: {:?}", final_frames, opt.images[last]);
+    }
+
+    match (&opt.format, &opt.outfile) {
+        (Format::Png | Format::Exr, _) => {
+            let path = |n| {
+                let path = opt.outdir.join(opt.name_pattern.format(n, total_frames));
+                match opt.format {
+                    Format::Exr => with_exr_extension(path),
+                    _ => path,
+                }
+            };
+            println!(
+                "Writing frames {} to {} as {:?} to {:?}",
+                start_frame,
+                total_frames - 1,
+                path(start_frame),
+                path(total_frames - 1)
+            );
+        }
+        (format, Some(outfile)) => println!("Writing a {:?} file to {:?}", format, outfile),
+        (format, None) => println!("Writing a {:?} file, but no --outfile was given", format),
+    }
+
+    let bytes = (total_frames - start_frame) as f64 * frame_bytes(info);
+    println!(
+        "Uncompressed that's {:.1} MB, compressed files are usually a fraction of that",
+        bytes / 1e6
+    );
+}
+
+/// The estimated size in bytes of the output above which a run asks before writing anything
+const LARGE_OUTPUT: f64 = 5e9;
+
+/// Roughly how many times smaller a PNG is than its raw pixels, photographs compress less
+/// than this and flat artwork much more
+const PNG_COMPRESSION: f64 = 2.0;
+
+/// Estimates the size of what a run will write from the size of its frames, asking whether
+/// to carry on when that's over `LARGE_OUTPUT`, unless --yes was given or there's no one at
+/// the terminal to ask, when it only warns
+fn confirm_output_size(opt: &Opt, interpolator: &Interpolator, start_frame: usize) -> Result<()> {
+    let compression = match opt.format {
+        Format::Png | Format::Apng | Format::Spritesheet => PNG_COMPRESSION,
+        Format::Raw | Format::Exr => 1.0,
+        // these compress each frame against the ones before it, far too well to guess at
+        Format::Gif | Format::Video | Format::Webp => return Ok(()),
+    };
+    let frames = interpolator.total_frames() - start_frame;
+    let bytes = frames as f64 * frame_bytes(interpolator.frame_info()) / compression;
+    if bytes <= LARGE_OUTPUT || opt.yes {
+        return Ok(());
+    }
+
+    let message = format!(
+        "This will write {} frames, around {:.1} GB.",
+        frames,
+        bytes / 1e9
+    );
+    let stdin = io::stdin();
+    if !stdin.is_terminal() {
+        warn!("{}", message);
+        return Ok(());
+    }
+    // held while asking so jobs of a batch ask one at a time
+    let mut stdin = stdin.lock();
+    eprint!("{} Carry on? [y/N] ", message);
+    let mut answer = String::new();
+    stdin
+        .read_line(&mut answer)
+        .context("Failed to read the answer.")?;
+    ensure!(
+        matches!(answer.trim(), "y" | "Y" | "yes"),
+        "Stopped before writing anything, pass --yes to write large outputs without asking."
+    );
+    Ok(())
+}
+
+/// The number of bytes of raw pixel data in a frame like `info`
+fn frame_bytes(info: ImageInfo) -> f64 {
+    let sample_bytes = info.bit_depth.bits() as f64 / 8.0;
+    info.width as f64 * info.height as f64 * info.color_type.channels() as f64 * sample_bytes
+}
+
+/// Generates every frame from `start_frame` onwards in parallel, returning them in order
+#[cfg(any(feature = "gif", feature = "webp"))]
+fn collect_frames(
+    interpolator: &Interpolator,
+    start_frame: usize,
+    progress: &ProgressBar,
+) -> Result<Vec<Image>> {
+    let frames = Mutex::new(vec![None; interpolator.total_frames()]);
+    interpolator.par_for_each_frame_with_progress(
+        |n| n >= start_frame,
+        |n, image| {
+            frames.lock().unwrap()[n] = Some(image);
+            Ok(())
+        },
+        |done, _| progress.set_position(done as u64),
+    )?;
+    Ok(frames.into_inner().unwrap().into_iter().flatten().collect())
+}
+
+/// Makes the frame at `to` a copy of the frame at `from`, hard linking them where possible
+/// so the copy takes up no more space
+fn link_frame(from: &Path, to: &Path) -> Result<()> {
+    if to.exists() {
+        fs::remove_file(to).with_context(|| format!("Failed to replace frame {:?}.", to))?;
+    }
+    if fs::hard_link(from, to).is_err() {
+        fs::copy(from, to)
+            .with_context(|| format!("Failed to copy frame {:?} to {:?}.", from, to))?;
+    }
+    Ok(())
+}
+
+/// The number of encoded frames which can wait to be written before the frames being
+/// generated wait for the writer to catch up
+const WRITE_QUEUE: usize = 16;
+
+/// The path each of the `total_frames` frames is saved to, checking that no two frames
+/// would be saved to the same file
+fn frame_paths(
+    outdir: &Path,
+    name_pattern: &NamePattern,
+    total_frames: usize,
+) -> Result<Vec<PathBuf>> {
+    let paths = (0..total_frames)
+        .map(|n| outdir.join(name_pattern.format(n, total_frames)))
         .collect();
-    Image::new_from_parts(new_image_data, im1.width, im1.height)
+    unique_paths(paths)
+}
+
+/// Checks that no two frames would be saved to the same one of `paths`, giving them back
+fn unique_paths(paths: Vec<PathBuf>) -> Result<Vec<PathBuf>> {
+    let mut seen = HashMap::new();
+    for (n, path) in paths.iter().enumerate() {
+        if let Some(m) = seen.insert(path, n) {
+            bail!(
+                "Frames {} and {} would both be saved to {:?}, the --name-pattern must give every frame its own file.",
+                m,
+                n,
+                path
+            );
+        }
+    }
+    Ok(paths)
+}
+
+/// The paths of the frames saved by --format exr, named by the --name-pattern but always
+/// with the extension `.exr`
+#[cfg(feature = "exr")]
+fn exr_frame_paths(
+    outdir: &Path,
+    name_pattern: &NamePattern,
+    total_frames: usize,
+) -> Result<Vec<PathBuf>> {
+    let paths = (0..total_frames)
+        .map(|n| with_exr_extension(outdir.join(name_pattern.format(n, total_frames))))
+        .collect();
+    // the names are only checked once they have their extension, as replacing it can
+    // leave two frames with the same name
+    unique_paths(paths)
+}
+
+/// Gives a frame's `path` the extension `.exr` in place of any other, unless that is all
+/// digits and so the frame number, as in `frame.{:04}`, which `.exr` is added after
+fn with_exr_extension(path: PathBuf) -> PathBuf {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some(number) if number.bytes().all(|c| c.is_ascii_digit()) => {
+            let mut path = path.into_os_string();
+            path.push(".exr");
+            PathBuf::from(path)
+        }
+        _ => path.with_extension("exr"),
+    }
+}
+
+/// Writes the encoded frames sent down `receiver` to their files in the order of `frames`,
+/// which is every frame that will be sent, holding on to any which arrive early.
+/// Each frame is sent as one file for each of `outputs`, the paths of every frame's file.
+fn write_in_order(
+    receiver: mpsc::Receiver<(usize, Vec<Vec<u8>>)>,
+    frames: Vec<usize>,
+    outputs: &[Vec<PathBuf>],
+) -> Result<()> {
+    let mut early = HashMap::new();
+    for n in frames {
+        let png = loop {
+            if let Some(png) = early.remove(&n) {
+                break png;
+            }
+            match receiver.recv() {
+                Ok((m, png)) if m == n => break png,
+                Ok((m, png)) => {
+                    early.insert(m, png);
+                }
+                // the frames stopped coming because one failed, which is reported instead
+                Err(_) => return Ok(()),
+            }
+        };
+        for (paths, png) in outputs.iter().zip(png) {
+            let path = &paths[n];
+            fs::write(path, png)
+                .with_context(|| format!("Failed to save image file: {:?}", path))?;
+            debug!("Wrote frame {} to {:?}", n, path);
+        }
+    }
+    Ok(())
+}
+
+/// Saves an image of the change from the frame before to each frame from `first` on,
+/// next to the frame with `diff_` in front of its file name, reading back the frames
+/// which were just saved so it shows exactly what changed in them
+fn save_diff_frames(
+    paths: &[PathBuf],
+    first: usize,
+    amplify: f64,
+    png_options: &PngOptions,
+) -> Result<()> {
+    (first..paths.len()).into_par_iter().try_for_each(|n| {
+        let previous = Image::new_from_path(&paths[n - 1])?;
+        let frame = Image::new_from_path(&paths[n])?;
+        let name = paths[n].file_name().unwrap_or_default().to_string_lossy();
+        let path = paths[n].with_file_name(format!("diff_{}", name));
+        frame
+            .diff(&previous, amplify)?
+            .save_with(&path, png_options)?;
+        debug!(
+            "Wrote the change from frame {} to {} to {:?}",
+            n - 1,
+            n,
+            path
+        );
+        Ok(())
+    })
+}
+
+/// Works out the format to save in, which unless --format is given follows from the
+/// extension of the --outfile, with a directory of PNGs when there's no --outfile
+fn output_format(opt: &mut Opt) -> Result<Format> {
+    // `-o animation.gif` means the file rather than a directory of frames with that name
+    if opt.outfile.is_none() && Format::from_extension(&opt.outdir).is_some() {
+        opt.outfile = Some(opt.outdir.clone());
+    }
+    if let Some(format) = opt.format_arg {
+        return Ok(format);
+    }
+
+    let outfile = match &opt.outfile {
+        Some(outfile) => outfile,
+        None => return Ok(Format::Png),
+    };
+    Format::from_extension(outfile).ok_or_else(|| {
+        let png = outfile
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("png"));
+        if png {
+            anyhow!(
+                "{:?} could be an APNG or a sprite sheet, choose with --format apng or --format spritesheet.",
+                outfile
+            )
+        } else {
+            anyhow!(
+                "Can't tell which format to save {:?} in from its extension, give one with --format.",
+                outfile
+            )
+        }
+    })
+}
+
+/// Makes sure `outdir` exists and is ready to have frames written into it.
+///
+/// An existing directory must be empty unless `force` is set, in which case it is wiped,
+/// or `append` is set, in which case the frames are written alongside its contents.
+/// Otherwise stale frames from an earlier, longer run would be mixed in with the new ones.
+fn prepare_outdir(outdir: &Path, force: bool, append: bool) -> Result<()> {
+    if outdir.exists() {
+        let non_empty = fs::read_dir(outdir)
+            .with_context(|| format!("Failed to read output directory {:?}.", outdir))?
+            .next()
+            .is_some();
+
+        if force {
+            fs::remove_dir_all(outdir)
+                .with_context(|| format!("Failed to remove output directory {:?}.", outdir))?;
+        } else if append || !non_empty {
+            return Ok(());
+        } else {
+            bail!(
+                "Output directory {:?} already exists and isn't empty, \
+                 use --force to replace it or --append to write into it anyway.",
+                outdir
+            );
+        }
+    }
+
+    fs::create_dir_all(outdir)
+        .with_context(|| format!("Failed to create output directory {:?}.", outdir))
+}
+
+/// Reads the image paths listed in a manifest file, one per line.
+///
+/// Blank lines and lines starting with `#` are ignored, and relative paths are taken to be
+/// relative to the directory containing the manifest rather than the working directory.
+fn read_manifest(manifest: &Path) -> Result<Vec<PathBuf>> {
+    let contents = fs::read_to_string(manifest)
+        .with_context(|| format!("Failed to read the image manifest {:?}.", manifest))?;
+    let base = manifest.parent().unwrap_or_else(|| Path::new(""));
+
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| base.join(line))
+        .collect())
+}
+
+/// Reads the paths of images from standard input, one per line until it ends.
+///
+/// Unlike a manifest, each line is taken as the whole path and relative paths are
+/// relative to the current directory, just like the paths given as arguments.
+fn read_stdin_paths() -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for line in io::stdin().lock().lines() {
+        let line = line.context("Failed to read the list of images from standard input.")?;
+        let line = line.strip_suffix('\r').unwrap_or(&line);
+        if !line.is_empty() {
+            paths.push(PathBuf::from(line));
+        }
+    }
+    ensure!(
+        !paths.is_empty(),
+        "No images were given on standard input, --stdin expects a path on each line."
+    );
+    Ok(paths)
+}
+
+// only one is ever parsed, so the size of the options doesn't matter
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, StructOpt)]
+#[structopt(name = "interpol")]
+enum Command {
+    /// Generate the frames interpolating between images, which is also what happens
+    /// without a subcommand
+    Interpolate(Opt),
+    /// Print the dimensions, color type and bit depth of images as they would be loaded
+    Info(InfoOpt),
+    /// Copy the frames saved by several runs into a single sequence
+    Concat(ConcatOpt),
+    /// Interpolate between two gradients made in memory and check the frames come out
+    /// as they should, to make sure a build works without reading or writing any files
+    Check,
 }
 
 #[derive(Debug, StructOpt)]
+struct InfoOpt {
+    /// The images to describe
+    #[structopt(required = true)]
+    images: Vec<PathBuf>,
+}
+
+#[derive(Debug, StructOpt)]
+struct ConcatOpt {
+    /// The directories of frames to join together, in order, each frame's place
+    /// within its directory coming from the order of the file names
+    #[structopt(required = true)]
+    dirs: Vec<PathBuf>,
+
+    /// The directory to save the joined frames to
+    #[structopt(short, long, default_value = "frames")]
+    outdir: PathBuf,
+
+    /// The file name of each frame, see the option of the same name for interpolate
+    #[structopt(long, default_value = "frame_{:09}.png")]
+    name_pattern: NamePattern,
+
+    /// Leave out the first frame of every directory after the first, for runs which
+    /// each start on the image the last one finished on
+    #[structopt(long)]
+    skip_repeated: bool,
+
+    /// Delete the output directory and everything in it if it already exists
+    #[structopt(long)]
+    force: bool,
+
+    /// Log each frame copied
+    #[structopt(short, long, parse(from_occurrences))]
+    verbose: u8,
+}
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(
+    name = "interpol",
+    after_help = "Run `interpol info --help`, `interpol concat --help` or `interpol check --help` for the other subcommands."
+)]
 struct Opt {
     /// The images to interpolate between in the output frames
-    #[structopt(required(true), min_values(2))]
+    #[structopt(required_unless_one = &["from-file", "stdin", "batch"])]
     images: Vec<PathBuf>,
 
+    /// A file listing more images to interpolate between, one per line,
+    /// these are added after any images given as arguments
+    #[structopt(long)]
+    from_file: Option<PathBuf>,
+
+    /// Read more images to interpolate between from standard input, one path per line until
+    /// it ends, relative to the current directory. These are added after any images given as
+    /// arguments or by --from-file, and the progress bar is hidden when they are piped in
+    #[structopt(long)]
+    stdin: bool,
+
+    /// A file listing a second stack of images, one per line, matching the others image for
+    /// image. Each frame is blended with the matching frame of this stack by --stack-weight
+    #[structopt(long, conflicts_with_all = &["batch", "skip-bad-inputs", "strip-rows"])]
+    second_stack: Option<PathBuf>,
+
+    /// How far each frame is blended across to the --second-stack,
+    /// from 0.0 for just the first stack to 1.0 for just the second, 0.5 by default
+    #[structopt(long, requires = "second-stack")]
+    stack_weight: Option<f64>,
+
+    /// A TOML file of separate sequences to generate one after another, each a [[job]] table
+    /// with a list of `images` and optionally its own `outdir`, `outfile` and `n_frames`,
+    /// the rest of the settings are taken from the command line
+    #[structopt(long, conflicts_with_all = &["images", "from-file", "stdin"])]
+    batch: Option<PathBuf>,
+
+    /// The number of --batch jobs to run at the same time
+    #[structopt(long, default_value = "1")]
+    jobs: usize,
+
+    /// The number of threads to generate frames with, shared between all of the --batch jobs,
+    /// defaults to the number of logical cores
+    #[structopt(long)]
+    threads: Option<usize>,
+
     /// The directory to save the interpolated frames to
     #[structopt(short, long, default_value = "frames")]
     outdir: PathBuf,
 
-    /// The number of frames between each target image in the output frames
+    /// The file name of each frame, containing either a printf-style placeholder like %05d
+    /// or a format-style one like {:05}, the width is sized to the number of frames if left out
+    #[structopt(long, default_value = "frame_{:09}.png")]
+    name_pattern: NamePattern,
+
+    /// Write just the frame at this position through the sequence to stdout as a PNG,
+    /// from 0.0 on the first image to 1.0 on the last
+    #[structopt(long)]
+    at: Option<f64>,
+
+    /// Delete the output directory and everything in it if it already exists
+    #[structopt(long, conflicts_with = "append")]
+    force: bool,
+
+    /// Write the frames into the output directory even if it already contains files
+    #[structopt(long)]
+    append: bool,
+
+    /// Start generating from this frame of the sequence, resuming an interrupted run
+    /// by writing the remaining frames into the existing output directory
+    #[structopt(long, conflicts_with = "force")]
+    start_frame: Option<usize>,
+
+    /// Decode, blend and save each frame this many rows at a time rather than all at once,
+    /// for images too large to hold whole in memory. Only linear interpolation without
+    /// dithering or resizing of non-interlaced PNGs is supported
+    #[structopt(long)]
+    strip_rows: Option<u32>,
+
+    /// Don't generate frames whose file already exists in the output directory
+    #[structopt(long, conflicts_with = "force")]
+    skip_existing: bool,
+
+    /// The file to save the animation to, for formats which produce a single file
+    #[structopt(long)]
+    outfile: Option<PathBuf>,
+
+    /// Alongside each frame after the first save an image of how much each of its pixels
+    /// changed from the frame before, named diff_ then the frame's file name
+    #[structopt(long, conflicts_with = "strip-rows")]
+    diff_frames: bool,
+
+    /// How many times over the changes saved by --diff-frames are amplified,
+    /// so that small ones can be seen
+    #[structopt(long, default_value = "1")]
+    diff_amplify: f64,
+
+    /// Also save every frame scaled down to each of these widths, separated by commas,
+    /// keeping its aspect ratio. Each size goes in a directory named after its width inside
+    /// the output directory, e.g. frames/640/frame_000000000.png, with the frames blended
+    /// once at full size and then scaled
+    #[structopt(
+        long,
+        use_delimiter = true,
+        value_name = "WIDTHS",
+        conflicts_with = "strip-rows"
+    )]
+    sizes: Vec<u32>,
+
+    /// The filter used to scale the frames down to each of the --sizes, box or lanczos
+    /// give the smoothest results
+    #[structopt(long, default_value = "box", possible_values = &Filter::VARIANTS)]
+    sizes_filter: Filter,
+
+    /// The format to save the frames in, raw writes every frame's channel values
+    /// into the --outfile back to back, described by a sidecar TOML file, and exr saves
+    /// each frame to the --outdir as an OpenEXR of floats, as 32 bit frames must be. By default it's
+    /// worked out from the extension of the --outfile, such as .gif, .webp, .apng, .mp4
+    /// or .raw, or png without one. An --outdir named like one of these files is taken
+    /// to be the --outfile.
+    #[structopt(long = "format", possible_values = &Format::VARIANTS)]
+    format_arg: Option<Format>,
+
+    /// The format the frames are saved in, from --format or the output's extension
+    #[structopt(skip = Format::Png)]
+    format: Format,
+
+    /// Play the images from last to first, along with any --frames-between or --timeline
+    #[structopt(long)]
+    reverse: bool,
+
+    /// Leave out any images which can't be decoded rather than stopping, reporting which
+    /// images the neighbours of each one left out now transition between.
+    /// Every image is decoded up front to check it
+    #[structopt(long)]
+    skip_bad_inputs: bool,
+
+    /// Play forwards through the images then backwards to the start, leaving out the final
+    /// frame so the sequence loops seamlessly
+    #[structopt(long)]
+    ping_pong: bool,
+
+    /// Add a transition from the last image back to the first, leaving out the final frame
+    /// so the sequence loops seamlessly
+    #[structopt(long = "loop")]
+    looping: bool,
+
+    /// Resize every image to a common size instead of requiring them to match,
+    /// either "first", "largest" or an explicit WIDTHxHEIGHT
+    #[structopt(long)]
+    resize_to: Option<ResizeTarget>,
+
+    /// The filter used to sample pixels when resizing images
+    #[structopt(long, default_value = "bilinear", possible_values = &Filter::VARIANTS)]
+    resize_filter: Filter,
+
+    /// How images with a different aspect ratio to the --resize-to size are fitted to it,
+    /// contain scales them to fit inside and fills the bars either side with the
+    /// --background color or black, cover scales them to fill it and crops off the rest,
+    /// stretch distorts them
+    #[structopt(long, default_value = "stretch", possible_values = &Fit::VARIANTS)]
+    fit: Fit,
+
+    /// The part of each image kept by --fit cover, or where it sits between the bars
+    /// of --fit contain
+    #[structopt(long, default_value = "center", possible_values = &Gravity::VARIANTS)]
+    gravity: Gravity,
+
+    /// Promote every image to RGB or RGBA as it is loaded, so grayscale and color images
+    /// or images with and without alpha can be mixed. Gray values are copied into the red,
+    /// green and blue channels and missing alpha is fully opaque, alpha is never removed.
+    #[structopt(long, possible_values = &Promote::VARIANTS)]
+    promote: Option<Promote>,
+
+    /// Print the frames which would be generated and where they would be written,
+    /// reading only the image headers and without writing anything
+    #[structopt(long)]
+    dry_run: bool,
+
+    /// Save just this many frames spread evenly from the first frame to the last,
+    /// as PNGs in the --outdir, to check the transitions before a full run
+    #[structopt(
+        long,
+        conflicts_with_all = &["start-frame", "skip-existing", "strip-rows", "metadata"]
+    )]
+    preview: Option<usize>,
+
+    /// Save a grid of every --contact-every frame to this PNG, or WebP, instead, each shrunk down
+    /// and labelled with its frame number, to review the whole sequence at once
+    #[structopt(
+        long,
+        conflicts_with_all = &["preview", "start-frame", "skip-existing", "strip-rows", "metadata"]
+    )]
+    contact_sheet: Option<PathBuf>,
+
+    /// Play the frames in a window as they are generated, saving them as PNGs in the
+    /// --outdir as well unless --no-save is given. Closing the window stops the run, and
+    /// once every frame has been shown the last one stays up until it is closed. Needs
+    /// interpol to be built with the gui feature
+    #[structopt(
+        long,
+        conflicts_with_all = &[
+            "preview", "contact-sheet", "skip-existing", "strip-rows", "sizes", "diff-frames", "metadata"
+        ]
+    )]
+    preview_window: bool,
+
+    /// The most frames per second the --preview-window plays, by default --fps
+    #[cfg(feature = "gui")]
+    #[structopt(long, requires = "preview-window")]
+    max_fps: Option<f64>,
+
+    /// Only play the frames in the --preview-window, without saving them
+    #[cfg(feature = "gui")]
+    #[structopt(long, requires = "preview-window")]
+    no_save: bool,
+
+    /// How many frames apart the frames on a --contact-sheet are, the last frame is
+    /// always included
+    #[structopt(long, default_value = "10")]
+    contact_every: usize,
+
+    /// The width in pixels of each frame on a --contact-sheet
+    #[structopt(long, default_value = "160")]
+    contact_width: u32,
+
+    /// Don't show a progress bar while generating the frames
+    #[structopt(short, long)]
+    quiet: bool,
+
+    /// Don't ask before writing output estimated to take up more than 5 GB
+    #[structopt(short, long)]
+    yes: bool,
+
+    /// Log each image decoded, transition started and frame written,
+    /// give it twice to also log every frame generated
+    #[structopt(short, long, parse(from_occurrences))]
+    verbose: u8,
+
+    /// The frame rate of videos, and of --duration
+    #[structopt(long, default_value = "30")]
+    fps: f64,
+
+    /// Stamp each frame with its index or its timecode at --fps
+    #[structopt(long, default_value = "none", possible_values = &BurnIn::VARIANTS)]
+    burn_in: BurnIn,
+
+    /// The corner of each frame the --burn-in is stamped in
+    #[structopt(long, default_value = "top-left", possible_values = &Corner::VARIANTS)]
+    burn_in_pos: Corner,
+
+    /// The number of frames in each row of a sprite sheet, all of them by default,
+    /// or of a contact sheet, which is as square as possible by default
+    #[structopt(long)]
+    columns: Option<usize>,
+
+    /// The delay between each frame of a GIF, in hundredths of a second
+    #[cfg(feature = "gif")]
+    #[structopt(long, default_value = "4")]
+    delay: u16,
+
+    /// Where the colors of a GIF come from, all picks them from every frame, first from just
+    /// the first frame, and otherwise they are read from a file with one color on each line
+    #[cfg(feature = "gif")]
+    #[structopt(long, value_name = "all|first|FILE", default_value = "all")]
+    palette_from: PaletteFrom,
+
+    /// The delay between each frame of a WebP in milliseconds
+    #[cfg(feature = "webp")]
+    #[structopt(long, default_value = "40")]
+    webp_delay: u32,
+
+    /// Compress WebP frames lossily at this quality from 0 to 100, rather than losslessly
+    #[cfg(feature = "webp")]
+    #[structopt(long)]
+    webp_quality: Option<f32>,
+
+    /// How hard to compress PNGs, fast is usually plenty when the frames
+    /// are going to be encoded into a video afterwards
+    #[structopt(long, default_value = "fast", possible_values = &Compression::VARIANTS)]
+    compression: Compression,
+
+    /// The filter applied to the rows of PNGs before compressing them, adaptive gives
+    /// the smallest files and none is the quickest to write
+    #[structopt(long, default_value = "sub", possible_values = &PngFilter::VARIANTS)]
+    png_filter: PngFilter,
+
+    /// Split the image data of PNGs into IDAT chunks of at most this many bytes,
+    /// rather than a single chunk, for image readers which need smaller chunks
+    #[structopt(long)]
+    chunk_size: Option<usize>,
+
+    /// The delay between each frame of an APNG, as a fraction of a second
+    #[structopt(long, default_value = "1/25")]
+    apng_delay: FrameDelay,
+
+    /// The number of frames between each target image in the output frames,
+    /// N images produce (N - 1) * n_frames + 1 frames, ending exactly on the last image
     #[structopt(short, long, default_value = "50")]
     n_frames: usize,
-}
 
-/// This func takes 2 pixels and a float in [0.0..1.0]
-/// which represents how far to interpolate between the two
-fn smooth(mu: f64, c1: Pixel, c2: Pixel) -> Pixel {
-    let t2 = mu - mu.trunc();
-    let t1 = 1.0 - t2;
+    /// The length of each transition in seconds, at --fps frames per second, instead of
+    /// giving --n-frames, the number of frames is rounded to the nearest whole frame
+    #[structopt(long, conflicts_with_all = &["n-frames", "frames-between"])]
+    duration: Option<f64>,
 
-    Pixel {
-        r: (c1.r as f64 * t1 + c2.r as f64 * t2) as u8,
-        g: (c1.g as f64 * t1 + c2.g as f64 * t2) as u8,
-        b: (c1.b as f64 * t1 + c2.b as f64 * t2) as u8,
-    }
-}
+    /// The time in seconds at which each image is fully shown, separated by commas,
+    /// e.g. "0,1.5,5" for three images, each transition is as long as the gap at --fps
+    #[structopt(
+        long,
+        use_delimiter = true,
+        conflicts_with_all = &["n-frames", "frames-between", "duration"]
+    )]
+    timeline: Vec<f64>,
 
-/// This structure represents an image
-/// underneath its just a vector of pixels
-/// plus a width and height
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-struct Image {
-    data: Vec<Pixel>,
-    width: u32,
-    height: u32,
+    /// Share the frames out between the transitions by how much the images change, rather
+    /// than evenly, so a video spends its frames where there is the most to see, the total
+    /// number of frames stays the same
+    #[structopt(long, conflicts_with = "timeline")]
+    distribute_by_motion: bool,
+
+    /// Decode every image at the start, several at once, rather than each as it is needed,
+    /// which is quicker for many large images but holds them all in memory
+    #[structopt(long, conflicts_with = "strip-rows")]
+    preload: bool,
+
+    /// The number of frames for each transition between adjacent images, separated by commas,
+    /// e.g. "10,50,10" for four images, a single number applies to every transition
+    #[structopt(long, use_delimiter = true)]
+    frames_between: Vec<usize>,
+
+    /// The number of extra frames each image is shown for before the transition away from it,
+    /// held frames are saved as hard links to a single file where possible
+    #[structopt(long, default_value = "0")]
+    hold: usize,
+
+    /// The easing curve applied to the progress through each transition
+    #[structopt(long, default_value = "linear", possible_values = &Easing::VARIANTS)]
+    easing: Easing,
+
+    /// The easing curve for the red channel alone, replacing --easing
+    #[structopt(long, possible_values = &Easing::VARIANTS)]
+    easing_r: Option<Easing>,
+
+    /// The easing curve for the green channel alone, replacing --easing
+    #[structopt(long, possible_values = &Easing::VARIANTS)]
+    easing_g: Option<Easing>,
+
+    /// The easing curve for the blue channel alone, replacing --easing
+    #[structopt(long, possible_values = &Easing::VARIANTS)]
+    easing_b: Option<Easing>,
+
+    /// A file of eased progress values, one on each line, sampled evenly through each
+    /// transition to use as the easing curve in place of --easing, e.g. one exported from
+    /// an animation tool
+    #[structopt(long)]
+    mu_curve_from_file: Option<PathBuf>,
+
+    /// Allow the values of --mu-curve-from-file to go back down, rewinding that part
+    /// of the transition, rather than treating it as a mistake
+    #[structopt(long, requires = "mu-curve-from-file")]
+    allow_non_monotonic_curve: bool,
+
+    /// How the colors of the images are combined during each transition, multiply darkens
+    /// the middle of the transition while screen and add brighten it
+    #[structopt(long, default_value = "mix", possible_values = &BlendMode::VARIANTS)]
+    blend_mode: BlendMode,
+
+    /// How each image replaces the last, dissolve blends the whole frame at once while
+    /// the others move a region of the next image across the frame
+    #[structopt(long, default_value = "dissolve", possible_values = &Transition::VARIANTS)]
+    transition: Transition,
+
+    /// The width in pixels over which the edge of a wipe or iris transition is softened
+    #[structopt(long, default_value = "0")]
+    feather: u32,
+
+    /// Only interpolate the rectangle X,Y,WIDTH,HEIGHT of each frame, the rest of the frame
+    /// being copied from the image each transition starts on
+    #[structopt(long, value_name = "X,Y,W,H", conflicts_with = "strip-rows")]
+    roi: Option<Region>,
+
+    /// Pan and zoom across the frames, showing the rectangle START on the first frame and
+    /// moving it along the easing curve to END on the last, both given as X,Y,WIDTH,HEIGHT.
+    /// With a single image the frames pan and zoom across just that image.
+    #[structopt(
+        long,
+        number_of_values = 2,
+        value_names = &["START", "END"],
+        conflicts_with = "strip-rows"
+    )]
+    ken_burns: Vec<Region>,
+
+    /// The WIDTHxHEIGHT the --ken-burns rectangle is scaled to in each frame,
+    /// by default the size of the images
+    #[structopt(long, requires = "ken-burns")]
+    ken_burns_size: Option<ResizeTarget>,
+
+    /// Blur the motion in each frame by averaging this many blends spread over the time
+    /// up to the next frame, which takes as many times longer
+    #[structopt(long, value_name = "SAMPLES", conflicts_with = "strip-rows")]
+    motion_blur: Option<usize>,
+
+    /// Blend the color channels in linear light rather than directly on the sRGB values
+    #[structopt(long)]
+    gamma_correct: bool,
+
+    /// Weight the colors of images with transparency by their alpha while blending,
+    /// which stops transparent areas fading in and out through dark fringes
+    #[structopt(long)]
+    premultiplied: bool,
+
+    /// The color space the color channels are blended in, oklab gives the most even
+    /// looking fades and always blends in linear light
+    #[structopt(long, default_value = "rgb", possible_values = &ColorSpace::VARIANTS)]
+    color_space: ColorSpace,
+
+    /// How the frames between images are calculated, catmull-rom follows a smooth curve
+    /// through three or more images rather than changing direction sharply at each one,
+    /// morph follows the estimated motion between the images while blending them
+    #[structopt(long, default_value = "linear", possible_values = &Interpolation::VARIANTS)]
+    interpolation: Interpolation,
+
+    /// How blended 8 bit frames are dithered to avoid banding on slow fades, ordered
+    /// dithering is stable between frames while floyd-steinberg flickers when animated,
+    /// noise is random but repeats exactly for the same --seed
+    #[structopt(long, default_value = "none", possible_values = &Dither::VARIANTS)]
+    dither: Dither,
+
+    /// The seed the noise of --dither noise is generated from, runs with the same seed
+    /// write identical frames
+    #[structopt(long, default_value = "0")]
+    seed: u64,
+
+    /// The bit depth of the frames, whatever the bit depth of the images, 8 bit frames
+    /// of 16 bit or float images are dithered by --dither or otherwise rounded.
+    /// 32 bit frames are floats, which aren't clipped to the range of the images.
+    #[structopt(long, possible_values = &BitDepth::VARIANTS)]
+    output_bit_depth: Option<BitDepth>,
+
+    /// Composite images with transparency over this color, e.g. 000000 for black,
+    /// saving frames without alpha
+    #[structopt(long)]
+    background: Option<SolidColor>,
+
+    /// Keep the decoded images in this directory, so later runs with the same images can
+    /// load them from there rather than decoding them again until they are changed
+    #[structopt(long)]
+    cache_dir: Option<PathBuf>,
+
+    /// After a successful run, write a JSON file recording the inputs along with hashes
+    /// of their contents, the settings and the frames of each transition
+    #[structopt(long, conflicts_with = "batch")]
+    metadata: Option<PathBuf>,
 }
 
-impl Image {
-    fn new_from_path<P>(p: P) -> Result<Self>
-    where
-        P: AsRef<Path> + Debug,
-    {
-        let path = p.as_ref();
-        let file =
-            File::open(path).with_context(|| format!("Failed to open image file {:?}", path))?;
-        let decoder = png::Decoder::new(file);
-        let (info, mut reader) = decoder
-            .read_info()
-            .with_context(|| format!("Decoder failed to read information from {:?}", path))?;
-        let mut buf = vec![0; info.buffer_size()];
-        reader
-            .next_frame(&mut buf)
-            .with_context(|| format!("Reader failed to read any frames from {:?}", path))?;
-
-        Self::new_from_parts(buf.as_rgb().into(), info.width, info.height)
-    }
-
-    fn new_from_parts(data: Vec<Pixel>, width: u32, height: u32) -> Result<Self> {
-        ensure!(
-            data.len() as u32 == width * height,
-            "Data must match the dimensions given in width and height."
-        );
+impl Opt {
+    /// How far frames are blended across to the --second-stack, half way unless given
+    fn stack_weight(&self) -> f64 {
+        self.stack_weight.unwrap_or(0.5)
+    }
 
-        Ok(Self {
-            data,
-            width,
-            height,
-        })
+    /// The settings PNGs are encoded with
+    fn png_options(&self) -> PngOptions {
+        PngOptions {
+            compression: self.compression,
+            filter: self.png_filter,
+            chunk_size: self.chunk_size,
+        }
     }
+}
 
-    fn save<P>(&self, p: P) -> Result<()>
-    where
-        P: AsRef<Path> + Debug,
-    {
-        let path = p.as_ref();
-        let file = File::create(path).with_context(|| {
-            format!("Failed to create file at {:?} to save the image to.", path)
-        })?;
-        let ref mut w = BufWriter::new(file);
-
-        let mut encoder = png::Encoder::new(w, self.width, self.height);
-        encoder.set_color(png::ColorType::RGB);
-        encoder.set_depth(png::BitDepth::Eight);
-        let mut writer = encoder
-            .write_header()
-            .with_context(|| format!("Failed to write the header to image file: {:?}", path))?;
-
-        writer
-            .write_image_data(self.data.as_bytes())
-            .with_context(|| format!("Failed to write the data to image file: {:?}", path))?;
+/// Where the palette of a GIF comes from
+#[cfg(feature = "gif")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PaletteFrom {
+    /// Picked from the pixels of every frame
+    All,
+    /// Picked from the pixels of the first frame
+    First,
+    /// Read from a file
+    File(PathBuf),
+}
 
-        Ok(())
+#[cfg(feature = "gif")]
+impl FromStr for PaletteFrom {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "all" => Ok(PaletteFrom::All),
+            "first" => Ok(PaletteFrom::First),
+            _ => Ok(PaletteFrom::File(PathBuf::from(s))),
+        }
     }
 }
@@ -0,0 +1,263 @@
+//! Motion compensated blending, which estimates how the content of one keyframe moves
+//! to reach the next and slides the pixels along that path while cross-fading them.
+//!
+//! The motion is found by block matching on a downscaled copy of each image's brightness,
+//! giving one motion vector per block which is smoothed and then interpolated across
+//! the full resolution image.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use rayon::prelude::*;
+
+use crate::{blend::interpolate, ColorSpace, Image, Sample, Samples};
+
+/// How many pixels along each side are averaged into one when estimating motion
+const SCALE: usize = 4;
+/// The side of the square blocks matched between the images, in downscaled pixels
+const BLOCK: usize = 4;
+/// The furthest a block is searched for in each direction, in downscaled pixels
+const RADIUS: isize = 6;
+/// The cost added for each downscaled pixel a block moves,
+/// so featureless blocks prefer to stay where they are
+const MOTION_PENALTY: f32 = 0.01;
+
+/// A coarse field of motion vectors describing where the content of one image
+/// has moved to in another
+#[derive(Debug)]
+pub(crate) struct Flow {
+    columns: usize,
+    rows: usize,
+    /// The displacement of each block from the first image to the second, in pixels
+    vectors: Vec<(f32, f32)>,
+}
+
+impl Flow {
+    /// Estimates the motion from `from` to `to`, which must have the same dimensions
+    pub(crate) fn estimate(from: &Image, to: &Image) -> Flow {
+        let (a, b) = (Luma::new(from), Luma::new(to));
+        let columns = a.width.div_ceil(BLOCK);
+        let rows = a.height.div_ceil(BLOCK);
+
+        let vectors: Vec<_> = (0..columns * rows)
+            .into_par_iter()
+            .map(|i| {
+                let (x, y) = (
+                    (i % columns * BLOCK) as isize,
+                    (i / columns * BLOCK) as isize,
+                );
+                let mut best = ((0, 0), f32::INFINITY);
+                for dy in -RADIUS..=RADIUS {
+                    for dx in -RADIUS..=RADIUS {
+                        let cost = a.block_difference(&b, x, y, dx, dy)
+                            + MOTION_PENALTY * (dx.abs() + dy.abs()) as f32;
+                        if cost < best.1 {
+                            best = ((dx, dy), cost);
+                        }
+                    }
+                }
+
+                let ((dx, dy), _) = best;
+                ((dx * SCALE as isize) as f32, (dy * SCALE as isize) as f32)
+            })
+            .collect();
+
+        Flow {
+            columns,
+            rows,
+            vectors,
+        }
+        .median_filtered()
+    }
+
+    /// Replaces each vector with the median of its neighbourhood,
+    /// removing the odd block which matched somewhere unrelated
+    fn median_filtered(self) -> Flow {
+        let median = |mut values: Vec<f32>| {
+            values.sort_by(f32::total_cmp);
+            values[values.len() / 2]
+        };
+
+        let vectors = (0..self.rows)
+            .flat_map(|row| (0..self.columns).map(move |column| (column, row)))
+            .map(|(column, row)| {
+                let neighbours: Vec<_> = (row.saturating_sub(1)..(row + 2).min(self.rows))
+                    .flat_map(|r| {
+                        (column.saturating_sub(1)..(column + 2).min(self.columns))
+                            .map(move |c| (c, r))
+                    })
+                    .map(|(c, r)| self.vectors[r * self.columns + c])
+                    .collect();
+
+                (
+                    median(neighbours.iter().map(|v| v.0).collect()),
+                    median(neighbours.iter().map(|v| v.1).collect()),
+                )
+            })
+            .collect();
+
+        Flow { vectors, ..self }
+    }
+
+    /// The motion at the pixel (`x`, `y`), interpolated between the centres of the blocks
+    fn at(&self, x: f32, y: f32) -> (f32, f32) {
+        let size = (SCALE * BLOCK) as f32;
+        let gx = (x / size - 0.5).clamp(0.0, (self.columns - 1) as f32);
+        let gy = (y / size - 0.5).clamp(0.0, (self.rows - 1) as f32);
+        let (x0, y0) = (gx.floor() as usize, gy.floor() as usize);
+        let (x1, y1) = ((x0 + 1).min(self.columns - 1), (y0 + 1).min(self.rows - 1));
+        let (tx, ty) = (gx - x0 as f32, gy - y0 as f32);
+
+        let vector = |c: usize, r: usize| self.vectors[r * self.columns + c];
+        let lerp =
+            |a: (f32, f32), b: (f32, f32), t: f32| (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t);
+        let top = lerp(vector(x0, y0), vector(x1, y0), tx);
+        let bottom = lerp(vector(x0, y1), vector(x1, y1), tx);
+        lerp(top, bottom, ty)
+    }
+}
+
+/// The motion between pairs of keyframes, keyed by the indices of the keyframes
+/// and estimated the first time each pair is blended
+#[derive(Debug, Default)]
+pub(crate) struct FlowCache(Mutex<HashMap<(usize, usize), Arc<Flow>>>);
+
+impl FlowCache {
+    pub(crate) fn get(&self, keys: (usize, usize), from: &Image, to: &Image) -> Arc<Flow> {
+        let mut flows = self.0.lock().unwrap();
+        let flow = flows
+            .entry(keys)
+            .or_insert_with(|| Arc::new(Flow::estimate(from, to)));
+        Arc::clone(flow)
+    }
+}
+
+/// A downscaled copy of an image's brightness, used for matching blocks
+struct Luma {
+    width: usize,
+    height: usize,
+    values: Vec<f32>,
+}
+
+impl Luma {
+    fn new(image: &Image) -> Luma {
+        match &image.data {
+            Samples::Eight(data) => Self::from_samples(data, image),
+            Samples::Sixteen(data) => Self::from_samples(data, image),
+            Samples::Float(data) => Self::from_samples(data, image),
+        }
+    }
+
+    fn from_samples<S: Sample>(data: &[S], image: &Image) -> Luma {
+        let channels = image.color_type.channels();
+        let gray = image.color_type.color_channels() == 1;
+        let (src_width, src_height) = (image.width as usize, image.height as usize);
+        let (width, height) = (src_width.div_ceil(SCALE), src_height.div_ceil(SCALE));
+
+        let mut sums = vec![0.0; width * height];
+        let mut counts = vec![0u32; width * height];
+        for (i, pixel) in data.chunks_exact(channels).enumerate() {
+            let (x, y) = (i % src_width, i / src_width);
+            let luma = if gray {
+                pixel[0].to_normalized()
+            } else {
+                0.299 * pixel[0].to_normalized()
+                    + 0.587 * pixel[1].to_normalized()
+                    + 0.114 * pixel[2].to_normalized()
+            };
+
+            let cell = y / SCALE * width + x / SCALE;
+            sums[cell] += luma as f32;
+            counts[cell] += 1;
+        }
+
+        Luma {
+            width,
+            height,
+            values: sums
+                .iter()
+                .zip(&counts)
+                .map(|(s, &c)| s / c as f32)
+                .collect(),
+        }
+    }
+
+    /// The brightness at (`x`, `y`), repeating the edge pixels outside the image
+    fn get(&self, x: isize, y: isize) -> f32 {
+        let x = x.clamp(0, self.width as isize - 1) as usize;
+        let y = y.clamp(0, self.height as isize - 1) as usize;
+        self.values[y * self.width + x]
+    }
+
+    /// The sum of absolute differences between the block at (`x`, `y`) in this image
+    /// and the block offset from it by (`dx`, `dy`) in `other`
+    fn block_difference(&self, other: &Luma, x: isize, y: isize, dx: isize, dy: isize) -> f32 {
+        let right = (x + BLOCK as isize).min(self.width as isize);
+        let bottom = (y + BLOCK as isize).min(self.height as isize);
+        (y..bottom)
+            .flat_map(|y| (x..right).map(move |x| (x, y)))
+            .map(|(x, y)| (self.get(x, y) - other.get(x + dx, y + dy)).abs())
+            .sum()
+    }
+}
+
+/// Blends two images `mu` of the way along the motion between them,
+/// moving the content of each part of the way before mixing them
+pub(crate) fn morph(
+    mu: f64,
+    im1: &Image,
+    im2: &Image,
+    flow: &Flow,
+    color_space: ColorSpace,
+    gamma_correct: bool,
+) -> Image {
+    let start = warp(im1, flow, -mu as f32);
+    let end = warp(im2, flow, (1.0 - mu) as f32);
+    interpolate(mu, &start, &end, color_space, gamma_correct)
+}
+
+/// Resamples an image, each pixel being taken from `amount` of the motion away from it
+fn warp(image: &Image, flow: &Flow, amount: f32) -> Image {
+    let data = match &image.data {
+        Samples::Eight(data) => Samples::Eight(warp_samples(data, image, flow, amount)),
+        Samples::Sixteen(data) => Samples::Sixteen(warp_samples(data, image, flow, amount)),
+        Samples::Float(data) => Samples::Float(warp_samples(data, image, flow, amount)),
+    };
+
+    Image {
+        data,
+        profile: image.profile.clone(),
+        ..*image
+    }
+}
+
+fn warp_samples<S: Sample>(data: &[S], image: &Image, flow: &Flow, amount: f32) -> Vec<S> {
+    let channels = image.color_type.channels();
+    let (width, height) = (image.width as usize, image.height as usize);
+    let pixel = |x: usize, y: usize| &data[(y * width + x) * channels..][..channels];
+
+    (0..height)
+        .into_par_iter()
+        .flat_map_iter(|y| {
+            (0..width).flat_map(move |x| {
+                let (fx, fy) = flow.at(x as f32 + 0.5, y as f32 + 0.5);
+                let src_x = (x as f32 + fx * amount).clamp(0.0, (width - 1) as f32);
+                let src_y = (y as f32 + fy * amount).clamp(0.0, (height - 1) as f32);
+
+                let (x0, y0) = (src_x.floor() as usize, src_y.floor() as usize);
+                let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+                let (tx, ty) = ((src_x - x0 as f32) as f64, (src_y - y0 as f32) as f64);
+
+                let (p00, p10) = (pixel(x0, y0), pixel(x1, y0));
+                let (p01, p11) = (pixel(x0, y1), pixel(x1, y1));
+                (0..channels).map(move |c| {
+                    let top = p00[c].to_f64() * (1.0 - tx) + p10[c].to_f64() * tx;
+                    let bottom = p01[c].to_f64() * (1.0 - tx) + p11[c].to_f64() * tx;
+                    S::from_f64(top * (1.0 - ty) + bottom * ty)
+                })
+            })
+        })
+        .collect()
+}
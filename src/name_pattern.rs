@@ -0,0 +1,119 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, ensure, Result};
+
+/// A template for the file names of the output frames, made up of some literal text
+/// around a single placeholder which is replaced with the frame number.
+///
+/// The placeholder can be written printf-style as `%d` or `%05d`,
+/// or format-style as `{}` or `{:05}`, where the number is the zero padded width.
+/// `%%`, `{{` and `}}` can be used for literal percent signs and braces.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NamePattern {
+    prefix: String,
+    /// The zero padded width of the frame number,
+    /// if this is `None` it is sized to fit the total number of frames
+    width: Option<usize>,
+    suffix: String,
+}
+
+impl NamePattern {
+    /// The file name of frame `n` out of `total_frames`
+    pub fn format(&self, n: usize, total_frames: usize) -> String {
+        let width = self.width.unwrap_or_else(|| total_frames.to_string().len());
+        format!("{}{:0width$}{}", self.prefix, n, self.suffix, width = width)
+    }
+}
+
+impl FromStr for NamePattern {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut prefix = String::new();
+        let mut suffix = String::new();
+        let mut width = None;
+        let mut seen_placeholder = false;
+
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            let placeholder = match (c, chars.peek()) {
+                ('%', Some('%')) | ('{', Some('{')) | ('}', Some('}')) => {
+                    chars.next();
+                    None
+                }
+                ('%', _) => {
+                    let digits: String =
+                        std::iter::from_fn(|| chars.next_if(char::is_ascii_digit)).collect();
+                    ensure!(
+                        chars.next() == Some('d'),
+                        "Name pattern {:?} contains a % which isn't followed by d or 0Nd.",
+                        s
+                    );
+                    Some(digits)
+                }
+                ('{', _) => {
+                    let spec: String =
+                        std::iter::from_fn(|| chars.next_if(|&c| c != '}')).collect();
+                    ensure!(
+                        chars.next() == Some('}'),
+                        "Name pattern {:?} contains an unclosed {{.",
+                        s
+                    );
+                    let digits = match spec.strip_prefix(':') {
+                        Some(digits) => digits.to_string(),
+                        None if spec.is_empty() => String::new(),
+                        None => bail!("Name pattern {:?} contains an invalid {{{}}}.", s, spec),
+                    };
+                    ensure!(
+                        digits.chars().all(|c| c.is_ascii_digit()),
+                        "Name pattern {:?} contains an invalid width in {{{}}}.",
+                        s,
+                        spec
+                    );
+                    Some(digits)
+                }
+                ('}', _) => bail!("Name pattern {:?} contains an unmatched }}.", s),
+                _ => {
+                    if seen_placeholder {
+                        suffix.push(c);
+                    } else {
+                        prefix.push(c);
+                    }
+                    continue;
+                }
+            };
+
+            match placeholder {
+                Some(digits) => {
+                    ensure!(
+                        !seen_placeholder,
+                        "Name pattern {:?} must contain exactly one frame number placeholder.",
+                        s
+                    );
+                    seen_placeholder = true;
+                    width = if digits.is_empty() {
+                        None
+                    } else {
+                        Some(digits.parse()?)
+                    };
+                }
+                // an escaped character
+                None if seen_placeholder => suffix.push(c),
+                None => prefix.push(c),
+            }
+        }
+
+        if !seen_placeholder {
+            return Err(anyhow!(
+                "Name pattern {:?} must contain a frame number placeholder such as %05d or {{:05}}.",
+                s
+            ));
+        }
+
+        Ok(Self {
+            prefix,
+            width,
+            suffix,
+        })
+    }
+}
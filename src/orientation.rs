@@ -0,0 +1,147 @@
+use std::{io::BufReader, path::Path};
+
+use anyhow::{Context, Result};
+use log::warn;
+
+use crate::{error::open_image, Image, ImageInfo, Sample, Samples};
+
+/// The EXIF orientation of an image, describing how its stored pixels must be
+/// flipped and rotated to be displayed the right way up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Orientation {
+    /// 1: stored upright
+    Normal,
+    /// 2: mirrored left to right
+    FlipHorizontal,
+    /// 3: upside down
+    Rotate180,
+    /// 4: mirrored top to bottom
+    FlipVertical,
+    /// 5: mirrored across the diagonal from the top left corner
+    Transpose,
+    /// 6: needs rotating a quarter turn clockwise
+    Rotate90,
+    /// 7: mirrored across the diagonal from the top right corner
+    Transverse,
+    /// 8: needs rotating a quarter turn anticlockwise
+    Rotate270,
+}
+
+impl Orientation {
+    /// Reads the orientation from the EXIF data of the image at `path`, embedded in the
+    /// `eXIf` chunk of a PNG or the APP1 segment of a JPEG.
+    ///
+    /// Images without any EXIF data are upright, as are any with EXIF data which can't be
+    /// understood, though that is logged as a warning.
+    pub(crate) fn from_path(path: &Path) -> Result<Self> {
+        let file = open_image(path)?;
+        let exif = match exif::Reader::new().read_from_container(&mut BufReader::new(file)) {
+            Ok(exif) => exif,
+            Err(exif::Error::NotFound(_)) => return Ok(Orientation::Normal),
+            Err(exif::Error::Io(e)) => {
+                return Err(e).with_context(|| format!("Failed to read EXIF data from {:?}", path))
+            }
+            Err(e) => {
+                warn!("Ignoring the unreadable EXIF data in {:?}: {}", path, e);
+                return Ok(Orientation::Normal);
+            }
+        };
+
+        let value = exif
+            .get_field(exif::Tag::Orientation, exif::In::PRIMARY)
+            .and_then(|field| field.value.get_uint(0));
+        Ok(match value {
+            None | Some(1) => Orientation::Normal,
+            Some(2) => Orientation::FlipHorizontal,
+            Some(3) => Orientation::Rotate180,
+            Some(4) => Orientation::FlipVertical,
+            Some(5) => Orientation::Transpose,
+            Some(6) => Orientation::Rotate90,
+            Some(7) => Orientation::Transverse,
+            Some(8) => Orientation::Rotate270,
+            Some(other) => {
+                warn!(
+                    "Ignoring the unknown EXIF orientation {} of {:?}",
+                    other, path
+                );
+                Orientation::Normal
+            }
+        })
+    }
+
+    /// Whether the image's width and height are swapped when it is turned upright
+    pub(crate) fn swaps_dimensions(self) -> bool {
+        matches!(
+            self,
+            Orientation::Transpose
+                | Orientation::Rotate90
+                | Orientation::Transverse
+                | Orientation::Rotate270
+        )
+    }
+
+    /// The properties of an image described by `info` once it has been turned upright
+    pub(crate) fn apply_to_info(self, info: ImageInfo) -> ImageInfo {
+        if self.swaps_dimensions() {
+            ImageInfo {
+                width: info.height,
+                height: info.width,
+                ..info
+            }
+        } else {
+            info
+        }
+    }
+
+    /// The position in a `width` by `height` stored image of the pixel displayed at (`x`, `y`)
+    fn source(self, x: usize, y: usize, width: usize, height: usize) -> (usize, usize) {
+        match self {
+            Orientation::Normal => (x, y),
+            Orientation::FlipHorizontal => (width - 1 - x, y),
+            Orientation::Rotate180 => (width - 1 - x, height - 1 - y),
+            Orientation::FlipVertical => (x, height - 1 - y),
+            Orientation::Transpose => (y, x),
+            Orientation::Rotate90 => (y, height - 1 - x),
+            Orientation::Transverse => (width - 1 - y, height - 1 - x),
+            Orientation::Rotate270 => (width - 1 - y, x),
+        }
+    }
+}
+
+impl Image {
+    /// The image flipped and rotated so that it is displayed upright given `orientation`
+    pub(crate) fn oriented(self, orientation: Orientation) -> Image {
+        if orientation == Orientation::Normal {
+            return self;
+        }
+
+        let data = match &self.data {
+            Samples::Eight(data) => Samples::Eight(self.reorient(data, orientation)),
+            Samples::Sixteen(data) => Samples::Sixteen(self.reorient(data, orientation)),
+            Samples::Float(data) => Samples::Float(self.reorient(data, orientation)),
+        };
+        let info = orientation.apply_to_info(self.info());
+        Image {
+            data,
+            width: info.width,
+            height: info.height,
+            ..self
+        }
+    }
+
+    fn reorient<S: Sample>(&self, data: &[S], orientation: Orientation) -> Vec<S> {
+        let channels = self.color_type.channels();
+        let (width, height) = (self.width as usize, self.height as usize);
+        let info = orientation.apply_to_info(self.info());
+        let (out_width, out_height) = (info.width as usize, info.height as usize);
+
+        let mut out = Vec::with_capacity(data.len());
+        for y in 0..out_height {
+            for x in 0..out_width {
+                let (sx, sy) = orientation.source(x, y, width, height);
+                out.extend_from_slice(&data[(sy * width + sx) * channels..][..channels]);
+            }
+        }
+        out
+    }
+}
@@ -0,0 +1,473 @@
+#[cfg(feature = "gif")]
+use std::borrow::Cow;
+use std::{
+    fmt::Debug,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    str::FromStr,
+};
+#[cfg(feature = "video")]
+use std::{
+    io,
+    process::{Command, Stdio},
+};
+
+use anyhow::{anyhow, ensure, Context, Result};
+#[cfg(feature = "gif")]
+use rayon::prelude::*;
+
+#[cfg(feature = "gif")]
+use crate::Palette;
+use crate::{BitDepth, ColorType, Image, ImageInfo};
+
+/// The ways the generated frames can be written out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Format {
+    /// A directory containing one PNG file per frame
+    Png,
+    /// A single animated GIF containing every frame
+    Gif,
+    /// A single animated PNG containing every frame
+    Apng,
+    /// A video encoded by piping the frames through ffmpeg
+    Video,
+    /// A single PNG with every frame tiled in a grid
+    Spritesheet,
+    /// A single animated WebP containing every frame
+    Webp,
+    /// A single file of every frame's raw channel values one after another,
+    /// described by a sidecar file
+    Raw,
+    /// A directory containing one OpenEXR file of 32 bit floats per frame
+    Exr,
+}
+
+impl Format {
+    pub const VARIANTS: [&'static str; 8] = [
+        "png",
+        "gif",
+        "apng",
+        "video",
+        "spritesheet",
+        "webp",
+        "raw",
+        "exr",
+    ];
+
+    /// The Cargo feature needed to save in this format, if interpol was built without it
+    pub fn missing_feature(self) -> Option<&'static str> {
+        match self {
+            Format::Gif if !cfg!(feature = "gif") => Some("gif"),
+            Format::Webp if !cfg!(feature = "webp") => Some("webp"),
+            Format::Video if !cfg!(feature = "video") => Some("video"),
+            Format::Exr if !cfg!(feature = "exr") => Some("exr"),
+            _ => None,
+        }
+    }
+
+    /// The format a single file saved to `path` is in going by its extension, if that names
+    /// one. A `.png` file names none, as it could be either an APNG or a sprite sheet.
+    pub fn from_extension(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "gif" => Some(Format::Gif),
+            "apng" => Some(Format::Apng),
+            "webp" => Some(Format::Webp),
+            "mp4" | "mkv" | "mov" | "webm" | "avi" => Some(Format::Video),
+            "raw" | "rgb" | "rgba" => Some(Format::Raw),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "png" => Ok(Format::Png),
+            "gif" => Ok(Format::Gif),
+            "apng" => Ok(Format::Apng),
+            "video" => Ok(Format::Video),
+            "spritesheet" => Ok(Format::Spritesheet),
+            "webp" => Ok(Format::Webp),
+            "raw" => Ok(Format::Raw),
+            "exr" => Ok(Format::Exr),
+            _ => Err(anyhow!("Unknown output format {:?}.", s)),
+        }
+    }
+}
+
+/// How long each frame of an APNG is shown for, as a fraction of a second
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FrameDelay {
+    pub numerator: u16,
+    pub denominator: u16,
+}
+
+impl FromStr for FrameDelay {
+    type Err = anyhow::Error;
+
+    /// Parses a delay written as `numerator/denominator`, e.g. `1/25`
+    fn from_str(s: &str) -> Result<Self> {
+        let (numerator, denominator) = s
+            .split_once('/')
+            .ok_or_else(|| anyhow!("Frame delay {:?} must be of the form NUM/DEN.", s))?;
+        let numerator = numerator
+            .trim()
+            .parse()
+            .with_context(|| format!("Invalid frame delay numerator in {:?}.", s))?;
+        let denominator = denominator
+            .trim()
+            .parse()
+            .with_context(|| format!("Invalid frame delay denominator in {:?}.", s))?;
+        ensure!(
+            denominator != 0,
+            "Frame delay {:?} must have a non-zero denominator.",
+            s
+        );
+
+        Ok(Self {
+            numerator,
+            denominator,
+        })
+    }
+}
+
+/// Writes the frames to `p` as a looping animated GIF, showing each frame for
+/// `delay` hundredths of a second.
+///
+/// GIF frames are limited to 256 colors, so a single global palette is computed across
+/// every frame by `Palette::median_cut`, which stops the colors flickering as the frames
+/// change. Pixels of RGBA frames which are less than half opaque become fully transparent.
+#[cfg(feature = "gif")]
+pub fn save_gif<P>(frames: &[Image], p: P, delay: u16) -> Result<()>
+where
+    P: AsRef<Path> + Debug,
+{
+    let palette = Palette::median_cut(frames)?;
+    save_gif_with_palette(frames, p, delay, &palette)
+}
+
+/// Writes the frames to `p` as a looping animated GIF in the same way as `save_gif`,
+/// with every pixel mapped to the nearest color of `palette`.
+///
+/// Frames with alpha need one entry left over for transparent pixels, so then the palette
+/// can have at most 255 colors.
+#[cfg(feature = "gif")]
+pub fn save_gif_with_palette<P>(frames: &[Image], p: P, delay: u16, palette: &Palette) -> Result<()>
+where
+    P: AsRef<Path> + Debug,
+{
+    let path = p.as_ref();
+    let first = frames
+        .first()
+        .ok_or_else(|| anyhow!("There are no frames to save to {:?}.", path))?;
+    ensure!(
+        first.width <= u16::MAX as u32 && first.height <= u16::MAX as u32,
+        "GIF images can be at most {0}x{0} pixels, but the frames are {1}x{2}.",
+        u16::MAX,
+        first.width,
+        first.height
+    );
+
+    // transparent pixels take the entry after the last color of the palette
+    let color_type = first.color_type;
+    let transparent = if color_type.has_alpha() {
+        ensure!(
+            palette.colors().len() < 256,
+            "The palette has 256 colors, but the frames have alpha so one must be left for transparent pixels."
+        );
+        Some(palette.colors().len() as u8)
+    } else {
+        None
+    };
+    let mut table = palette.to_rgb_bytes();
+    table.resize(256 * 3, 0);
+
+    let file = File::create(path)
+        .with_context(|| format!("Failed to create file at {:?} to save the GIF to.", path))?;
+    let mut encoder = gif::Encoder::new(
+        BufWriter::new(file),
+        first.width as u16,
+        first.height as u16,
+        &table,
+    )
+    .with_context(|| format!("Failed to write the header to GIF file: {:?}", path))?;
+    encoder
+        .set_repeat(gif::Repeat::Infinite)
+        .with_context(|| format!("Failed to set the GIF {:?} to loop.", path))?;
+
+    // GIF can only store 8 bit colors
+    let buffers: Vec<_> = frames
+        .par_iter()
+        .map(|image| {
+            palette.indices(
+                &image.data.to_eight_bit(),
+                color_type,
+                transparent.unwrap_or(0),
+            )
+        })
+        .collect();
+    for (image, buffer) in frames.iter().zip(buffers) {
+        let frame = gif::Frame {
+            width: image.width as u16,
+            height: image.height as u16,
+            delay,
+            transparent,
+            buffer: Cow::Owned(buffer),
+            ..gif::Frame::default()
+        };
+        encoder
+            .write_frame(&frame)
+            .with_context(|| format!("Failed to write a frame to GIF file: {:?}", path))?;
+    }
+
+    Ok(())
+}
+
+/// Writes the frames to `p` as a looping animated WebP, showing each frame for `delay`
+/// milliseconds.
+///
+/// The frames are compressed lossily at `quality` from 0 to 100 when it is given and
+/// losslessly otherwise, frames with alpha keep it and 16 bit frames are reduced to 8 bits.
+#[cfg(feature = "webp")]
+pub fn save_webp<P>(frames: &[Image], p: P, delay: u32, quality: Option<f32>) -> Result<()>
+where
+    P: AsRef<Path> + Debug,
+{
+    let path = p.as_ref();
+    let first = frames
+        .first()
+        .ok_or_else(|| anyhow!("There are no frames to save to {:?}.", path))?;
+    ensure!(
+        (frames.len() as u64 * delay as u64) < i32::MAX as u64,
+        "The WebP animation {:?} would be too long at {}ms per frame.",
+        path,
+        delay
+    );
+    if let Some(quality) = quality {
+        ensure!(
+            (0.0..=100.0).contains(&quality),
+            "The WebP quality must be between 0 and 100, got {}.",
+            quality
+        );
+    }
+
+    let mut config =
+        webp::WebPConfig::new().map_err(|_| anyhow!("Failed to set up the WebP encoder."))?;
+    match quality {
+        Some(quality) => config.quality = quality,
+        None => config.lossless = 1,
+    }
+
+    // WebP only stores 8 bit RGB or RGBA
+    let color_type = first.color_type;
+    let frame_data: Vec<Vec<u8>> = frames
+        .iter()
+        .map(|image| match color_type {
+            ColorType::Rgb | ColorType::Rgba => image.data.to_eight_bit().into_owned(),
+            ColorType::Gray => image
+                .data
+                .to_eight_bit()
+                .iter()
+                .flat_map(|&l| [l; 3])
+                .collect(),
+            ColorType::GrayAlpha => image
+                .data
+                .to_eight_bit()
+                .chunks_exact(2)
+                .flat_map(|px| [px[0], px[0], px[0], px[1]])
+                .collect(),
+        })
+        .collect();
+
+    let mut encoder = webp::AnimEncoder::new(first.width, first.height, &config);
+    encoder.set_loop_count(0);
+    for (n, data) in frame_data.iter().enumerate() {
+        let timestamp = (n as u32 * delay) as i32;
+        encoder.add_frame(if color_type.has_alpha() {
+            webp::AnimFrame::from_rgba(data, first.width, first.height, timestamp)
+        } else {
+            webp::AnimFrame::from_rgb(data, first.width, first.height, timestamp)
+        });
+    }
+    let webp = encoder
+        .try_encode()
+        .map_err(|e| anyhow!("Failed to encode the WebP {:?}: {:?}", path, e))?;
+
+    std::fs::write(path, &*webp).with_context(|| format!("Failed to write WebP file: {:?}", path))
+}
+
+/// Writes the frames to `p` as a looping animated PNG, showing each frame for `delay`.
+///
+/// The frames are encoded one at a time as they are generated,
+/// so the whole animation never has to be held in memory.
+pub fn save_apng<P, I>(frames: I, p: P, delay: FrameDelay) -> Result<()>
+where
+    P: AsRef<Path> + Debug,
+    I: ExactSizeIterator<Item = Result<Image>>,
+{
+    let path = p.as_ref();
+    let num_frames = frames.len();
+    let mut frames = frames;
+    let first = match frames.next() {
+        Some(image) => image?,
+        None => return Err(anyhow!("There are no frames to save to {:?}.", path)),
+    };
+
+    let file = File::create(path)
+        .with_context(|| format!("Failed to create file at {:?} to save the APNG to.", path))?;
+    let mut encoder = first.png_encoder(BufWriter::new(file))?;
+    encoder
+        .set_animated(num_frames as u32, 0)
+        .and_then(|_| encoder.set_frame_delay(delay.numerator, delay.denominator))
+        .with_context(|| format!("Failed to set up the animation for APNG file: {:?}", path))?;
+    let mut writer = encoder
+        .write_header()
+        .with_context(|| format!("Failed to write the header to APNG file: {:?}", path))?;
+
+    writer
+        .write_image_data(&first.data.to_be_bytes())
+        .with_context(|| format!("Failed to write a frame to APNG file: {:?}", path))?;
+    for image in frames {
+        writer
+            .write_image_data(&image?.data.to_be_bytes())
+            .with_context(|| format!("Failed to write a frame to APNG file: {:?}", path))?;
+    }
+
+    writer
+        .finish()
+        .with_context(|| format!("Failed to finish writing APNG file: {:?}", path))
+}
+
+/// Encodes the frames into a video at `p` by piping them into `ffmpeg` as raw pixels,
+/// at `fps` frames per second.
+///
+/// ffmpeg picks the codec from the extension of `p`, for example H.264 for `.mp4` files.
+/// Odd widths and heights are padded by a pixel as most codecs need even dimensions.
+#[cfg(feature = "video")]
+pub fn save_video<P, I>(frames: I, p: P, fps: f64) -> Result<()>
+where
+    P: AsRef<Path> + Debug,
+    I: Iterator<Item = Result<Image>>,
+{
+    let path = p.as_ref();
+    let mut frames = frames;
+    let first = match frames.next() {
+        Some(image) => image?,
+        None => return Err(anyhow!("There are no frames to save to {:?}.", path)),
+    };
+    let pix_fmt = pix_fmt(first.info());
+
+    let mut child = Command::new("ffmpeg")
+        .args(["-hide_banner", "-loglevel", "error", "-y"])
+        .args(["-f", "rawvideo", "-pix_fmt", pix_fmt])
+        .args(["-s", &format!("{}x{}", first.width, first.height)])
+        .args(["-r", &fps.to_string(), "-i", "-"])
+        .args([
+            "-vf",
+            "pad=ceil(iw/2)*2:ceil(ih/2)*2",
+            "-pix_fmt",
+            "yuv420p",
+        ])
+        .arg(path)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => {
+                anyhow!(
+                    "Failed to find ffmpeg, it must be installed and on the PATH to save videos."
+                )
+            }
+            _ => anyhow::Error::new(e).context("Failed to start ffmpeg."),
+        })?;
+
+    // close stdin once all the frames are written so ffmpeg knows to finish
+    let written = {
+        let mut stdin = BufWriter::new(child.stdin.take().expect("stdin is piped"));
+        std::iter::once(Ok(first))
+            .chain(frames)
+            .try_for_each(|image| -> Result<()> {
+                stdin
+                    .write_all(&image?.data.to_be_bytes())
+                    .context("Failed to write a frame to ffmpeg.")
+            })
+            .and_then(|_| stdin.flush().context("Failed to write a frame to ffmpeg."))
+    };
+
+    let status = child
+        .wait()
+        .context("Failed to wait for ffmpeg to finish.")?;
+    ensure!(
+        status.success(),
+        "ffmpeg failed to encode the video {:?}, exiting with {}.",
+        path,
+        status
+    );
+    written
+}
+
+/// The name ffmpeg gives to raw frames laid out like images described by `info`
+fn pix_fmt(info: ImageInfo) -> &'static str {
+    match (info.color_type, info.bit_depth) {
+        (ColorType::Gray, BitDepth::Eight) => "gray",
+        (ColorType::GrayAlpha, BitDepth::Eight) => "ya8",
+        (ColorType::Gray, BitDepth::Sixteen) => "gray16be",
+        (ColorType::GrayAlpha, BitDepth::Sixteen) => "ya16be",
+        (ColorType::Rgb, BitDepth::Eight) => "rgb24",
+        (ColorType::Rgba, BitDepth::Eight) => "rgba",
+        (ColorType::Rgb, BitDepth::Sixteen) => "rgb48be",
+        (ColorType::Rgba, BitDepth::Sixteen) => "rgba64be",
+        (ColorType::Gray, BitDepth::Float) => "grayf32be",
+        (ColorType::GrayAlpha, BitDepth::Float) => "yaf32be",
+        (ColorType::Rgb, BitDepth::Float) => "rgbf32be",
+        (ColorType::Rgba, BitDepth::Float) => "rgbaf32be",
+    }
+}
+
+/// Writes the channel values of every frame to `p` one after another with no headers,
+/// in the layout of [`Image::write_raw`], for piping into other tools.
+///
+/// The size and layout of the frames are written to a TOML file alongside it, named
+/// after `p` with `.toml` added, including the ffmpeg `pix_fmt` which reads the frames.
+pub fn save_raw<P, I>(frames: I, p: P) -> Result<()>
+where
+    P: AsRef<Path> + Debug,
+    I: Iterator<Item = Result<Image>>,
+{
+    let path = p.as_ref();
+    let file = File::create(path)
+        .with_context(|| format!("Failed to create file at {:?} to save the frames to.", path))?;
+    let mut writer = BufWriter::new(file);
+
+    let mut info = None;
+    let mut count = 0;
+    for image in frames {
+        let image = image?;
+        info.get_or_insert(image.info());
+        image
+            .write_raw(&mut writer)
+            .with_context(|| format!("Failed to write a frame to {:?}", path))?;
+        count += 1;
+    }
+    writer
+        .flush()
+        .with_context(|| format!("Failed to write a frame to {:?}", path))?;
+    let info = info.ok_or_else(|| anyhow!("There are no frames to save to {:?}.", path))?;
+
+    let mut sidecar = path.as_os_str().to_owned();
+    sidecar.push(".toml");
+    let description = format!(
+        "width = {}\nheight = {}\nframes = {}\ncolor_type = {:?}\nbit_depth = {}\npix_fmt = {:?}\n",
+        info.width,
+        info.height,
+        count,
+        info.color_type.name(),
+        info.bit_depth.bits(),
+        pix_fmt(info)
+    );
+    std::fs::write(&sidecar, description)
+        .with_context(|| format!("Failed to write the frame description to {:?}", sidecar))
+}
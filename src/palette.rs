@@ -0,0 +1,202 @@
+use std::{collections::HashMap, fmt::Debug, fs, path::Path};
+
+use anyhow::{anyhow, ensure, Context, Result};
+
+use crate::{ColorType, Image, SolidColor};
+
+/// The maximum number of pixels sampled across all the frames when building a palette
+const PALETTE_SAMPLES: usize = 1 << 20;
+
+/// A table of at most 256 colors for saving images with, such as the frames of a GIF
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Palette {
+    colors: Vec<[u8; 3]>,
+}
+
+impl Palette {
+    /// Creates a palette of `colors`, of which there must be between 1 and 256
+    pub fn new(colors: Vec<[u8; 3]>) -> Result<Self> {
+        ensure!(
+            (1..=256).contains(&colors.len()),
+            "A palette must have between 1 and 256 colors, not {}.",
+            colors.len()
+        );
+        Ok(Self { colors })
+    }
+
+    /// Reads a palette from the file at `p`, which has one color on each line written as
+    /// `#rrggbb` or `color:r,g,b`, ignoring blank lines
+    pub fn from_path<P>(p: P) -> Result<Self>
+    where
+        P: AsRef<Path> + Debug,
+    {
+        let path = p.as_ref();
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read the palette {:?}", path))?;
+        let colors = contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| match line.trim().parse() {
+                Ok(SolidColor { rgb, size: None }) => Ok(rgb),
+                _ => Err(anyhow!(
+                    "Line {} of the palette {:?} isn't a color such as #ff8000: {:?}",
+                    i + 1,
+                    path,
+                    line
+                )),
+            })
+            .collect::<Result<_>>()?;
+        Self::new(colors).with_context(|| format!("Failed to load the palette {:?}", path))
+    }
+
+    /// Picks the colors which best represent the opaque pixels of every one of `frames` by
+    /// median cut, 256 of them or 255 when the frames have alpha, leaving an entry for
+    /// transparent pixels.
+    ///
+    /// Pixels are sampled evenly across all of the frames, so the palette suits them as a
+    /// whole and doesn't change from one frame to the next.
+    pub fn median_cut(frames: &[Image]) -> Result<Self> {
+        let first = frames
+            .first()
+            .ok_or_else(|| anyhow!("There are no frames to take a palette from."))?;
+        let color_type = first.color_type;
+        let channels = color_type.channels();
+        let total_pixels: usize = frames
+            .iter()
+            .map(|im| im.width as usize * im.height as usize)
+            .sum();
+        let stride = (total_pixels / PALETTE_SAMPLES).max(1);
+        let samples: Vec<_> = frames
+            .iter()
+            .flat_map(|im| {
+                im.data
+                    .to_eight_bit()
+                    .chunks_exact(channels)
+                    .filter(|px| alpha(color_type, px).is_none_or(|a| a >= 128))
+                    .map(|px| rgb(color_type, px))
+                    .collect::<Vec<_>>()
+            })
+            .step_by(stride)
+            .collect();
+
+        let colors = if color_type.has_alpha() { 255 } else { 256 };
+        Self::new(median_cut(samples, colors))
+    }
+
+    /// The colors of the palette
+    pub fn colors(&self) -> &[[u8; 3]] {
+        &self.colors
+    }
+
+    /// The colors as consecutive red, green and blue bytes
+    pub(crate) fn to_rgb_bytes(&self) -> Vec<u8> {
+        self.colors.iter().flatten().copied().collect()
+    }
+
+    /// Maps the pixels of the 8 bit `data`, made up of `color_type`'s channels, to the
+    /// index of the nearest color in the palette, pixels less than half opaque taking
+    /// the index `transparent` instead
+    pub(crate) fn indices(&self, data: &[u8], color_type: ColorType, transparent: u8) -> Vec<u8> {
+        // most frames only have a fraction as many colors as pixels
+        let mut nearest = HashMap::new();
+        data.chunks_exact(color_type.channels())
+            .map(|px| match alpha(color_type, px) {
+                Some(a) if a < 128 => transparent,
+                _ => *nearest
+                    .entry(rgb(color_type, px))
+                    .or_insert_with_key(|&color| self.nearest(color)),
+            })
+            .collect()
+    }
+
+    /// The index of the color closest to `color`
+    fn nearest(&self, color: [u8; 3]) -> u8 {
+        let distance = |other: &[u8; 3]| -> u32 {
+            color
+                .iter()
+                .zip(other)
+                .map(|(&a, &b)| (a as i32 - b as i32).pow(2) as u32)
+                .sum()
+        };
+        (0..self.colors.len())
+            .min_by_key(|&i| distance(&self.colors[i]))
+            .expect("a palette has at least one color") as u8
+    }
+}
+
+/// Splits `pixels` into at most `colors` boxes of similar colors, returning the mean color
+/// of each box. The box spanning the widest range of any one channel is split in two at its
+/// median along that channel, until there are enough boxes or every box is a single color.
+fn median_cut(pixels: Vec<[u8; 3]>, colors: usize) -> Vec<[u8; 3]> {
+    if pixels.is_empty() {
+        // there's nothing opaque to match, but a palette still needs a color
+        return vec![[0; 3]];
+    }
+
+    let mut boxes = vec![ColorBox::new(pixels)];
+    while boxes.len() < colors {
+        let widest = (0..boxes.len()).max_by_key(|&i| boxes[i].range).unwrap();
+        if boxes[widest].range == 0 {
+            break;
+        }
+        let ColorBox {
+            mut pixels,
+            channel,
+            ..
+        } = boxes.swap_remove(widest);
+        let median = pixels.len() / 2;
+        pixels.select_nth_unstable_by_key(median, |px| px[channel]);
+        let upper = pixels.split_off(median);
+        boxes.push(ColorBox::new(pixels));
+        boxes.push(ColorBox::new(upper));
+    }
+    boxes.iter().map(ColorBox::mean).collect()
+}
+
+/// Some of the pixels being cut into a palette, with the channel their values are
+/// spread the widest across and how widely
+struct ColorBox {
+    pixels: Vec<[u8; 3]>,
+    channel: usize,
+    range: u8,
+}
+
+impl ColorBox {
+    fn new(pixels: Vec<[u8; 3]>) -> Self {
+        let range = |c: usize| {
+            let values = pixels.iter().map(|px| px[c]);
+            values.clone().max().unwrap_or(0) - values.min().unwrap_or(0)
+        };
+        let channel = (0..3).max_by_key(|&c| range(c)).unwrap();
+        Self {
+            range: range(channel),
+            channel,
+            pixels,
+        }
+    }
+
+    fn mean(&self) -> [u8; 3] {
+        let mut sums = [0u64; 3];
+        for px in &self.pixels {
+            for (sum, &v) in sums.iter_mut().zip(px) {
+                *sum += v as u64;
+            }
+        }
+        let n = self.pixels.len() as u64;
+        sums.map(|sum| ((sum + n / 2) / n) as u8)
+    }
+}
+
+/// The alpha of an 8 bit pixel made up of `color_type`'s channels, if it has any
+fn alpha(color_type: ColorType, px: &[u8]) -> Option<u8> {
+    px.get(color_type.color_channels()).copied()
+}
+
+/// The color of an 8 bit pixel made up of `color_type`'s channels as RGB
+fn rgb(color_type: ColorType, px: &[u8]) -> [u8; 3] {
+    match color_type.color_channels() {
+        1 => [px[0]; 3],
+        _ => [px[0], px[1], px[2]],
+    }
+}
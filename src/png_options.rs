@@ -0,0 +1,118 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+
+/// How hard the PNG encoder works to shrink the files it writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Compression {
+    /// Quick compression which still shrinks most images well, the png crate's own default
+    Fast,
+    /// zlib's default level, smaller files than `Fast` but several times slower to write
+    Default,
+    /// zlib's highest level, the smallest files and the slowest to write
+    Best,
+}
+
+impl Compression {
+    pub const VARIANTS: [&'static str; 3] = ["fast", "default", "best"];
+}
+
+impl FromStr for Compression {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "fast" => Ok(Compression::Fast),
+            "default" => Ok(Compression::Default),
+            "best" => Ok(Compression::Best),
+            _ => Err(anyhow!("Unknown compression {:?}.", s)),
+        }
+    }
+}
+
+/// The filter applied to each row of a PNG before it is compressed,
+/// predicting each byte from its neighbours so only the difference is stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PngFilter {
+    /// Store the bytes as they are, the quickest to write
+    None,
+    /// Predict each byte from the pixel to its left
+    Sub,
+    /// Predict each byte from the pixel above it
+    Up,
+    /// Predict each byte from the average of the pixels to its left and above it
+    Average,
+    /// Predict each byte from whichever of the pixels to its left, above it and above left
+    /// is closest to their gradient
+    Paeth,
+    /// Try every filter on each row and keep whichever looks likely to compress best,
+    /// which gives the smallest files but is the slowest
+    Adaptive,
+}
+
+impl PngFilter {
+    pub const VARIANTS: [&'static str; 6] = ["none", "sub", "up", "average", "paeth", "adaptive"];
+}
+
+impl FromStr for PngFilter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "none" => Ok(PngFilter::None),
+            "sub" => Ok(PngFilter::Sub),
+            "up" => Ok(PngFilter::Up),
+            "average" => Ok(PngFilter::Average),
+            "paeth" => Ok(PngFilter::Paeth),
+            "adaptive" => Ok(PngFilter::Adaptive),
+            _ => Err(anyhow!("Unknown PNG filter {:?}.", s)),
+        }
+    }
+}
+
+/// The settings PNGs are encoded with, which trade the size of the files for the time
+/// taken to write them without changing any of their pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PngOptions {
+    pub compression: Compression,
+    pub filter: PngFilter,
+    /// The most bytes of compressed image data put in each IDAT chunk, when the image data
+    /// is split into chunks of a limited size rather than written as a single chunk
+    pub chunk_size: Option<usize>,
+}
+
+impl Default for PngOptions {
+    /// The png crate's own defaults
+    fn default() -> Self {
+        Self {
+            compression: Compression::Fast,
+            filter: PngFilter::Sub,
+            chunk_size: None,
+        }
+    }
+}
+
+impl PngOptions {
+    /// Sets up `encoder` to write with these options
+    pub(crate) fn apply_to<W: std::io::Write>(&self, encoder: &mut png::Encoder<'_, W>) {
+        encoder.set_compression(match self.compression {
+            Compression::Fast => png::Compression::Fast,
+            Compression::Default => png::Compression::Default,
+            Compression::Best => png::Compression::Best,
+        });
+        let (filter, adaptive) = match self.filter {
+            PngFilter::None => (png::FilterType::NoFilter, false),
+            PngFilter::Sub => (png::FilterType::Sub, false),
+            PngFilter::Up => (png::FilterType::Up, false),
+            PngFilter::Average => (png::FilterType::Avg, false),
+            PngFilter::Paeth => (png::FilterType::Paeth, false),
+            PngFilter::Adaptive => (png::FilterType::Sub, true),
+        };
+        encoder.set_filter(filter);
+        encoder.set_adaptive_filter(if adaptive {
+            png::AdaptiveFilterType::Adaptive
+        } else {
+            png::AdaptiveFilterType::NonAdaptive
+        });
+    }
+}
@@ -0,0 +1,125 @@
+use std::{borrow::Cow, fmt::Debug, path::Path, sync::Arc};
+
+use anyhow::{anyhow, Result};
+
+use crate::{animation, bmp, exr, image::open_png, jpeg, tiff};
+
+/// The chunks of a PNG describing how its color values should be displayed,
+/// which are carried from the keyframes through to the saved frames so that
+/// viewers color-manage them the same way as the sources
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ColorProfile {
+    /// The gamma from a `gAMA` chunk, multiplied by 100000
+    pub gamma: Option<u32>,
+    /// The white point then the red, green and blue primaries from a `cHRM` chunk,
+    /// each as an x and y pair multiplied by 100000
+    pub chromaticities: Option<[u32; 8]>,
+    /// The rendering intent from an `sRGB` chunk, marking the image as sRGB
+    pub srgb: Option<u8>,
+    /// The ICC profile embedded in an `iCCP` chunk
+    pub icc_profile: Option<Arc<[u8]>>,
+}
+
+impl ColorProfile {
+    /// Reads the color chunks of the image at `p` without decoding any of its pixels,
+    /// JPEGs, GIFs, BMPs, TIFFs and EXRs are always treated as untagged
+    pub fn from_path<P>(p: P) -> Result<Self>
+    where
+        P: AsRef<Path> + Debug,
+    {
+        let path = p.as_ref();
+        if jpeg::is_jpeg(path)?
+            || animation::is_gif(path)?
+            || bmp::is_bmp(path)?
+            || tiff::is_tiff(path)?
+            || exr::is_exr(path)?
+        {
+            return Ok(Self::default());
+        }
+
+        let (reader, _) = open_png(path)?;
+        Ok(Self::from_png_info(reader.info()))
+    }
+
+    /// Whether the image carries no information about its color space,
+    /// in which case viewers generally assume it is sRGB
+    pub fn is_untagged(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub(crate) fn from_png_info(info: &png::Info) -> Self {
+        let chromaticities = info.source_chromaticities.map(|c| {
+            let scaled =
+                |(x, y): (png::ScaledFloat, png::ScaledFloat)| [x.into_scaled(), y.into_scaled()];
+            let (white, red, green, blue) = (
+                scaled(c.white),
+                scaled(c.red),
+                scaled(c.green),
+                scaled(c.blue),
+            );
+            [
+                white[0], white[1], red[0], red[1], green[0], green[1], blue[0], blue[1],
+            ]
+        });
+
+        Self {
+            gamma: info.source_gamma.map(png::ScaledFloat::into_scaled),
+            chromaticities,
+            srgb: info.srgb.map(|intent| intent as u8),
+            icc_profile: info.icc_profile.as_deref().map(Arc::from),
+        }
+    }
+
+    /// Adds the chunks of this profile to the header of a PNG being encoded
+    pub(crate) fn apply_to(&self, info: &mut png::Info) {
+        let scaled = png::ScaledFloat::from_scaled;
+        info.source_gamma = self.gamma.map(scaled);
+        info.source_chromaticities = self.chromaticities.map(|c| png::SourceChromaticities {
+            white: (scaled(c[0]), scaled(c[1])),
+            red: (scaled(c[2]), scaled(c[3])),
+            green: (scaled(c[4]), scaled(c[5])),
+            blue: (scaled(c[6]), scaled(c[7])),
+        });
+        info.srgb = self.srgb.and_then(|intent| match intent {
+            0 => Some(png::SrgbRenderingIntent::Perceptual),
+            1 => Some(png::SrgbRenderingIntent::RelativeColorimetric),
+            2 => Some(png::SrgbRenderingIntent::Saturation),
+            3 => Some(png::SrgbRenderingIntent::AbsoluteColorimetric),
+            _ => None,
+        });
+        info.icc_profile = self
+            .icc_profile
+            .as_ref()
+            .map(|icc| Cow::Owned(icc.to_vec()));
+    }
+}
+
+/// Finds the color profile shared by a sequence of keyframes, each named by its label in errors.
+///
+/// Untagged keyframes are assumed to match the others, but keyframes tagged with different
+/// profiles can't be blended without one of them being displayed wrongly.
+pub(crate) fn shared_profile(
+    profiles: impl IntoIterator<Item = (String, ColorProfile)>,
+) -> Result<ColorProfile> {
+    let mut shared: Option<(String, ColorProfile)> = None;
+    for (label, profile) in profiles {
+        if profile.is_untagged() {
+            continue;
+        }
+
+        match &shared {
+            Some((first, expected)) if *expected != profile => {
+                return Err(anyhow!(
+                    "{} and {} have different color profiles (gAMA, cHRM, sRGB or iCCP chunks), \
+                     so they can't be blended without one of them changing color.",
+                    first,
+                    label
+                ))
+            }
+            Some(_) => {}
+            None => shared = Some((label, profile)),
+        }
+    }
+
+    Ok(shared.map(|(_, profile)| profile).unwrap_or_default())
+}
@@ -0,0 +1,88 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+
+use crate::{ColorType, Image, Sample, Samples};
+
+/// The color types images can be promoted to so that a mix of them can be blended together
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Promote {
+    /// Grayscale images become RGB with the gray value in every channel, and
+    /// grayscale with alpha becomes RGBA keeping its alpha. RGB and RGBA are unchanged.
+    Rgb,
+    /// Every image becomes RGBA, grayscale is spread over the color channels as for
+    /// `Rgb`, and images without alpha are made fully opaque
+    Rgba,
+}
+
+impl Promote {
+    pub const VARIANTS: [&'static str; 2] = ["rgb", "rgba"];
+
+    /// The color type images of `color_type` have once they are promoted.
+    ///
+    /// Promotion only ever adds channels, so an image with alpha keeps it even when
+    /// promoting to `Rgb`, and the bit depth never changes.
+    pub fn color_type(self, color_type: ColorType) -> ColorType {
+        match (self, color_type) {
+            (Promote::Rgb, ColorType::Gray) => ColorType::Rgb,
+            (Promote::Rgb, ColorType::GrayAlpha) => ColorType::Rgba,
+            (Promote::Rgb, other) => other,
+            (Promote::Rgba, _) => ColorType::Rgba,
+        }
+    }
+}
+
+impl FromStr for Promote {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "rgb" => Ok(Promote::Rgb),
+            "rgba" => Ok(Promote::Rgba),
+            _ => Err(anyhow!("Unknown promotion {:?}.", s)),
+        }
+    }
+}
+
+impl Image {
+    /// Converts the image to the color type given by `promote`, copying the gray value
+    /// of each pixel into the red, green and blue channels and adding an alpha channel
+    /// at full opacity where one is needed. The image is unchanged if it is already of
+    /// that color type.
+    pub fn promote(self, promote: Promote) -> Image {
+        let color_type = promote.color_type(self.color_type);
+        if color_type == self.color_type {
+            return self;
+        }
+
+        let (from, to) = (self.color_type, color_type);
+        let data = match &self.data {
+            Samples::Eight(data) => Samples::Eight(convert(data, from, to)),
+            Samples::Sixteen(data) => Samples::Sixteen(convert(data, from, to)),
+            Samples::Float(data) => Samples::Float(convert(data, from, to)),
+        };
+        Image {
+            data,
+            color_type,
+            ..self
+        }
+    }
+}
+
+/// Rebuilds pixels of `from`'s channels with `to`'s, where `to` has as many color
+/// channels as `from` or more and has alpha if `from` does
+fn convert<S: Sample>(data: &[S], from: ColorType, to: ColorType) -> Vec<S> {
+    let opaque = S::from_f64(S::MAX);
+    let mut out = Vec::with_capacity(data.len() / from.channels() * to.channels());
+    for px in data.chunks_exact(from.channels()) {
+        let (color, alpha) = px.split_at(from.color_channels());
+        match color {
+            [luma] => out.extend(std::iter::repeat_n(*luma, to.color_channels())),
+            _ => out.extend_from_slice(color),
+        }
+        if to.has_alpha() {
+            out.push(alpha.first().copied().unwrap_or(opaque));
+        }
+    }
+    out
+}
@@ -0,0 +1,136 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, ensure, Context, Result};
+
+use crate::{Image, ImageInfo, Samples};
+
+/// A rectangle of pixels within an image
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Region {
+    /// The column of the region's left edge
+    pub x: u32,
+    /// The row of the region's top edge
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Region {
+    /// Checks that the region lies wholly within images like `info`
+    pub fn check_fits(&self, info: ImageInfo) -> Result<()> {
+        ensure!(
+            self.x as u64 + self.width as u64 <= info.width as u64
+                && self.y as u64 + self.height as u64 <= info.height as u64,
+            "The {}x{} region at {},{} doesn't fit in the {}x{} images.",
+            self.width,
+            self.height,
+            self.x,
+            self.y,
+            info.width,
+            info.height
+        );
+        Ok(())
+    }
+}
+
+impl FromStr for Region {
+    type Err = anyhow::Error;
+
+    /// Parses a region written as `X,Y,WIDTH,HEIGHT`
+    fn from_str(s: &str) -> Result<Self> {
+        let parts: Vec<_> = s.split(',').collect();
+        let [x, y, width, height] = parts[..] else {
+            return Err(anyhow!("Region {:?} must be X,Y,WIDTH,HEIGHT.", s));
+        };
+        let parse = |part: &str, name| -> Result<u32> {
+            part.trim()
+                .parse()
+                .with_context(|| format!("Invalid {} in region {:?}.", name, s))
+        };
+        let region = Region {
+            x: parse(x, "x")?,
+            y: parse(y, "y")?,
+            width: parse(width, "width")?,
+            height: parse(height, "height")?,
+        };
+        ensure!(
+            region.width > 0 && region.height > 0,
+            "Region {:?} must have a non-zero width and height.",
+            s
+        );
+        Ok(region)
+    }
+}
+
+impl Image {
+    /// Creates a copy of the pixels of this image within `region`,
+    /// which must lie within the image
+    pub(crate) fn crop(&self, region: Region) -> Image {
+        fn crop<S: Copy>(data: &[S], image_width: u32, channels: usize, region: Region) -> Vec<S> {
+            let row_len = image_width as usize * channels;
+            let (start, len) = (
+                region.x as usize * channels,
+                region.width as usize * channels,
+            );
+            data.chunks_exact(row_len)
+                .skip(region.y as usize)
+                .take(region.height as usize)
+                .flat_map(|row| &row[start..start + len])
+                .copied()
+                .collect()
+        }
+
+        let channels = self.color_type.channels();
+        let data = match &self.data {
+            Samples::Eight(data) => Samples::Eight(crop(data, self.width, channels, region)),
+            Samples::Sixteen(data) => Samples::Sixteen(crop(data, self.width, channels, region)),
+            Samples::Float(data) => Samples::Float(crop(data, self.width, channels, region)),
+        };
+        Image {
+            data,
+            color_type: self.color_type,
+            width: region.width,
+            height: region.height,
+            profile: self.profile.clone(),
+        }
+    }
+
+    /// Copies `image` over the pixels of this image with its top left corner at `x`, `y`,
+    /// `image` having the same color type and bit depth and fitting within this image
+    pub(crate) fn paste(&mut self, image: &Image, x: u32, y: u32) {
+        fn paste<S: Copy>(
+            dst: &mut [S],
+            dst_width: u32,
+            src: &[S],
+            src_width: u32,
+            x: u32,
+            y: u32,
+            channels: usize,
+        ) {
+            let (dst_row_len, src_row_len) =
+                (dst_width as usize * channels, src_width as usize * channels);
+            for (row, src) in dst
+                .chunks_exact_mut(dst_row_len)
+                .skip(y as usize)
+                .zip(src.chunks_exact(src_row_len))
+            {
+                let start = x as usize * channels;
+                row[start..start + src_row_len].copy_from_slice(src);
+            }
+        }
+
+        let channels = self.color_type.channels();
+        match (&mut self.data, &image.data) {
+            (Samples::Eight(dst), Samples::Eight(src)) => {
+                paste(dst, self.width, src, image.width, x, y, channels)
+            }
+            (Samples::Sixteen(dst), Samples::Sixteen(src)) => {
+                paste(dst, self.width, src, image.width, x, y, channels)
+            }
+            (Samples::Float(dst), Samples::Float(src)) => {
+                paste(dst, self.width, src, image.width, x, y, channels)
+            }
+            _ => unreachable!("only images of the same bit depth are pasted together"),
+        }
+    }
+}
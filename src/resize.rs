@@ -0,0 +1,446 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, ensure, Context, Result};
+
+use crate::{Image, ImageInfo, Sample, Samples, SolidColor};
+
+/// The ways pixels can be sampled when resizing an image
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Filter {
+    /// Use the value of the closest source pixel
+    Nearest,
+    /// Blend the four closest source pixels weighted by their distance
+    Bilinear,
+    /// Average every source pixel each destination pixel covers, by how much of it is
+    /// covered, which keeps the edges of flat artwork crisp when shrinking
+    Box,
+    /// Weight the source pixels around each destination pixel by a three lobed Lanczos
+    /// kernel, the sharpest of the filters for photographs
+    Lanczos,
+}
+
+impl Filter {
+    pub const VARIANTS: [&'static str; 4] = ["nearest", "bilinear", "box", "lanczos"];
+}
+
+impl FromStr for Filter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "nearest" => Ok(Filter::Nearest),
+            "bilinear" => Ok(Filter::Bilinear),
+            "box" => Ok(Filter::Box),
+            "lanczos" => Ok(Filter::Lanczos),
+            _ => Err(anyhow!("Unknown resize filter {:?}.", s)),
+        }
+    }
+}
+
+/// How an image is fitted to a size with a different aspect ratio
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Fit {
+    /// Scale the whole image to fit inside the size, filling the bars either side of it
+    /// with a background color
+    Contain,
+    /// Scale the image to fill the size, cropping off whatever sticks out
+    Cover,
+    /// Scale the width and height separately to the size, distorting the image
+    Stretch,
+}
+
+impl Fit {
+    pub const VARIANTS: [&'static str; 3] = ["contain", "cover", "stretch"];
+}
+
+impl FromStr for Fit {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "contain" => Ok(Fit::Contain),
+            "cover" => Ok(Fit::Cover),
+            "stretch" => Ok(Fit::Stretch),
+            _ => Err(anyhow!("Unknown fit {:?}.", s)),
+        }
+    }
+}
+
+/// The part of an image kept in place when it is fitted to a size with a different aspect
+/// ratio, what is cropped off by [`Fit::Cover`] or where the image sits between the bars
+/// of [`Fit::Contain`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Gravity {
+    Center,
+    Top,
+    Bottom,
+    Left,
+    Right,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Gravity {
+    pub const VARIANTS: [&'static str; 9] = [
+        "center",
+        "top",
+        "bottom",
+        "left",
+        "right",
+        "top-left",
+        "top-right",
+        "bottom-left",
+        "bottom-right",
+    ];
+
+    /// How far across and down the spare space the image is placed, from `0.0` for the
+    /// left or top edge to `1.0` for the right or bottom
+    fn alignment(self) -> (f64, f64) {
+        match self {
+            Gravity::Center => (0.5, 0.5),
+            Gravity::Top => (0.5, 0.0),
+            Gravity::Bottom => (0.5, 1.0),
+            Gravity::Left => (0.0, 0.5),
+            Gravity::Right => (1.0, 0.5),
+            Gravity::TopLeft => (0.0, 0.0),
+            Gravity::TopRight => (1.0, 0.0),
+            Gravity::BottomLeft => (0.0, 1.0),
+            Gravity::BottomRight => (1.0, 1.0),
+        }
+    }
+}
+
+impl FromStr for Gravity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "center" => Ok(Gravity::Center),
+            "top" => Ok(Gravity::Top),
+            "bottom" => Ok(Gravity::Bottom),
+            "left" => Ok(Gravity::Left),
+            "right" => Ok(Gravity::Right),
+            "top-left" => Ok(Gravity::TopLeft),
+            "top-right" => Ok(Gravity::TopRight),
+            "bottom-left" => Ok(Gravity::BottomLeft),
+            "bottom-right" => Ok(Gravity::BottomRight),
+            _ => Err(anyhow!("Unknown gravity {:?}.", s)),
+        }
+    }
+}
+
+/// The size every image is resized to before interpolating
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResizeTarget {
+    /// The size of the first image
+    First,
+    /// The size of the image with the largest area
+    Largest,
+    /// An explicit width and height
+    Exact(u32, u32),
+}
+
+impl ResizeTarget {
+    /// Works out the width and height to resize to given the images being resized
+    pub fn resolve(self, infos: &[ImageInfo]) -> Result<(u32, u32)> {
+        match self {
+            ResizeTarget::First => infos
+                .first()
+                .map(|info| (info.width, info.height))
+                .ok_or_else(|| anyhow!("There are no images to take the size of.")),
+            ResizeTarget::Largest => infos
+                .iter()
+                .max_by_key(|info| info.width as u64 * info.height as u64)
+                .map(|info| (info.width, info.height))
+                .ok_or_else(|| anyhow!("There are no images to take the size of.")),
+            ResizeTarget::Exact(width, height) => Ok((width, height)),
+        }
+    }
+}
+
+impl FromStr for ResizeTarget {
+    type Err = anyhow::Error;
+
+    /// Parses either `first`, `largest` or a size written as `WIDTHxHEIGHT`
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "first" => Ok(ResizeTarget::First),
+            "largest" => Ok(ResizeTarget::Largest),
+            _ => {
+                let (width, height) = s.split_once('x').ok_or_else(|| {
+                    anyhow!(
+                        "Resize target {:?} must be first, largest or WIDTHxHEIGHT.",
+                        s
+                    )
+                })?;
+                let width = width
+                    .parse()
+                    .with_context(|| format!("Invalid width in resize target {:?}.", s))?;
+                let height = height
+                    .parse()
+                    .with_context(|| format!("Invalid height in resize target {:?}.", s))?;
+                ensure!(
+                    width > 0 && height > 0,
+                    "Resize target {:?} must have a non-zero width and height.",
+                    s
+                );
+                Ok(ResizeTarget::Exact(width, height))
+            }
+        }
+    }
+}
+
+impl Image {
+    /// Creates a copy of this image scaled to `width` by `height` pixels
+    pub fn resize(&self, width: u32, height: u32, filter: Filter) -> Image {
+        if (width, height) == (self.width, self.height) {
+            return self.clone();
+        }
+        let rect = [0.0, 0.0, self.width as f64, self.height as f64];
+        self.sample_rect(rect, width, height, filter)
+    }
+
+    /// Creates a copy of this image scaled to `width` pixels wide, keeping its aspect ratio
+    /// by rounding the height to the nearest pixel
+    pub fn resize_to_width(&self, width: u32, filter: Filter) -> Image {
+        let height = self.height as f64 * width as f64 / self.width as f64;
+        self.resize(width, height.round().max(1.0) as u32, filter)
+    }
+
+    /// Creates a copy of this image scaled to `width` by `height` pixels, keeping its aspect
+    /// ratio unless `fit` is [`Fit::Stretch`]. `gravity` picks the part of the image which
+    /// is kept when cropping and where it sits when letterboxing, the bars being filled
+    /// with `background`.
+    pub fn resize_fit(
+        &self,
+        width: u32,
+        height: u32,
+        filter: Filter,
+        fit: Fit,
+        gravity: Gravity,
+        background: [u8; 3],
+    ) -> Image {
+        let (x_scale, y_scale) = (
+            width as f64 / self.width as f64,
+            height as f64 / self.height as f64,
+        );
+        let (x_align, y_align) = gravity.alignment();
+        match fit {
+            Fit::Stretch => self.resize(width, height, filter),
+            Fit::Cover => {
+                let scale = x_scale.max(y_scale);
+                let (rect_width, rect_height) = (width as f64 / scale, height as f64 / scale);
+                let rect = [
+                    (self.width as f64 - rect_width) * x_align,
+                    (self.height as f64 - rect_height) * y_align,
+                    rect_width,
+                    rect_height,
+                ];
+                self.sample_rect(rect, width, height, filter)
+            }
+            Fit::Contain => {
+                let scale = x_scale.min(y_scale);
+                let scaled =
+                    |len: u32, max: u32| ((len as f64 * scale).round() as u32).clamp(1, max);
+                let (inner_width, inner_height) =
+                    (scaled(self.width, width), scaled(self.height, height));
+                let inner = self.resize(inner_width, inner_height, filter);
+                let info = ImageInfo {
+                    width,
+                    height,
+                    ..self.info()
+                };
+                let solid = SolidColor {
+                    rgb: background,
+                    size: None,
+                };
+                let mut image = solid.image(info).with_color_profile(self.profile.clone());
+                image.paste(
+                    &inner,
+                    ((width - inner_width) as f64 * x_align).round() as u32,
+                    ((height - inner_height) as f64 * y_align).round() as u32,
+                );
+                image
+            }
+        }
+    }
+
+    /// Samples the part of this image within `rect`, its left and top edges then its width
+    /// and height in pixels, none of which need to be whole, scaled to `width` by `height`
+    /// pixels. Anywhere the rectangle goes outside the image takes the nearest edge pixel.
+    pub(crate) fn sample_rect(
+        &self,
+        rect: [f64; 4],
+        width: u32,
+        height: u32,
+        filter: Filter,
+    ) -> Image {
+        let data = match &self.data {
+            Samples::Eight(data) => {
+                Samples::Eight(self.resample(data, rect, width, height, filter))
+            }
+            Samples::Sixteen(data) => {
+                Samples::Sixteen(self.resample(data, rect, width, height, filter))
+            }
+            Samples::Float(data) => {
+                Samples::Float(self.resample(data, rect, width, height, filter))
+            }
+        };
+
+        Image {
+            data,
+            color_type: self.color_type,
+            width,
+            height,
+            profile: self.profile.clone(),
+        }
+    }
+
+    fn resample<S: Sample>(
+        &self,
+        data: &[S],
+        rect: [f64; 4],
+        width: u32,
+        height: u32,
+        filter: Filter,
+    ) -> Vec<S> {
+        let channels = self.color_type.channels();
+        let (src_width, src_height) = (self.width as usize, self.height as usize);
+        if let Filter::Box | Filter::Lanczos = filter {
+            let columns = filter_weights(filter, rect[0], rect[2], width as usize, src_width);
+            let rows = filter_weights(filter, rect[1], rect[3], height as usize, src_height);
+            return convolve(data, src_width, channels, &columns, &rows);
+        }
+
+        let [left, top, rect_width, rect_height] = rect;
+        let x_scale = rect_width / width as f64;
+        let y_scale = rect_height / height as f64;
+        let pixel = |x: usize, y: usize| &data[(y * src_width + x) * channels..][..channels];
+
+        let mut out = Vec::with_capacity(width as usize * height as usize * channels);
+        for y in 0..height as usize {
+            // sample from the centre of each destination pixel
+            let src_y =
+                (top + (y as f64 + 0.5) * y_scale - 0.5).clamp(0.0, (src_height - 1) as f64);
+            for x in 0..width as usize {
+                let src_x =
+                    (left + (x as f64 + 0.5) * x_scale - 0.5).clamp(0.0, (src_width - 1) as f64);
+                match filter {
+                    Filter::Nearest => {
+                        out.extend_from_slice(pixel(src_x.round() as usize, src_y.round() as usize))
+                    }
+                    Filter::Bilinear => {
+                        let (x0, y0) = (src_x.floor() as usize, src_y.floor() as usize);
+                        let (x1, y1) = ((x0 + 1).min(src_width - 1), (y0 + 1).min(src_height - 1));
+                        let (tx, ty) = (src_x - x0 as f64, src_y - y0 as f64);
+
+                        let (p00, p10) = (pixel(x0, y0), pixel(x1, y0));
+                        let (p01, p11) = (pixel(x0, y1), pixel(x1, y1));
+                        out.extend((0..channels).map(|c| {
+                            let top = p00[c].to_f64() * (1.0 - tx) + p10[c].to_f64() * tx;
+                            let bottom = p01[c].to_f64() * (1.0 - tx) + p11[c].to_f64() * tx;
+                            S::from_f64(top * (1.0 - ty) + bottom * ty)
+                        }));
+                    }
+                    Filter::Box | Filter::Lanczos => unreachable!("these filters are convolved"),
+                }
+            }
+        }
+        out
+    }
+}
+
+/// The number of lobes either side of the centre of the Lanczos kernel
+const LANCZOS_LOBES: f64 = 3.0;
+
+/// The source pixels making up each of `len` destination pixels along one axis, with how
+/// much each of them counts for, the destination pixels spanning `size` source pixels from
+/// `start`. Source pixels past either end of the `src_len` there are take the nearest one.
+fn filter_weights(
+    filter: Filter,
+    start: f64,
+    size: f64,
+    len: usize,
+    src_len: usize,
+) -> Vec<Vec<(usize, f64)>> {
+    let scale = size / len as f64;
+    (0..len)
+        .map(|i| {
+            let weights: Vec<(i64, f64)> = match filter {
+                Filter::Box => {
+                    let (from, to) = (start + i as f64 * scale, start + (i + 1) as f64 * scale);
+                    (from.floor() as i64..to.ceil() as i64)
+                        .map(|j| (j, to.min(j as f64 + 1.0) - from.max(j as f64)))
+                        .collect()
+                }
+                Filter::Lanczos => {
+                    // the kernel is stretched over more source pixels when shrinking so
+                    // every one of them is taken into account
+                    let stretch = scale.max(1.0);
+                    let centre = start + (i as f64 + 0.5) * scale - 0.5;
+                    let radius = LANCZOS_LOBES * stretch;
+                    ((centre - radius).ceil() as i64..=(centre + radius).floor() as i64)
+                        .map(|j| (j, lanczos((j as f64 - centre) / stretch)))
+                        .collect()
+                }
+                Filter::Nearest | Filter::Bilinear => unreachable!("these filters are sampled"),
+            };
+            let total: f64 = weights.iter().map(|(_, w)| w).sum();
+            weights
+                .into_iter()
+                .map(|(j, w)| (j.clamp(0, src_len as i64 - 1) as usize, w / total))
+                .collect()
+        })
+        .collect()
+}
+
+/// The three lobed Lanczos kernel
+fn lanczos(x: f64) -> f64 {
+    if x == 0.0 {
+        1.0
+    } else if x.abs() >= LANCZOS_LOBES {
+        0.0
+    } else {
+        let pi_x = std::f64::consts::PI * x;
+        LANCZOS_LOBES * pi_x.sin() * (pi_x / LANCZOS_LOBES).sin() / (pi_x * pi_x)
+    }
+}
+
+/// Filters the rows of `data`, `src_width` pixels wide, by the weights of each of `columns`
+/// and then the result down each column by the weights of each of `rows`
+fn convolve<S: Sample>(
+    data: &[S],
+    src_width: usize,
+    channels: usize,
+    columns: &[Vec<(usize, f64)>],
+    rows: &[Vec<(usize, f64)>],
+) -> Vec<S> {
+    let width = columns.len();
+    let across: Vec<f64> = data
+        .chunks_exact(src_width * channels)
+        .flat_map(|row| {
+            columns.iter().flat_map(move |weights| {
+                (0..channels).map(move |c| {
+                    weights
+                        .iter()
+                        .map(|&(j, w)| row[j * channels + c].to_f64() * w)
+                        .sum::<f64>()
+                })
+            })
+        })
+        .collect();
+
+    let mut out = Vec::with_capacity(width * rows.len() * channels);
+    for weights in rows {
+        for i in 0..width * channels {
+            let value: f64 = weights
+                .iter()
+                .map(|&(j, w)| across[j * width * channels + i] * w)
+                .sum();
+            out.push(S::from_f64(value));
+        }
+    }
+    out
+}
@@ -0,0 +1,294 @@
+use std::{
+    borrow::Cow,
+    hash::{Hash, Hasher},
+    mem,
+    ops::{Add, Mul, Sub},
+    str::FromStr,
+};
+
+use anyhow::{anyhow, Result};
+
+/// The number of bits used to store each channel of a pixel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BitDepth {
+    Eight,
+    Sixteen,
+    /// 32 bit floating point, as high dynamic range images are stored
+    Float,
+}
+
+impl BitDepth {
+    pub const VARIANTS: [&'static str; 3] = ["8", "16", "32"];
+
+    pub fn bits(self) -> u32 {
+        match self {
+            BitDepth::Eight => 8,
+            BitDepth::Sixteen => 16,
+            BitDepth::Float => 32,
+        }
+    }
+}
+
+impl FromStr for BitDepth {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "8" => Ok(BitDepth::Eight),
+            "16" => Ok(BitDepth::Sixteen),
+            "32" => Ok(BitDepth::Float),
+            _ => Err(anyhow!(
+                "Unknown bit depth {:?}, it must be 8, 16 or 32.",
+                s
+            )),
+        }
+    }
+}
+
+/// A floating point type channel values are blended in.
+///
+/// `f32` holds every 8 bit value and weight exactly enough to round to the same result
+/// as `f64` while doing half the work, 16 bit values need the precision of `f64`. The one
+/// exception is a blend landing exactly half way between two values: neither type holds
+/// weights like 1/50 exactly, so either can round such a tie up or down, and they don't
+/// always agree.
+pub trait Accumulator:
+    Copy + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Send + Sync + 'static
+{
+    const ONE: Self;
+
+    fn from_f64(value: f64) -> Self;
+
+    fn to_f64(self) -> f64;
+}
+
+impl Accumulator for f32 {
+    const ONE: Self = 1.0;
+
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl Accumulator for f64 {
+    const ONE: Self = 1.0;
+
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
+
+/// A type which can hold the value of a single channel of a pixel
+pub trait Sample: Copy + Send + Sync + 'static {
+    /// The value representing a channel at full intensity
+    const MAX: f64;
+
+    /// Whether channel values are kept within [0.0..MAX], as they are for every type but
+    /// floats, whose high dynamic range values can go past 1.0
+    const BOUNDED: bool = true;
+
+    /// The type `smooth` blends these values in
+    type Accumulator: Accumulator;
+
+    fn to_f64(self) -> f64;
+
+    /// Converts a value in [0.0..MAX] back into a channel value,
+    /// rounding to the nearest value and clamping anything out of range
+    fn from_f64(value: f64) -> Self;
+
+    /// The channel value scaled into [0.0..1.0]
+    fn to_normalized(self) -> f64 {
+        self.to_f64() / Self::MAX
+    }
+
+    /// Converts a value in [0.0..1.0] back into a channel value, see `from_f64`
+    fn from_normalized(value: f64) -> Self {
+        Self::from_f64(value * Self::MAX)
+    }
+}
+
+impl Sample for u8 {
+    const MAX: f64 = u8::MAX as f64;
+
+    type Accumulator = f32;
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    fn from_f64(value: f64) -> Self {
+        value.round().clamp(0.0, <Self as Sample>::MAX) as u8
+    }
+}
+
+impl Sample for u16 {
+    const MAX: f64 = u16::MAX as f64;
+
+    type Accumulator = f64;
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    fn from_f64(value: f64) -> Self {
+        value.round().clamp(0.0, <Self as Sample>::MAX) as u16
+    }
+}
+
+impl Sample for f32 {
+    /// Float values are 1.0 at the brightness the integer types top out at, but can go
+    /// past it in high dynamic range images
+    const MAX: f64 = 1.0;
+
+    const BOUNDED: bool = false;
+
+    type Accumulator = f32;
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    /// Converts a value back into a channel value without rounding or clamping it, so the
+    /// values brighter than 1.0 in high dynamic range images are kept
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+}
+
+/// The channel values making up an image, stored at their native bit depth
+#[derive(Debug, Clone)]
+pub enum Samples {
+    Eight(Vec<u8>),
+    Sixteen(Vec<u16>),
+    Float(Vec<f32>),
+}
+
+// float values are compared by their bits so that images with NaNs still equal themselves,
+// keeping equality consistent with hashing
+impl PartialEq for Samples {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Samples::Eight(a), Samples::Eight(b)) => a == b,
+            (Samples::Sixteen(a), Samples::Sixteen(b)) => a == b,
+            (Samples::Float(a), Samples::Float(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.to_bits() == b.to_bits())
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Samples {}
+
+impl Hash for Samples {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match self {
+            Samples::Eight(data) => data.hash(state),
+            Samples::Sixteen(data) => data.hash(state),
+            Samples::Float(data) => {
+                data.len().hash(state);
+                data.iter().for_each(|v| v.to_bits().hash(state));
+            }
+        }
+    }
+}
+
+impl Samples {
+    pub fn bit_depth(&self) -> BitDepth {
+        match self {
+            Samples::Eight(_) => BitDepth::Eight,
+            Samples::Sixteen(_) => BitDepth::Sixteen,
+            Samples::Float(_) => BitDepth::Float,
+        }
+    }
+
+    /// The number of channel values, which is independent of the bit depth
+    pub fn len(&self) -> usize {
+        match self {
+            Samples::Eight(data) => data.len(),
+            Samples::Sixteen(data) => data.len(),
+            Samples::Float(data) => data.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Decodes the big-endian byte layout used by PNG, or the same layout of floats
+    pub(crate) fn from_be_bytes(bytes: Vec<u8>, bit_depth: BitDepth) -> Self {
+        match bit_depth {
+            BitDepth::Eight => Samples::Eight(bytes),
+            BitDepth::Sixteen => Samples::Sixteen(
+                bytes
+                    .chunks_exact(2)
+                    .map(|b| u16::from_be_bytes([b[0], b[1]]))
+                    .collect(),
+            ),
+            BitDepth::Float => Samples::Float(
+                bytes
+                    .chunks_exact(4)
+                    .map(|b| f32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Encodes the samples in the big-endian byte layout used by PNG, with floats in the
+    /// same order, this only has to copy the data for 16 bit and float samples
+    pub(crate) fn to_be_bytes(&self) -> Cow<'_, [u8]> {
+        match self {
+            Samples::Eight(data) => Cow::Borrowed(data),
+            Samples::Sixteen(data) => {
+                Cow::Owned(data.iter().flat_map(|c| c.to_be_bytes()).collect())
+            }
+            Samples::Float(data) => Cow::Owned(data.iter().flat_map(|c| c.to_be_bytes()).collect()),
+        }
+    }
+
+    /// The samples reduced to 8 bits per channel, rounding to the nearest value,
+    /// for outputs which can't store anything more precise
+    pub(crate) fn to_eight_bit(&self) -> Cow<'_, [u8]> {
+        match self {
+            Samples::Eight(data) => Cow::Borrowed(data),
+            Samples::Sixteen(data) => Cow::Owned(
+                data.iter()
+                    .map(|&c| ((c as u32 + 128) / 257) as u8)
+                    .collect(),
+            ),
+            // anything brighter than 8 bits can show is clipped to white
+            Samples::Float(data) => Cow::Owned(
+                data.iter()
+                    .map(|&c| u8::from_normalized(c as f64))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl From<Vec<u8>> for Samples {
+    fn from(data: Vec<u8>) -> Self {
+        Samples::Eight(data)
+    }
+}
+
+impl From<Vec<u16>> for Samples {
+    fn from(data: Vec<u16>) -> Self {
+        Samples::Sixteen(data)
+    }
+}
+
+impl From<Vec<f32>> for Samples {
+    fn from(data: Vec<f32>) -> Self {
+        Samples::Float(data)
+    }
+}
@@ -0,0 +1,136 @@
+use std::{path::Path, str::FromStr};
+
+use anyhow::{anyhow, ensure, Context, Result};
+
+use crate::{BitDepth, ColorType, Image, ImageInfo, Samples};
+
+/// A keyframe filled with a single color, given in place of an image path,
+/// for fading to or from a flat color without making an image of it.
+///
+/// It is written either as `#rrggbb` in hex or `color:r,g,b` in decimal, optionally
+/// followed by a size such as `x640x480`. Without a size it takes the size of the
+/// nearest image file in the sequence. The `#` can be left out when parsing a color
+/// which isn't standing in for a path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SolidColor {
+    pub rgb: [u8; 3],
+    pub size: Option<(u32, u32)>,
+}
+
+impl SolidColor {
+    /// Whether `p` names a solid color rather than a file, files whose names start with
+    /// `#` or `color:` can still be used by writing them as `./#name.png`
+    pub fn is_solid_color(p: &Path) -> bool {
+        p.to_str()
+            .is_some_and(|s| s.starts_with('#') || s.starts_with("color:"))
+    }
+
+    /// Parses `p` as a solid color, `None` means it is the path of an image file
+    pub fn from_path(p: &Path) -> Result<Option<Self>> {
+        match p.to_str() {
+            Some(s) if Self::is_solid_color(p) => s.parse().map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// An image of this color with the properties described by `info`,
+    /// grayscale images take the color's brightness and any alpha is fully opaque
+    pub fn image(&self, info: ImageInfo) -> Image {
+        let [r, g, b] = self.rgb.map(f64::from);
+        let luma = (0.299 * r + 0.587 * g + 0.114 * b).round() as u8;
+        let pixel: Vec<u8> = match info.color_type {
+            ColorType::Gray => vec![luma],
+            ColorType::GrayAlpha => vec![luma, u8::MAX],
+            ColorType::Rgb => self.rgb.to_vec(),
+            ColorType::Rgba => vec![self.rgb[0], self.rgb[1], self.rgb[2], u8::MAX],
+        };
+
+        let pixels = info.width as usize * info.height as usize;
+        let data = match info.bit_depth {
+            BitDepth::Eight => Samples::Eight(pixel.repeat(pixels)),
+            // 257 maps 255 onto 65535 exactly
+            BitDepth::Sixteen => Samples::Sixteen(
+                pixel
+                    .iter()
+                    .map(|&v| v as u16 * 257)
+                    .collect::<Vec<_>>()
+                    .repeat(pixels),
+            ),
+            BitDepth::Float => Samples::Float(
+                pixel
+                    .iter()
+                    .map(|&v| v as f32 / 255.0)
+                    .collect::<Vec<_>>()
+                    .repeat(pixels),
+            ),
+        };
+
+        Image {
+            data,
+            color_type: info.color_type,
+            width: info.width,
+            height: info.height,
+            profile: Default::default(),
+        }
+    }
+}
+
+impl FromStr for SolidColor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || {
+            anyhow!(
+                "Invalid solid color {:?}, it must be e.g. #ff8000 or color:255,128,0 with an optional size like x640x480.",
+                s
+            )
+        };
+
+        let (color, size) = match s.split_once('x') {
+            Some((color, size)) => (color, Some(size)),
+            None => (s, None),
+        };
+
+        let hex = match color.strip_prefix('#') {
+            Some(hex) => Some(hex),
+            None if !color.starts_with("color:") => Some(color),
+            None => None,
+        };
+        let rgb: Vec<u8> = if let Some(hex) = hex {
+            ensure!(
+                hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()),
+                invalid()
+            );
+            (0..6)
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+                .collect::<Result<_, _>>()
+        } else {
+            color
+                .strip_prefix("color:")
+                .ok_or_else(invalid)?
+                .split(',')
+                .map(|v| v.trim().parse())
+                .collect::<Result<_, _>>()
+        }
+        .with_context(invalid)?;
+        ensure!(rgb.len() == 3, invalid());
+
+        let size = size
+            .map(|size| -> Result<(u32, u32)> {
+                let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+                let (width, height) = (
+                    width.parse().with_context(invalid)?,
+                    height.parse().with_context(invalid)?,
+                );
+                ensure!(width > 0 && height > 0, invalid());
+                Ok((width, height))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            rgb: [rgb[0], rgb[1], rgb[2]],
+            size,
+        })
+    }
+}
@@ -0,0 +1,116 @@
+use std::convert::TryFrom;
+
+use anyhow::{anyhow, ensure, Result};
+
+use crate::{BitDepth, ColorProfile, Image, ImageInfo, Samples};
+
+/// A grid of frames tiled into a single image, filled left to right then top to bottom
+#[derive(Debug, Clone)]
+pub struct SpriteSheet {
+    sheet: Image,
+    columns: usize,
+    frames: usize,
+}
+
+impl SpriteSheet {
+    /// Creates an empty sheet with space for `frames` frames like `info` in rows of `columns`.
+    ///
+    /// Cells left empty at the end of a partial last row are black, or transparent when the
+    /// frames have alpha.
+    pub fn new(info: ImageInfo, frames: usize, columns: usize) -> Result<Self> {
+        ensure!(frames > 0, "A sprite sheet needs at least one frame.");
+        ensure!(columns > 0, "A sprite sheet needs at least one column.");
+
+        let columns = columns.min(frames);
+        let rows = frames.div_ceil(columns);
+        let too_big = || {
+            anyhow!(
+                "A sprite sheet of {} frames of {}x{} pixels in {} columns is too big.",
+                frames,
+                info.width,
+                info.height,
+                columns
+            )
+        };
+        let width = u32::try_from(columns)
+            .ok()
+            .and_then(|columns| info.width.checked_mul(columns))
+            .ok_or_else(too_big)?;
+        let height = u32::try_from(rows)
+            .ok()
+            .and_then(|rows| info.height.checked_mul(rows))
+            .ok_or_else(too_big)?;
+        let len = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|pixels| pixels.checked_mul(info.color_type.channels()))
+            .ok_or_else(too_big)?;
+
+        let data = match info.bit_depth {
+            BitDepth::Eight => Samples::Eight(vec![0; len]),
+            BitDepth::Sixteen => Samples::Sixteen(vec![0; len]),
+            BitDepth::Float => Samples::Float(vec![0.0; len]),
+        };
+        Ok(Self {
+            sheet: Image {
+                data,
+                color_type: info.color_type,
+                width,
+                height,
+                profile: ColorProfile::default(),
+            },
+            columns,
+            frames,
+        })
+    }
+
+    /// Copies `frame` into the cell for the frame at index `n`
+    pub fn insert(&mut self, n: usize, frame: &Image) -> Result<()> {
+        ensure!(
+            n < self.frames,
+            "Frame {} doesn't fit in a sprite sheet of {} frames.",
+            n,
+            self.frames
+        );
+        let cell_width = self.sheet.width as usize / self.columns;
+        ensure!(
+            frame.width as usize == cell_width
+                && frame.color_type == self.sheet.color_type
+                && frame.data.bit_depth() == self.sheet.data.bit_depth(),
+            "Every frame of a sprite sheet must have the same size and color type."
+        );
+
+        let channels = frame.color_type.channels();
+        let row_len = frame.width as usize * channels;
+        let sheet_row_len = self.sheet.width as usize * channels;
+        let (x, y) = (n % self.columns, n / self.columns);
+        let offset = y * frame.height as usize * sheet_row_len + x * row_len;
+        match (&mut self.sheet.data, &frame.data) {
+            (Samples::Eight(sheet), Samples::Eight(data)) => {
+                blit(sheet, data, offset, row_len, sheet_row_len)
+            }
+            (Samples::Sixteen(sheet), Samples::Sixteen(data)) => {
+                blit(sheet, data, offset, row_len, sheet_row_len)
+            }
+            (Samples::Float(sheet), Samples::Float(data)) => {
+                blit(sheet, data, offset, row_len, sheet_row_len)
+            }
+            _ => unreachable!("the bit depths are checked above"),
+        }
+        self.sheet.profile = frame.profile.clone();
+        Ok(())
+    }
+
+    /// The finished sheet as a single image
+    pub fn into_image(self) -> Image {
+        self.sheet
+    }
+}
+
+/// Copies the rows of `row_len` values from `src` into `dst` starting at `offset`,
+/// where each row of `dst` is `dst_row_len` long
+fn blit<S: Copy>(dst: &mut [S], src: &[S], offset: usize, row_len: usize, dst_row_len: usize) {
+    for (i, row) in src.chunks_exact(row_len).enumerate() {
+        let start = offset + i * dst_row_len;
+        dst[start..start + row_len].copy_from_slice(row);
+    }
+}
@@ -0,0 +1,163 @@
+//! Rendering frames a horizontal strip of rows at a time, for images too large
+//! to comfortably hold whole frames of in memory.
+//!
+//! Each keyframe is decoded incrementally with the `png` reader, the strips at the same
+//! rows of each keyframe are blended and the result streamed straight into the encoder,
+//! so only a few strips are ever held at once whatever the size of the image.
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+
+use crate::{
+    bmp, exr,
+    image::{open_png, png_encoder},
+    jpeg,
+    orientation::Orientation,
+    tiff, ColorProfile, Image, ImageInfo, PngOptions, Samples,
+};
+
+/// A PNG being decoded a strip at a time
+struct StripReader<'a> {
+    reader: png::Reader<File>,
+    info: ImageInfo,
+    path: &'a Path,
+}
+
+impl<'a> StripReader<'a> {
+    fn open(path: &'a Path) -> Result<Self> {
+        let format = if jpeg::is_jpeg(path)? {
+            Some("JPEG")
+        } else if bmp::is_bmp(path)? {
+            Some("BMP")
+        } else if tiff::is_tiff(path)? {
+            Some("TIFF")
+        } else if exr::is_exr(path)? {
+            Some("EXR")
+        } else {
+            None
+        };
+        if let Some(format) = format {
+            bail!(
+                "Only PNG images can be decoded in strips, {:?} is a {}.",
+                path,
+                format
+            );
+        }
+
+        let (reader, info) = open_png(path)?;
+        // the rows of an interlaced image are spread across the whole file
+        ensure!(
+            !reader.info().interlaced,
+            "Interlaced images can't be decoded in strips, {:?} is interlaced.",
+            path
+        );
+        // turning the image upright needs all of its rows at once
+        ensure!(
+            Orientation::from_path(path)? == Orientation::Normal,
+            "Images with an EXIF orientation can't be decoded in strips, {:?} is rotated or flipped.",
+            path
+        );
+        Ok(Self { reader, info, path })
+    }
+
+    /// Decodes the next `rows` rows of the image, or fewer once it reaches the bottom
+    fn next_strip(&mut self, rows: u32) -> Result<Image> {
+        let mut buf = Vec::new();
+        let mut height = 0;
+        let path = self.path;
+        while height < rows {
+            let row = self
+                .reader
+                .next_row()
+                .with_context(|| format!("Reader failed to read a row from {:?}", path))?;
+            match row {
+                Some(row) => buf.extend_from_slice(row.data()),
+                None => break,
+            }
+            height += 1;
+        }
+
+        Ok(Image {
+            data: Samples::from_be_bytes(buf, self.info.bit_depth),
+            color_type: self.info.color_type,
+            width: self.info.width,
+            height,
+            profile: ColorProfile::default(),
+        })
+    }
+}
+
+/// Decodes the PNGs at `sources` in strips of `strip_rows` rows, combining the strips at
+/// the same rows of each with `combine` and encoding them one after another into a PNG at `p`.
+///
+/// Every source must have the dimensions, color type and bit depth given by `info`, and
+/// `combine` must give back a strip of the same size as the ones it is passed. The last
+/// strip is shorter than the rest when the height isn't a multiple of `strip_rows`.
+pub(crate) fn render_in_strips<F>(
+    sources: &[&Path],
+    info: ImageInfo,
+    profile: &ColorProfile,
+    options: &PngOptions,
+    p: &Path,
+    strip_rows: u32,
+    combine: F,
+) -> Result<()>
+where
+    F: Fn(Vec<Image>) -> Image,
+{
+    ensure!(strip_rows > 0, "Each strip must have at least one row.");
+
+    let mut readers = sources
+        .iter()
+        .map(|path| StripReader::open(path))
+        .collect::<Result<Vec<_>>>()?;
+    if let Some(reader) = readers.iter().find(|reader| reader.info != info) {
+        return Err(anyhow!(
+            "{:?} doesn't match the other images, they must all have the same size, color type and bit depth.",
+            reader.path
+        ));
+    }
+
+    let file = File::create(p)
+        .with_context(|| format!("Failed to create file at {:?} to save the image to.", p))?;
+    let mut writer = png_encoder(BufWriter::new(file), info, profile, options)?
+        .write_header()
+        .with_context(|| format!("Failed to write the PNG header to {:?}", p))?;
+    let stream = match options.chunk_size {
+        Some(chunk_size) => writer.stream_writer_with_size(chunk_size),
+        None => writer.stream_writer(),
+    };
+    let mut stream =
+        stream.with_context(|| format!("Failed to start writing the image data to {:?}", p))?;
+
+    let mut rows = 0;
+    while rows < info.height {
+        let strips = readers
+            .iter_mut()
+            .map(|reader| reader.next_strip(strip_rows))
+            .collect::<Result<Vec<_>>>()?;
+        let height = strips[0].height;
+        ensure!(
+            height > 0 && strips.iter().all(|strip| strip.height == height),
+            "The images ended after {} of their {} rows.",
+            rows,
+            info.height
+        );
+
+        let strip = combine(strips);
+        stream
+            .write_all(&strip.data.to_be_bytes())
+            .with_context(|| format!("Failed to write rows to {:?}", p))?;
+        rows += height;
+    }
+
+    stream
+        .finish()
+        .and_then(|_| writer.finish())
+        .with_context(|| format!("Failed to finish writing the PNG {:?}", p))
+}
@@ -0,0 +1,169 @@
+#[cfg(feature = "tiff")]
+use std::{fs::File, io::BufReader};
+use std::{io::Read, path::Path};
+
+#[cfg(not(feature = "tiff"))]
+use anyhow::anyhow;
+use anyhow::Result;
+#[cfg(feature = "tiff")]
+use anyhow::{anyhow, ensure};
+#[cfg(feature = "tiff")]
+use log::debug;
+#[cfg(feature = "tiff")]
+use tiff::decoder::{Decoder, DecodingResult, Limits};
+
+use crate::{error::open_image, Image, ImageInfo};
+#[cfg(feature = "tiff")]
+use crate::{error::DecodeContext, BitDepth, ColorType, Samples};
+
+/// The bytes a TIFF file starts with, in little-endian and big-endian byte order
+const SIGNATURES: [[u8; 4]; 2] = [*b"II*\0", *b"MM\0*"];
+
+/// Checks the first bytes of the file at `path` to see whether it is a TIFF
+pub(crate) fn is_tiff(path: &Path) -> Result<bool> {
+    let mut file = open_image(path)?;
+    let mut signature = [0; 4];
+    match file.read_exact(&mut signature) {
+        Ok(()) => Ok(SIGNATURES.contains(&signature)),
+        // files too short to be a TIFF are left for the PNG decoder to report
+        Err(_) => Ok(false),
+    }
+}
+
+/// Opens the TIFF file at `path` on page `page`, checking that the page
+/// is in a format which can be interpolated
+#[cfg(feature = "tiff")]
+fn open_tiff(path: &Path, page: usize) -> Result<(Decoder<BufReader<File>>, ImageInfo)> {
+    let file = open_image(path)?;
+    // the size of the image is checked against what can be addressed when it's decoded
+    let mut decoder = Decoder::new(BufReader::new(file))
+        .decode_context(path, || {
+            format!("Decoder failed to read information from {:?}", path)
+        })?
+        .with_limits(Limits::unlimited());
+    if page > 0 {
+        decoder.seek_to_image(page).decode_context(path, || {
+            format!("Decoder failed to find page {} of {:?}", page, path)
+        })?;
+    }
+
+    let (width, height) = decoder.dimensions().decode_context(path, || {
+        format!("Decoder failed to read information from {:?}", path)
+    })?;
+    let color_type = decoder.colortype().decode_context(path, || {
+        format!("Decoder failed to read information from {:?}", path)
+    })?;
+    let (color_type, bits) = match color_type {
+        tiff::ColorType::Gray(bits) => (ColorType::Gray, bits),
+        tiff::ColorType::GrayA(bits) => (ColorType::GrayAlpha, bits),
+        tiff::ColorType::RGB(bits) => (ColorType::Rgb, bits),
+        tiff::ColorType::RGBA(bits) => (ColorType::Rgba, bits),
+        tiff::ColorType::CMYK(_) => {
+            return Err(anyhow!(
+                "Unsupported color type CMYK in {:?}, only grayscale, RGB and RGBA TIFFs are supported.",
+                path
+            ))
+        }
+        other => {
+            return Err(anyhow!(
+                "Unsupported color type {:?} in {:?}, only grayscale, RGB and RGBA TIFFs are supported.",
+                other,
+                path
+            ))
+        }
+    };
+    let bit_depth = match bits {
+        8 => BitDepth::Eight,
+        16 => BitDepth::Sixteen,
+        other => {
+            return Err(anyhow!(
+                "Unsupported bit depth {} in {:?}, only 8 and 16 bit images are supported.",
+                other,
+                path
+            ))
+        }
+    };
+
+    let info = ImageInfo {
+        width,
+        height,
+        color_type,
+        bit_depth,
+    };
+    Ok((decoder, info))
+}
+
+/// Reads just the header of the first page of the TIFF at `path`
+#[cfg(feature = "tiff")]
+pub(crate) fn read_info(path: &Path) -> Result<ImageInfo> {
+    let (_, info) = open_tiff(path, 0)?;
+    Ok(info)
+}
+
+/// The number of pages in the TIFF at `path`
+#[cfg(feature = "tiff")]
+pub(crate) fn page_count(path: &Path) -> Result<usize> {
+    let (mut decoder, _) = open_tiff(path, 0)?;
+    let mut pages = 1;
+    while decoder.more_images() {
+        decoder.next_image().decode_context(path, || {
+            format!("Decoder failed to read page {} of {:?}", pages, path)
+        })?;
+        pages += 1;
+    }
+    Ok(pages)
+}
+
+/// Decodes page `page` of the TIFF at `path`, the first page being 0
+#[cfg(feature = "tiff")]
+pub(crate) fn decode(path: &Path, page: usize) -> Result<Image> {
+    let (mut decoder, info) = open_tiff(path, page)?;
+    let data = match decoder
+        .read_image()
+        .decode_context(path, || format!("Decoder failed to decode page {} of {:?}", page, path))?
+    {
+        DecodingResult::U8(data) => Samples::Eight(data),
+        DecodingResult::U16(data) => Samples::Sixteen(data),
+        _ => {
+            return Err(anyhow!(
+                "Unsupported sample format in {:?}, only 8 and 16 bit unsigned integer TIFFs are supported.",
+                path
+            ))
+        }
+    };
+    ensure!(
+        data.bit_depth() == info.bit_depth,
+        "Decoder gave {} bit values for the {} bit page {} of {:?}.",
+        data.bit_depth().bits(),
+        info.bit_depth.bits(),
+        page,
+        path
+    );
+    debug!("Decoded page {} of the TIFF {:?}", page, path);
+    Image::new_from_parts(data, info.color_type, info.width, info.height)
+}
+
+// TIFFs are still recognised without the decoder, so they can be reported clearly
+// rather than failing as broken PNGs
+#[cfg(not(feature = "tiff"))]
+pub(crate) fn read_info(path: &Path) -> Result<ImageInfo> {
+    Err(disabled(path))
+}
+
+#[cfg(not(feature = "tiff"))]
+pub(crate) fn page_count(path: &Path) -> Result<usize> {
+    Err(disabled(path))
+}
+
+#[cfg(not(feature = "tiff"))]
+pub(crate) fn decode(path: &Path, _page: usize) -> Result<Image> {
+    Err(disabled(path))
+}
+
+#[cfg(not(feature = "tiff"))]
+fn disabled(path: &Path) -> anyhow::Error {
+    anyhow!(
+        "{:?} is a TIFF, which can only be decoded when interpol is built with the \"tiff\" feature.",
+        path
+    )
+}
@@ -0,0 +1,142 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+
+use crate::{smooth, Image, Sample, Samples};
+
+/// How the end image of a transition takes the place of the start image
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Transition {
+    /// Every pixel is blended from the start image to the end image at once
+    Dissolve,
+    /// The end image is revealed by an edge moving from the right of the frame to the left
+    WipeLeft,
+    /// The end image is revealed by an edge moving from the left of the frame to the right
+    WipeRight,
+    /// The end image slides in from the right, pushing the start image off to the left
+    Slide,
+    /// The end image is revealed by a circle growing from the middle of the frame
+    Iris,
+}
+
+impl Transition {
+    pub const VARIANTS: [&'static str; 5] =
+        ["dissolve", "wipe-left", "wipe-right", "slide", "iris"];
+
+    /// Whether the pixels of the images move around the frame, rather than each pixel
+    /// only ever showing that pixel of the start or end image
+    pub(crate) fn moves_pixels(self) -> bool {
+        self == Transition::Slide
+    }
+
+    /// Composes the frame `mu` of the way from `start` to `end` for every transition
+    /// except `Dissolve`, copying the region of the frame the end image has reached so far
+    /// over the start image.
+    ///
+    /// The edges of wipes and irises are blended across `feather` pixels, a slide always has
+    /// a hard edge as the two images are side by side.
+    pub(crate) fn compose(self, mu: f64, start: &Image, end: &Image, feather: u32) -> Image {
+        let data = match (&start.data, &end.data) {
+            (Samples::Eight(s), Samples::Eight(e)) => {
+                Samples::Eight(self.compose_samples(mu, s, e, start, feather))
+            }
+            (Samples::Sixteen(s), Samples::Sixteen(e)) => {
+                Samples::Sixteen(self.compose_samples(mu, s, e, start, feather))
+            }
+            (Samples::Float(s), Samples::Float(e)) => {
+                Samples::Float(self.compose_samples(mu, s, e, start, feather))
+            }
+            _ => unreachable!("images of differing bit depths are rejected by the Interpolator"),
+        };
+
+        Image {
+            data,
+            color_type: start.color_type,
+            width: start.width,
+            height: start.height,
+            profile: start.profile.clone(),
+        }
+    }
+
+    /// Composes the channel values of two images with the dimensions of `layout`
+    fn compose_samples<S: Sample>(
+        self,
+        mu: f64,
+        start: &[S],
+        end: &[S],
+        layout: &Image,
+        feather: u32,
+    ) -> Vec<S> {
+        let (width, height) = (layout.width as usize, layout.height as usize);
+        let channels = layout.color_type.channels();
+
+        if self == Transition::Slide {
+            // the columns which have slid in so far, rounded to whole pixels
+            let offset = (mu * width as f64).round() as usize;
+            let row_len = width * channels;
+            let split = (width - offset.min(width)) * channels;
+            let mut data = Vec::with_capacity(start.len());
+            for (s, e) in start.chunks_exact(row_len).zip(end.chunks_exact(row_len)) {
+                data.extend_from_slice(&s[row_len - split..]);
+                data.extend_from_slice(&e[..row_len - split]);
+            }
+            return data;
+        }
+
+        // the edge travels far enough that the feathering starts and ends outside the frame
+        let feather = feather as f64;
+        let (cx, cy) = (width as f64 / 2.0, height as f64 / 2.0);
+        let distance = match self {
+            Transition::WipeLeft | Transition::WipeRight => width as f64,
+            _ => cx.hypot(cy),
+        };
+        let edge = mu * (distance + feather) - feather / 2.0;
+
+        let mut data = Vec::with_capacity(start.len());
+        for (i, (s, e)) in start
+            .chunks_exact(channels)
+            .zip(end.chunks_exact(channels))
+            .enumerate()
+        {
+            // measured from the pixel's centre to where the edge started
+            let (x, y) = ((i % width) as f64 + 0.5, (i / width) as f64 + 0.5);
+            let position = match self {
+                Transition::WipeLeft => width as f64 - x,
+                Transition::WipeRight => x,
+                _ => (x - cx).hypot(y - cy),
+            };
+
+            // the share of the pixel the end image has covered
+            let weight = if feather > 0.0 {
+                (edge - position) / feather + 0.5
+            } else if position < edge {
+                1.0
+            } else {
+                0.0
+            };
+            if weight <= 0.0 {
+                data.extend_from_slice(s);
+            } else if weight >= 1.0 {
+                data.extend_from_slice(e);
+            } else {
+                data.extend(s.iter().zip(e).map(|(&s, &e)| smooth(weight, s, e)));
+            }
+        }
+        data
+    }
+}
+
+impl FromStr for Transition {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "dissolve" => Ok(Transition::Dissolve),
+            "wipe-left" => Ok(Transition::WipeLeft),
+            "wipe-right" => Ok(Transition::WipeRight),
+            "slide" => Ok(Transition::Slide),
+            "iris" => Ok(Transition::Iris),
+            _ => Err(anyhow!("Unknown transition {:?}.", s)),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, ensure, Result};
+use minifb::{Key, Scale, ScaleMode, Window, WindowOptions};
+
+use crate::{ColorType, Image, ImageInfo, Sample, Samples};
+
+/// A window frames are played in as they are generated, at no more than a set frame rate
+pub struct PreviewWindow {
+    window: Window,
+    /// The pixels of the frame on show as 0RGB, as the window takes them
+    buffer: Vec<u32>,
+    width: usize,
+    height: usize,
+    frame_time: Duration,
+    shown: Option<Instant>,
+}
+
+impl PreviewWindow {
+    /// Opens a window for showing frames like `info` at up to `max_fps` frames per second,
+    /// scaled up to fill as much of the screen as they can for small frames
+    pub fn new(info: ImageInfo, max_fps: f64) -> Result<Self> {
+        ensure!(
+            max_fps.is_finite() && max_fps > 0.0,
+            "The preview window's frame rate must be a positive number, not {}.",
+            max_fps
+        );
+        let (width, height) = (info.width as usize, info.height as usize);
+        let options = WindowOptions {
+            resize: true,
+            scale: Scale::FitScreen,
+            scale_mode: ScaleMode::AspectRatioStretch,
+            ..WindowOptions::default()
+        };
+        let mut window = Window::new("interpol", width, height, options)
+            .map_err(|e| anyhow!("Failed to open the preview window: {}", e))?;
+        // the frames are paced by `show`, so drawing them shouldn't wait as well
+        window.set_target_fps(0);
+        Ok(Self {
+            window,
+            buffer: vec![0; width * height],
+            width,
+            height,
+            frame_time: Duration::from_secs_f64(1.0 / max_fps),
+            shown: None,
+        })
+    }
+
+    /// Whether the window is still open, until it is closed or escape is pressed
+    pub fn is_open(&self) -> bool {
+        self.window.is_open() && !self.window.is_key_down(Key::Escape)
+    }
+
+    /// Sets the title of the window
+    pub fn set_title(&mut self, title: &str) {
+        self.window.set_title(title);
+    }
+
+    /// Shows `image`, which must be the size of the frames the window was opened for, once
+    /// a frame's time has passed since the last one. Any transparency is shown over black.
+    pub fn show(&mut self, image: &Image) -> Result<()> {
+        ensure!(
+            (image.width() as usize, image.height() as usize) == (self.width, self.height),
+            "The preview window can only show {}x{} frames.",
+            self.width,
+            self.height
+        );
+        match image.data() {
+            Samples::Eight(data) => fill_buffer(data, image.color_type(), &mut self.buffer),
+            Samples::Sixteen(data) => fill_buffer(data, image.color_type(), &mut self.buffer),
+            Samples::Float(data) => fill_buffer(data, image.color_type(), &mut self.buffer),
+        }
+
+        if let Some(shown) = self.shown {
+            let wait = (shown + self.frame_time).saturating_duration_since(Instant::now());
+            thread::sleep(wait);
+        }
+        self.shown = Some(Instant::now());
+        self.redraw()
+    }
+
+    /// Draws the frame on show again, which also handles the window being moved, resized
+    /// or closed in the meantime
+    pub fn redraw(&mut self) -> Result<()> {
+        self.window
+            .update_with_buffer(&self.buffer, self.width, self.height)
+            .map_err(|e| anyhow!("Failed to update the preview window: {}", e))
+    }
+
+    /// The time between frames at the window's frame rate
+    pub fn frame_time(&self) -> Duration {
+        self.frame_time
+    }
+}
+
+/// Writes the pixels of `data` into `buffer` as 0RGB, grayscale being spread over the
+/// three channels, the colors of pixels with alpha faded to black by it, and the values of
+/// floats outside `0.0..=1.0` clipped so they can't spill into the neighbouring channels
+fn fill_buffer<S: Sample>(data: &[S], color_type: ColorType, buffer: &mut [u32]) {
+    let channels = color_type.channels();
+    for (px, out) in data.chunks_exact(channels).zip(buffer) {
+        let alpha = if color_type.has_alpha() {
+            px[channels - 1].to_f64() / S::MAX
+        } else {
+            1.0
+        };
+        let value = |v: S| (v.to_f64() / S::MAX * alpha * 255.0).round().clamp(0.0, 255.0) as u32;
+        let (r, g, b) = match color_type.color_channels() {
+            1 => (px[0], px[0], px[0]),
+            _ => (px[0], px[1], px[2]),
+        };
+        *out = value(r) << 16 | value(g) << 8 | value(b);
+    }
+}
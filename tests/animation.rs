@@ -0,0 +1,104 @@
+#[cfg(feature = "gif")]
+use std::{borrow::Cow, fs::File, io::BufWriter};
+use std::{env, fs, path::PathBuf};
+
+use interpol::{save_apng, ColorType, FrameDelay, Image, Interpolator, Samples};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("interpol-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn apng_frames_become_keyframes() {
+    let dir = temp_dir("apng");
+    let frames: Vec<_> = [0u8, 100, 200]
+        .iter()
+        .map(|&v| Image::new_from_parts(vec![v; 2 * 3], ColorType::Rgb, 2, 1).unwrap())
+        .collect();
+    let path = dir.join("animation.png");
+    let delay = FrameDelay {
+        numerator: 1,
+        denominator: 10,
+    };
+    save_apng(frames.clone().into_iter().map(Ok), &path, delay).unwrap();
+
+    let decoded = Image::frames_from_path(&path).unwrap();
+    assert_eq!(decoded.len(), 3);
+    for (decoded, frame) in decoded.iter().zip(&frames) {
+        assert_eq!(decoded.data(), frame.data());
+    }
+
+    // each frame of the animation is a keyframe, with a still image after them
+    let still = dir.join("still.png");
+    frames[0].save(&still).unwrap();
+    let interpolator = Interpolator::from_paths(vec![&path, &still], 2).unwrap();
+    assert_eq!(interpolator.total_frames(), 3 * 2 + 1);
+    let frames: Vec<_> = interpolator.collect::<Result<_, _>>().unwrap();
+    assert_eq!(frames[2].data(), &Samples::Eight(vec![100; 2 * 3]));
+    assert_eq!(frames[5].data(), &Samples::Eight(vec![100; 2 * 3]));
+
+    // preloading decodes the animation in one go, giving the same keyframes
+    let preloaded = Interpolator::from_paths(vec![&path, &still], 2)
+        .unwrap()
+        .preload()
+        .unwrap();
+    assert_eq!(preloaded.collect::<Result<Vec<_>, _>>().unwrap(), frames);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "gif")]
+#[test]
+fn partial_gif_frames_are_drawn_over_the_canvas() {
+    let dir = temp_dir("gif");
+    let path = dir.join("animation.gif");
+    // black, red, green and white
+    let palette = [0, 0, 0, 255, 0, 0, 0, 255, 0, 255, 255, 255];
+    let mut encoder =
+        gif::Encoder::new(BufWriter::new(File::create(&path).unwrap()), 2, 1, &palette).unwrap();
+    let frame = |left, indices: &[u8], dispose| gif::Frame {
+        left,
+        width: indices.len() as u16,
+        height: 1,
+        dispose,
+        buffer: Cow::Owned(indices.to_vec()),
+        ..gif::Frame::default()
+    };
+    // a full frame, then one covering just the right pixel which is cleared afterwards,
+    // and then one covering just the left pixel
+    encoder
+        .write_frame(&frame(0, &[1, 0], gif::DisposalMethod::Keep))
+        .unwrap();
+    encoder
+        .write_frame(&frame(1, &[2], gif::DisposalMethod::Background))
+        .unwrap();
+    encoder
+        .write_frame(&frame(0, &[3], gif::DisposalMethod::Keep))
+        .unwrap();
+    drop(encoder);
+
+    let frames: Vec<_> = Image::frames_from_path(&path)
+        .unwrap()
+        .into_iter()
+        .map(|frame| frame.data().clone())
+        .collect();
+    assert_eq!(
+        frames,
+        [
+            Samples::Eight(vec![255, 0, 0, 255, 0, 0, 0, 255]),
+            Samples::Eight(vec![255, 0, 0, 255, 0, 255, 0, 255]),
+            Samples::Eight(vec![255, 255, 255, 255, 0, 0, 0, 0]),
+        ]
+    );
+
+    // lazily loaded frames are drawn the same whichever order they are asked for in
+    let interpolator = Interpolator::from_paths(vec![&path], 1).unwrap();
+    for &n in &[2, 1, 0, 2, 1, 1, 0] {
+        let frame = interpolator.frame_at(n).unwrap().unwrap();
+        assert_eq!(frame.data(), &frames[n]);
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,23 @@
+use interpol::{ColorType, Image, Interpolator, Samples};
+
+#[test]
+fn partly_transparent_pixels_are_mixed_with_the_background() {
+    let pixel = |alpha: u8| Image::new_from_parts(vec![255, 0, 0, alpha], ColorType::Rgba, 1, 1);
+    let keyframes = vec![pixel(0).unwrap(), pixel(255).unwrap()];
+    let interpolator = Interpolator::new(keyframes, 2)
+        .unwrap()
+        .background([0, 0, 255]);
+    assert_eq!(interpolator.frame_info().color_type, ColorType::Rgb);
+
+    let frames: Vec<_> = interpolator
+        .map(|frame| frame.unwrap().data().clone())
+        .collect();
+    assert_eq!(
+        frames,
+        vec![
+            Samples::Eight(vec![0, 0, 255]),
+            Samples::Eight(vec![128, 0, 127]),
+            Samples::Eight(vec![255, 0, 0]),
+        ]
+    );
+}
@@ -0,0 +1,42 @@
+use interpol::{BurnIn, ColorType, Corner, Image, Interpolator, Samples};
+
+fn gray(value: u8) -> Image {
+    Image::new_from_parts(vec![value; 60 * 20], ColorType::Gray, 60, 20).unwrap()
+}
+
+#[test]
+fn timecodes_count_hours_minutes_seconds_and_frames() {
+    assert_eq!(BurnIn::None.label(7, 25.0), None);
+    assert_eq!(BurnIn::Frame.label(7, 25.0).unwrap(), "7");
+    assert_eq!(BurnIn::Timecode.label(33, 25.0).unwrap(), "00:00:01:08");
+    assert_eq!(
+        BurnIn::Timecode
+            .label(3600 * 30 + 61 * 30 + 29, 29.97)
+            .unwrap(),
+        "01:01:01:29"
+    );
+}
+
+#[test]
+fn frames_are_stamped_in_their_corner() {
+    let interpolator = Interpolator::new(vec![gray(200), gray(200)], 12)
+        .unwrap()
+        .hold(2)
+        .burn_in(BurnIn::Frame, Corner::BottomRight, 30.0);
+    // every frame has its own label, so none of them are copies of another
+    assert!((0..interpolator.total_frames()).all(|n| interpolator.duplicate_of(n).is_none()));
+
+    let frame = interpolator.frame_at(11).unwrap().unwrap();
+    let data = match frame.data() {
+        Samples::Eight(data) => data.clone(),
+        _ => unreachable!(),
+    };
+    let pixel = |x: usize, y: usize| data[y * 60 + x];
+    // "11" is 13 pixels wide and 9 tall, on a box darkening the frame by half
+    assert_eq!(pixel(0, 0), 200);
+    assert_eq!(pixel(46, 10), 200);
+    assert_eq!(pixel(47, 11), 100);
+    assert_eq!(pixel(59, 19), 100);
+    // the top of the second 1
+    assert_eq!(pixel(56, 12), 255);
+}
@@ -0,0 +1,57 @@
+use std::{env, fs};
+
+use interpol::{ColorProfile, ColorType, Image, Interpolator};
+
+fn gamma(scaled: u32) -> ColorProfile {
+    ColorProfile {
+        gamma: Some(scaled),
+        ..ColorProfile::default()
+    }
+}
+
+fn solid(value: u8, profile: ColorProfile) -> Image {
+    Image::new_from_parts(vec![value; 2 * 2 * 3], ColorType::Rgb, 2, 2)
+        .unwrap()
+        .with_color_profile(profile)
+}
+
+#[test]
+fn profiles_survive_saving_and_loading() {
+    let dir = env::temp_dir().join(format!("interpol-color-profile-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let profile = ColorProfile {
+        chromaticities: Some([31270, 32900, 64000, 33000, 30000, 60000, 15000, 6000]),
+        icc_profile: Some(b"not really an ICC profile".to_vec().into()),
+        ..gamma(45455)
+    };
+    let path = dir.join("tagged.png");
+    solid(10, profile.clone()).save(&path).unwrap();
+
+    assert_eq!(
+        Image::new_from_path(&path).unwrap().color_profile(),
+        &profile
+    );
+    assert_eq!(ColorProfile::from_path(&path).unwrap(), profile);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn frames_take_the_profile_of_tagged_keyframes() {
+    let keyframes = vec![solid(0, ColorProfile::default()), solid(100, gamma(45455))];
+    let frames: Vec<_> = Interpolator::new(keyframes, 2)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    for frame in frames {
+        assert_eq!(frame.color_profile(), &gamma(45455));
+    }
+}
+
+#[test]
+fn conflicting_profiles_are_rejected() {
+    let keyframes = vec![solid(0, gamma(45455)), solid(100, gamma(100000))];
+    assert!(Interpolator::new(keyframes, 2).is_err());
+}
@@ -0,0 +1,26 @@
+use interpol::{ColorSpace, ColorType, Image, Interpolator, Samples};
+
+fn midpoint(color_type: ColorType, start: Vec<u8>, end: Vec<u8>) -> Samples {
+    let image = |data| Image::new_from_parts(data, color_type, 1, 1).unwrap();
+    Interpolator::new(vec![image(start), image(end)], 2)
+        .unwrap()
+        .color_space(ColorSpace::Oklab)
+        .nth(1)
+        .unwrap()
+        .unwrap()
+        .data()
+        .clone()
+}
+
+#[test]
+fn oklab_fades_evenly_in_lightness() {
+    // halfway in OKLab lightness is an eighth of the light, far darker than the sRGB midpoint
+    assert_eq!(
+        midpoint(ColorType::Rgb, vec![0; 3], vec![255; 3]),
+        Samples::Eight(vec![99; 3])
+    );
+    assert_eq!(
+        midpoint(ColorType::GrayAlpha, vec![0, 0], vec![255, 255]),
+        Samples::Eight(vec![99, 128])
+    );
+}
@@ -0,0 +1,28 @@
+use interpol::{BitDepth, ColorType, ContactSheet, Image, ImageInfo, Samples};
+
+#[test]
+fn frames_are_shrunk_into_labelled_cells() {
+    let frame = Image::new_from_parts(vec![128u8; 40 * 20], ColorType::Gray, 40, 20).unwrap();
+    let info = ImageInfo {
+        width: 40,
+        height: 20,
+        color_type: ColorType::Gray,
+        bit_depth: BitDepth::Eight,
+    };
+    let mut sheet = ContactSheet::new(info, 3, 2, 20).unwrap();
+    for (cell, index) in [0, 10, 20].iter().enumerate() {
+        sheet.insert(cell, *index, &frame).unwrap();
+    }
+    let image = sheet.into_image();
+    assert_eq!((image.width(), image.height()), (40, 20));
+
+    let data = match image.data() {
+        Samples::Eight(data) => data,
+        _ => unreachable!(),
+    };
+    let pixel = |x: usize, y: usize| data[y * 40 + x];
+    // the border of the label's box, the top of the 1 of 10, and the frame itself
+    assert_eq!(pixel(20, 0), 0);
+    assert_eq!(pixel(23, 1), 255);
+    assert_eq!(pixel(39, 9), 128);
+}
@@ -0,0 +1,29 @@
+use std::{env, fs};
+
+use interpol::{ColorType, Image, ImageLoader};
+
+#[test]
+fn cached_images_are_replaced_when_the_file_changes() {
+    let dir = env::temp_dir().join(format!("interpol-cache-{}", std::process::id()));
+    let cache = dir.join("cache");
+    fs::create_dir_all(&dir).unwrap();
+
+    let path = dir.join("image.png");
+    let first = Image::new_from_parts(vec![10u8; 2 * 2 * 3], ColorType::Rgb, 2, 2).unwrap();
+    first.save(&path).unwrap();
+
+    let loader = ImageLoader::new().cache_dir(&cache);
+    assert_eq!(loader.load(&path).unwrap().data(), first.data());
+    assert_eq!(fs::read_dir(&cache).unwrap().count(), 1);
+    // the second load comes from the cache
+    assert_eq!(loader.load(&path).unwrap().data(), first.data());
+
+    let second = Image::new_from_parts(vec![200u8; 3 * 2 * 3], ColorType::Rgb, 3, 2).unwrap();
+    second.save(&path).unwrap();
+    let reloaded = loader.load(&path).unwrap();
+    assert_eq!(reloaded.data(), second.data());
+    assert_eq!(reloaded.width(), 3);
+    assert_eq!(fs::read_dir(&cache).unwrap().count(), 1);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,104 @@
+use std::{env, fs};
+
+use interpol::{ColorSpace, ColorType, Image, ImageInfo, Interpolator};
+
+fn solid(value: u8) -> Image {
+    Image::new_from_parts(vec![value; 2 * 2 * 3], ColorType::Rgb, 2, 2).unwrap()
+}
+
+#[test]
+fn zero_steps_is_an_error() {
+    assert!(Interpolator::new(vec![solid(0), solid(255)], 0).is_err());
+}
+
+#[test]
+fn zero_frames_between_is_an_error() {
+    let interpolator = Interpolator::new(vec![solid(0), solid(255)], 5).unwrap();
+    assert!(interpolator.frames_between(vec![0]).is_err());
+}
+
+#[test]
+fn a_single_image_is_an_error() {
+    assert!(Interpolator::new(vec![solid(0)], 5).is_err());
+}
+
+#[test]
+fn mismatched_formats_name_the_image() {
+    let rgba = Image::new_from_parts(vec![0u8; 2 * 2 * 4], ColorType::Rgba, 2, 2).unwrap();
+    let sixteen = Image::new_from_parts(vec![0u16; 2 * 2 * 3], ColorType::Rgb, 2, 2).unwrap();
+    for (image, description) in [(rgba, "2x2 RGBA 8 bit"), (sixteen, "2x2 RGB 16 bit")] {
+        let error = Interpolator::new(vec![solid(0), solid(255), image], 5)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.starts_with(&format!(
+                "Keyframe 2 is {}, but Keyframe 0 is 2x2 RGB 8 bit",
+                description
+            )),
+            "{}",
+            error
+        );
+    }
+}
+
+#[test]
+fn two_images_with_one_step_are_just_the_images() {
+    let frames: Vec<_> = Interpolator::new(vec![solid(0), solid(255)], 1)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(frames, vec![solid(0), solid(255)]);
+}
+
+#[test]
+fn dimensions_overflowing_u32_are_an_error() {
+    // 70000 * 70000 pixels wraps around in 32 bit arithmetic
+    assert!(Image::new_from_parts(vec![0u8; 3], ColorType::Rgb, 70000, 70000).is_err());
+    assert!(Image::new_from_parts(vec![0u8; 3], ColorType::Rgb, u32::MAX, u32::MAX).is_err());
+}
+
+#[test]
+fn rgb_buffers_must_match_their_dimensions() {
+    assert!(Image::from_rgb_buffer(&[0; 2 * 2 * 3 - 1], 2, 2).is_err());
+
+    let image = Image::from_rgb_buffer(&[7; 2 * 2 * 3], 2, 2).unwrap();
+    assert_eq!(image, solid(7));
+    assert_eq!(image.to_rgb_buffer(), Some(&[7; 2 * 2 * 3][..]));
+}
+
+#[test]
+fn identical_keyframes_produce_identical_frames() {
+    let frames: Vec<_> = Interpolator::new(vec![solid(77), solid(77)], 5)
+        .unwrap()
+        .color_space(ColorSpace::Hsv)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert!(frames.iter().all(|frame| *frame == solid(77)));
+}
+
+#[test]
+fn truncated_pngs_are_an_error_naming_the_file() {
+    let png = fs::read("tests/fixtures/start.png").unwrap();
+    let dir = env::temp_dir().join(format!("interpol-truncated-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    // every length short of the whole file, from empty through to just missing the IEND chunk
+    for len in 0..png.len() - 12 {
+        let path = dir.join(format!("{}.png", len));
+        fs::write(&path, &png[..len]).unwrap();
+        let error = Image::new_from_path(&path).unwrap_err();
+        assert!(
+            format!("{:#}", error).contains(&format!("{:?}", path)),
+            "{:#}",
+            error
+        );
+    }
+
+    let empty = dir.join("0.png");
+    let error = ImageInfo::from_path(&empty).unwrap_err().to_string();
+    assert_eq!(
+        error,
+        format!("{:?} is empty, there is no image in it.", empty)
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,27 @@
+use interpol::{ColorType, Image, Samples};
+
+#[test]
+fn diffs_are_amplified_and_alpha_shows_in_the_colors() {
+    let a =
+        Image::new_from_parts(vec![10u8, 20, 30, 255, 0, 0, 0, 0], ColorType::Rgba, 2, 1).unwrap();
+    let b =
+        Image::new_from_parts(vec![12u8, 10, 30, 255, 0, 0, 0, 40], ColorType::Rgba, 2, 1).unwrap();
+
+    let diff = a.diff(&b, 1.0).unwrap();
+    assert_eq!(diff.color_type(), ColorType::Rgb);
+    assert_eq!(diff.data(), &Samples::Eight(vec![2, 10, 0, 40, 40, 40]));
+    // amplifying saturates rather than wrapping around
+    assert_eq!(
+        b.diff(&a, 10.0).unwrap().data(),
+        &Samples::Eight(vec![20, 100, 0, 255, 255, 255])
+    );
+
+    let gray = Image::new_from_parts(vec![0u16, 1000], ColorType::Gray, 2, 1).unwrap();
+    let other = Image::new_from_parts(vec![500u16, 0], ColorType::Gray, 2, 1).unwrap();
+    assert_eq!(
+        gray.diff(&other, 2.0).unwrap().data(),
+        &Samples::Sixteen(vec![1000, 2000])
+    );
+    assert!(gray.diff(&a, 1.0).is_err());
+    assert!(gray.diff(&other, f64::NAN).is_err());
+}
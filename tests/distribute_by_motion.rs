@@ -0,0 +1,38 @@
+use interpol::{ColorType, Image, Interpolator};
+
+fn gray(value: u8) -> Image {
+    Image::new_from_parts(vec![value; 4], ColorType::Gray, 2, 2).unwrap()
+}
+
+fn frame_counts(interpolator: &Interpolator) -> Vec<usize> {
+    interpolator
+        .segments()
+        .map(|segment| segment.steps)
+        .collect()
+}
+
+#[test]
+fn frames_follow_the_change_between_images() {
+    let images = vec![gray(0), gray(0), gray(64), gray(255)];
+    let interpolator = Interpolator::new(images, 10)
+        .unwrap()
+        .distribute_by_motion()
+        .unwrap();
+    let counts = frame_counts(&interpolator);
+    assert_eq!(counts.iter().sum::<usize>(), 30);
+    // the transition without any change still keeps a frame
+    assert_eq!(counts[0], 1);
+    assert!(counts[2] > counts[1], "{:?}", counts);
+    assert_eq!(counts, vec![1, 8, 21]);
+}
+
+#[test]
+fn identical_images_share_the_frames_evenly() {
+    let interpolator = Interpolator::new(vec![gray(7), gray(7), gray(7)], 5)
+        .unwrap()
+        .frames_between(vec![1, 9])
+        .unwrap()
+        .distribute_by_motion()
+        .unwrap();
+    assert_eq!(frame_counts(&interpolator), vec![5, 5]);
+}
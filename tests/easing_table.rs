@@ -0,0 +1,38 @@
+use std::{env, fs};
+
+use interpol::EasingTable;
+
+#[test]
+fn progress_between_samples_follows_a_straight_line() {
+    let table = EasingTable::new(vec![0.0, 0.1, 0.5, 1.0], false).unwrap();
+    assert_eq!(table.apply(0.0), 0.0);
+    assert_eq!(table.apply(1.0), 1.0);
+    assert!((table.apply(0.5) - 0.3).abs() < 1e-12);
+    assert!((table.apply(5.0 / 6.0) - 0.75).abs() < 1e-12);
+}
+
+#[test]
+fn curves_which_turn_back_must_be_allowed() {
+    assert!(EasingTable::new(vec![0.0, 0.6, 0.4, 1.0], false).is_err());
+    let rewind = EasingTable::new(vec![0.0, 0.6, 0.4, 1.0], true).unwrap();
+    assert!((rewind.apply(0.5) - 0.5).abs() < 1e-12);
+    assert!(EasingTable::new(vec![0.5], true).is_err());
+}
+
+#[test]
+fn curves_load_from_files_of_lines() {
+    let path = env::temp_dir().join(format!("interpol-curve-{}.txt", std::process::id()));
+    fs::write(&path, "0\n 0.25\n\n1\n").unwrap();
+    let table = EasingTable::from_path(&path, false).unwrap();
+    assert_eq!(
+        table,
+        EasingTable::new(vec![0.0, 0.25, 1.0], false).unwrap()
+    );
+
+    fs::write(&path, "0\nhalf\n1\n").unwrap();
+    let error = EasingTable::from_path(&path, false)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("Line 2"), "{}", error);
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,73 @@
+use std::{env, fs, io};
+
+use interpol::{ColorType, Image, ImageInfo, InterpolError, Interpolator};
+
+fn solid(color_type: ColorType, width: u32) -> Image {
+    Image::new_from_parts(
+        vec![0u8; width as usize * 2 * color_type.channels()],
+        color_type,
+        width,
+        2,
+    )
+    .unwrap()
+}
+
+#[test]
+fn missing_files_fail_to_open() {
+    let path = env::temp_dir().join("interpol-does-not-exist.png");
+    let error = Image::new_from_path(&path).unwrap_err();
+    match error.downcast_ref::<InterpolError>() {
+        Some(InterpolError::Open { path: p, source }) => {
+            assert_eq!(p, &path);
+            assert_eq!(source.kind(), io::ErrorKind::NotFound);
+        }
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn mismatched_images_carry_both_formats() {
+    let error = Interpolator::new(vec![solid(ColorType::Rgb, 2), solid(ColorType::Rgba, 3)], 5)
+        .unwrap_err();
+    match error.downcast_ref::<InterpolError>() {
+        Some(InterpolError::Mismatch {
+            found, expected, ..
+        }) => {
+            assert_eq!((found.width, found.color_type), (3, ColorType::Rgba));
+            assert_eq!((expected.width, expected.color_type), (2, ColorType::Rgb));
+        }
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn broken_pngs_are_truncated_or_fail_to_decode() {
+    let png = fs::read("tests/fixtures/start.png").unwrap();
+    let dir = env::temp_dir().join(format!("interpol-errors-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let truncated = dir.join("truncated.png");
+    fs::write(&truncated, &png[..png.len() / 2]).unwrap();
+    let error = Image::new_from_path(&truncated).unwrap_err();
+    assert!(
+        matches!(
+            error.downcast_ref::<InterpolError>(),
+            Some(InterpolError::Truncated { path, .. }) if *path == truncated
+        ),
+        "{:#}",
+        error
+    );
+
+    let garbage = dir.join("garbage.png");
+    fs::write(&garbage, b"not an image at all").unwrap();
+    let error = ImageInfo::from_path(&garbage).unwrap_err();
+    assert!(
+        matches!(
+            error.downcast_ref::<InterpolError>(),
+            Some(InterpolError::Decode { path, .. }) if *path == garbage
+        ),
+        "{:#}",
+        error
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
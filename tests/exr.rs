@@ -0,0 +1,84 @@
+#![cfg(feature = "exr")]
+
+use std::{env, path::PathBuf};
+
+use interpol::{
+    BitDepth, BlendMode, ColorSpace, ColorType, Image, ImageInfo, Interpolator, Samples,
+};
+
+fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("interpol-{}-{}", std::process::id(), name))
+}
+
+#[test]
+fn float_images_round_trip_through_exrs() {
+    let path = temp_path("hdr.exr");
+    // values past 1.0 and below 0.0 are kept as they are
+    let data = vec![0.0f32, 0.5, 1.0, 2.5, 16.0, -0.25];
+    let image = Image::new_from_parts(data, ColorType::Rgb, 2, 1).unwrap();
+    image.save(&path).unwrap();
+
+    let info = ImageInfo::from_path(&path).unwrap();
+    assert_eq!(
+        (info.color_type, info.bit_depth),
+        (ColorType::Rgb, BitDepth::Float)
+    );
+    assert_eq!(Image::new_from_path(&path).unwrap(), image);
+
+    // integer images are scaled so their brightest value is 1.0, and gray spread over RGB
+    let gray = Image::new_from_parts(vec![0u8, 51, 255, 102], ColorType::GrayAlpha, 2, 1);
+    gray.unwrap().save(&path).unwrap();
+    assert_eq!(
+        Image::new_from_path(&path).unwrap().data(),
+        &Samples::Float(vec![0.0, 0.0, 0.0, 0.2, 1.0, 1.0, 1.0, 0.4])
+    );
+}
+
+#[test]
+fn float_frames_are_blended_without_clipping() {
+    let rgb = |v: f32| Image::new_from_parts(vec![v; 12], ColorType::Rgb, 2, 2).unwrap();
+    let interpolator = Interpolator::new(vec![rgb(0.0), rgb(4.0)], 4).unwrap();
+    assert_eq!(interpolator.frame_info().bit_depth, BitDepth::Float);
+    let frame = |n| interpolator.frame_at(n).unwrap().unwrap().data().clone();
+    assert_eq!(frame(2), Samples::Float(vec![2.0; 12]));
+    assert_eq!(frame(4), Samples::Float(vec![4.0; 12]));
+
+    // 8 bit frames of them are clipped as late as possible
+    let interpolator = Interpolator::new(vec![rgb(0.0), rgb(4.0)], 4)
+        .unwrap()
+        .output_bit_depth(BitDepth::Eight);
+    assert_eq!(
+        interpolator.frame_at(2).unwrap().unwrap().data(),
+        &Samples::Eight(vec![255; 12])
+    );
+}
+
+#[test]
+fn float_frames_keep_values_past_one_in_every_blend() {
+    let rgb = |v: f32| Image::new_from_parts(vec![v; 12], ColorType::Rgb, 2, 2).unwrap();
+    let interpolator = || Interpolator::new(vec![rgb(0.0), rgb(4.0)], 4).unwrap();
+    let values = |interpolator: Interpolator| {
+        let frame = interpolator.frame_at(2).unwrap().unwrap();
+        match frame.data() {
+            Samples::Float(data) => data.clone(),
+            data => panic!("expected a float frame, got {:?}", data),
+        }
+    };
+
+    // the value half way between black and 4.0, without the saturation clamped
+    let hsv = values(interpolator().color_space(ColorSpace::Hsv));
+    assert_eq!(hsv, vec![2.0; 12]);
+    // black plus 4.0 composited half way
+    let add = values(interpolator().blend_mode(BlendMode::Add));
+    assert_eq!(add, vec![3.0; 12]);
+    for interpolator in [
+        interpolator().color_space(ColorSpace::Oklab),
+        interpolator()
+            .color_space(ColorSpace::Oklab)
+            .motion_blur(2)
+            .unwrap(),
+    ] {
+        let oklab = values(interpolator);
+        assert!(oklab.iter().all(|&v| v > 1.0), "{:?}", oklab);
+    }
+}
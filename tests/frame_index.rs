@@ -0,0 +1,98 @@
+use std::{env, fs};
+
+use interpol::{ColorType, Image, Interpolator};
+
+fn solid(value: u8) -> Image {
+    Image::new_from_parts(vec![value; 2 * 2 * 3], ColorType::Rgb, 2, 2).unwrap()
+}
+
+fn interpolator() -> Interpolator {
+    Interpolator::new(vec![solid(0), solid(100), solid(250)], 4)
+        .unwrap()
+        .frames_between(vec![3, 5])
+        .unwrap()
+        .ping_pong(true)
+}
+
+#[test]
+fn frame_at_matches_iteration() {
+    let interpolator = interpolator();
+    let frames: Vec<_> = interpolator.clone().collect::<Result<_, _>>().unwrap();
+
+    assert_eq!(frames.len(), interpolator.total_frames());
+    assert_eq!(frames.len(), 2 * (3 + 5));
+    for (n, frame) in frames.iter().enumerate() {
+        assert_eq!(&interpolator.frame_at(n).unwrap().unwrap(), frame);
+    }
+    assert!(interpolator.frame_at(frames.len()).is_none());
+}
+
+#[test]
+fn render_all_matches_iteration() {
+    let interpolator = interpolator();
+    let frames: Vec<_> = interpolator.clone().collect::<Result<_, _>>().unwrap();
+    assert_eq!(interpolator.render_all().unwrap(), frames);
+    assert_eq!(interpolator.render_all_par().unwrap(), frames);
+    // rendering everything leaves the iterator where it was
+    assert_eq!(interpolator.len(), frames.len());
+}
+
+#[test]
+fn render_all_reports_images_which_fail_to_load() {
+    let dir = env::temp_dir().join(format!("interpol-render-all-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let paths = vec![dir.join("start.png"), dir.join("end.png")];
+    for (path, value) in paths.iter().zip([0, 100]) {
+        solid(value).save(path).unwrap();
+    }
+    let interpolator = Interpolator::from_paths(paths.clone(), 2).unwrap();
+    fs::remove_file(&paths[1]).unwrap();
+
+    assert!(interpolator.render_all().is_err());
+    assert!(interpolator.render_all_par().is_err());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn nth_skips_to_the_absolute_frame() {
+    let mut frames = interpolator();
+    let expected = frames.frame_at(9).unwrap().unwrap();
+
+    assert_eq!(frames.nth(9).unwrap().unwrap(), expected);
+    assert_eq!(frames.len(), frames.total_frames() - 10);
+    assert!(frames.nth(100).is_none());
+}
+
+#[test]
+fn duplicates_match_the_frames_they_copy() {
+    let interpolator = interpolator().ping_pong(false).hold(2);
+    let frames: Vec<_> = interpolator.clone().collect::<Result<_, _>>().unwrap();
+
+    assert_eq!(frames.len(), 3 + 5 + 1 + 3 * 2);
+    for (n, frame) in frames.iter().enumerate() {
+        if let Some(m) = interpolator.duplicate_of(n) {
+            assert!(m < n);
+            assert_eq!(&frames[m], frame);
+        }
+    }
+    assert_eq!(frames.last(), Some(&solid(250)));
+}
+
+#[test]
+fn progress_is_reported_in_frame_order() {
+    let interpolator = interpolator();
+    let total = interpolator.total_frames();
+    let mut reports = Vec::new();
+    interpolator
+        .par_for_each_frame_with_progress(
+            |n| n % 3 != 0,
+            |_, _| Ok(()),
+            |done, total| reports.push((done, total)),
+        )
+        .unwrap();
+
+    assert_eq!(
+        reports,
+        (1..=total).map(|done| (done, total)).collect::<Vec<_>>()
+    );
+}
@@ -0,0 +1,64 @@
+#![cfg(feature = "gif")]
+
+use std::{env, fs};
+
+use interpol::{save_gif_with_palette, ColorType, Image, Palette, Samples};
+
+fn pixels(colors: &[[u8; 3]]) -> Image {
+    let data = colors.iter().flatten().copied().collect::<Vec<_>>();
+    Image::new_from_parts(data, ColorType::Rgb, colors.len() as u32, 1).unwrap()
+}
+
+#[test]
+fn median_cut_keeps_few_colors_exactly() {
+    let colors = [[255, 0, 0], [0, 255, 0], [0, 0, 255], [10, 20, 30]];
+    let palette = Palette::median_cut(&[pixels(&colors[..2]), pixels(&colors[2..])]).unwrap();
+    let mut found = palette.colors().to_vec();
+    found.sort();
+    let mut expected = colors.to_vec();
+    expected.sort();
+    assert_eq!(found, expected);
+}
+
+#[test]
+fn median_cut_limits_the_colors() {
+    let colors: Vec<_> = (0..=255u8).flat_map(|r| [[r, 0, 0], [r, 255, 0]]).collect();
+    let palette = Palette::median_cut(&[pixels(&colors)]).unwrap();
+    assert_eq!(palette.colors().len(), 256);
+
+    let rgba = Image::new_from_parts(vec![0u8; 600 * 4], ColorType::Rgba, 600, 1).unwrap();
+    // fully transparent frames have nothing to match, but still get a color
+    assert_eq!(Palette::median_cut(&[rgba]).unwrap().colors(), &[[0, 0, 0]]);
+}
+
+#[test]
+fn gifs_use_the_nearest_colors_of_a_fixed_palette() {
+    let dir = env::temp_dir().join(format!("interpol-gif-palette-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let palette_path = dir.join("palette.txt");
+    fs::write(&palette_path, "#000000\n\ncolor:255,255,255\n").unwrap();
+    let palette = Palette::from_path(&palette_path).unwrap();
+    assert_eq!(palette.colors(), &[[0, 0, 0], [255, 255, 255]]);
+
+    let gif = dir.join("fade.gif");
+    let frames = [pixels(&[[20, 20, 20], [200, 200, 200]])];
+    save_gif_with_palette(&frames, &gif, 4, &palette).unwrap();
+    let decoded = Image::new_from_path(&gif).unwrap();
+    assert_eq!(
+        decoded.data(),
+        &Samples::Eight(vec![0, 0, 0, 255, 255, 255, 255, 255])
+    );
+
+    fs::write(&palette_path, "#000000\nnot a color\n").unwrap();
+    let error = Palette::from_path(&palette_path).unwrap_err().to_string();
+    assert!(error.contains("Line 2"), "{}", error);
+
+    // frames with alpha need a spare entry for transparency
+    let full = Palette::new((0..=255u8).map(|v| [v; 3]).collect()).unwrap();
+    let rgba = Image::new_from_parts(vec![0u8; 4], ColorType::Rgba, 1, 1).unwrap();
+    assert!(save_gif_with_palette(&[rgba], &gif, 4, &full).is_err());
+    assert!(Palette::new(Vec::new()).is_err());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
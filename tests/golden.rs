@@ -0,0 +1,51 @@
+//! Pins the exact output of small interpolations against golden files in `tests/fixtures`,
+//! so changes to rounding and the like show up as failures rather than slipping through.
+//!
+//! After a deliberate change to the output the golden files can be rewritten by running
+//! the tests with `INTERPOL_BLESS=1` set, and the differences checked before committing them.
+
+use std::{env, fs, path::Path};
+
+use interpol::{Image, Interpolator};
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+/// Interpolates between the fixtures `start` and `end` in memory,
+/// giving the channel values of every frame one after the other
+fn render(start: &str, end: &str, steps: usize) -> Vec<u8> {
+    let fixture = |name| Image::new_from_path(Path::new(FIXTURES).join(name)).unwrap();
+    let mut raw = Vec::new();
+    for frame in Interpolator::new(vec![fixture(start), fixture(end)], steps).unwrap() {
+        frame.unwrap().write_raw(&mut raw).unwrap();
+    }
+    raw
+}
+
+/// Compares `output` with the golden file `name`, or replaces the golden file when blessing
+fn check_golden(name: &str, output: &[u8]) {
+    let path = Path::new(FIXTURES).join(name);
+    if env::var_os("INTERPOL_BLESS").is_some() {
+        fs::write(&path, output).unwrap();
+        return;
+    }
+
+    let golden = fs::read(&path).unwrap_or_else(|e| {
+        panic!(
+            "Failed to read the golden file {:?}, set INTERPOL_BLESS=1 to create it: {}",
+            path, e
+        )
+    });
+    assert_eq!(output.len(), golden.len(), "{} has changed length", name);
+    if let Some(i) = output.iter().zip(&golden).position(|(o, g)| o != g) {
+        panic!(
+            "{} differs first at byte {}, which is {} rather than {}",
+            name, i, output[i], golden[i]
+        );
+    }
+}
+
+#[test]
+fn linear_rgb_matches_the_golden_output() {
+    // seven steps puts the weights on sevenths, which never land on whole values
+    check_golden("linear_rgb.golden", &render("start.png", "end.png", 7));
+}
@@ -0,0 +1,156 @@
+use std::{env, fs, path::PathBuf};
+
+use interpol::{BitDepth, ColorType, Image, ImageInfo, Samples};
+
+fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("interpol-{}-{}", std::process::id(), name))
+}
+
+/// A BMP with a 40 byte header, its `palette` of BGRX colors and then its `rows`,
+/// each already padded to a multiple of 4 bytes
+fn bmp(
+    width: i32,
+    height: i32,
+    bits: u16,
+    compression: u32,
+    palette: &[u8],
+    rows: &[u8],
+) -> Vec<u8> {
+    let pixels_start = 14 + 40 + palette.len() as u32;
+    let mut bytes = b"BM".to_vec();
+    bytes.extend_from_slice(&(pixels_start + rows.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&[0; 4]);
+    bytes.extend_from_slice(&pixels_start.to_le_bytes());
+    bytes.extend_from_slice(&40u32.to_le_bytes());
+    bytes.extend_from_slice(&width.to_le_bytes());
+    bytes.extend_from_slice(&height.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&bits.to_le_bytes());
+    bytes.extend_from_slice(&compression.to_le_bytes());
+    bytes.extend_from_slice(&[0; 20]);
+    bytes.extend_from_slice(palette);
+    bytes.extend_from_slice(rows);
+    bytes
+}
+
+#[test]
+fn bmps_decode_bottom_up_with_padded_rows() {
+    let path = temp_path("rgb.bmp");
+    // the bottom row comes first, each pixel blue, green, red
+    let rows = [
+        [30, 20, 10, 60, 50, 40, 0, 0],
+        [90, 80, 70, 255, 255, 255, 0, 0],
+    ]
+    .concat();
+    fs::write(&path, bmp(2, 2, 24, 0, &[], &rows)).unwrap();
+
+    let image = Image::new_from_path(&path).unwrap();
+    assert_eq!(ImageInfo::from_path(&path).unwrap(), image.info());
+    assert_eq!(image.color_type(), ColorType::Rgb);
+    assert_eq!(
+        image.data(),
+        &Samples::Eight(vec![70, 80, 90, 255, 255, 255, 10, 20, 30, 40, 50, 60])
+    );
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn palette_bmps_are_expanded_to_rgb() {
+    let path = temp_path("palette.bmp");
+    let palette = [0, 0, 255, 0, 0, 255, 0, 0];
+    fs::write(&path, bmp(3, -1, 8, 0, &palette, &[1, 0, 1, 0])).unwrap();
+
+    let image = Image::new_from_path(&path).unwrap();
+    assert_eq!(
+        image.data(),
+        &Samples::Eight(vec![0, 255, 0, 255, 0, 0, 0, 255, 0])
+    );
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn low_bit_grayscale_pngs_are_expanded_to_8_bits() {
+    // rows of 4 pixels packed into bytes, each scaled so its brightest value is 255
+    let images: [(png::BitDepth, &[u8], [u8; 4]); 3] = [
+        (png::BitDepth::One, &[0b1010_0000], [255, 0, 255, 0]),
+        (png::BitDepth::Two, &[0b00_01_10_11], [0, 85, 170, 255]),
+        (png::BitDepth::Four, &[0x0f, 0x50], [0, 255, 85, 0]),
+    ];
+    for (depth, row, expected) in images {
+        let path = temp_path(&format!("gray{:?}.png", depth));
+        let mut encoder = png::Encoder::new(fs::File::create(&path).unwrap(), 4, 1);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(depth);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(row).unwrap();
+        drop(writer);
+
+        let info = ImageInfo::from_path(&path).unwrap();
+        assert_eq!(
+            (info.color_type, info.bit_depth),
+            (ColorType::Gray, BitDepth::Eight)
+        );
+        let image = Image::new_from_path(&path).unwrap();
+        assert_eq!(
+            image.data(),
+            &Samples::Eight(expected.to_vec()),
+            "{:?}",
+            depth
+        );
+        fs::remove_file(&path).unwrap();
+    }
+}
+
+#[test]
+fn compressed_bmps_are_reported() {
+    let path = temp_path("rle.bmp");
+    fs::write(&path, bmp(2, 2, 8, 1, &[0; 8], &[0; 8])).unwrap();
+
+    let error = Image::new_from_path(&path).unwrap_err().to_string();
+    assert!(error.contains("Unsupported compression RLE8"), "{}", error);
+    fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "tiff")]
+#[test]
+fn tiff_pages_become_keyframes() {
+    use tiff::encoder::{colortype, TiffEncoder};
+
+    let path = temp_path("pages.tiff");
+    let mut file = fs::File::create(&path).unwrap();
+    let mut encoder = TiffEncoder::new(&mut file).unwrap();
+    let pages = [[0u16, 1000, 2000, 3000], [65535, 60000, 5, 6]];
+    for page in &pages {
+        encoder
+            .write_image::<colortype::Gray16>(2, 2, page)
+            .unwrap();
+    }
+    drop(file);
+
+    assert_eq!(
+        Image::new_from_path(&path).unwrap().data(),
+        &Samples::Sixteen(pages[0].to_vec())
+    );
+    let frames = Image::frames_from_path(&path).unwrap();
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[1].data(), &Samples::Sixteen(pages[1].to_vec()));
+    fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "tiff")]
+#[test]
+fn cmyk_tiffs_are_reported() {
+    use tiff::encoder::{colortype, TiffEncoder};
+
+    let path = temp_path("cmyk.tiff");
+    let mut file = fs::File::create(&path).unwrap();
+    TiffEncoder::new(&mut file)
+        .unwrap()
+        .write_image::<colortype::CMYK8>(1, 1, &[1, 2, 3, 4])
+        .unwrap();
+    drop(file);
+
+    let error = Image::new_from_path(&path).unwrap_err().to_string();
+    assert!(error.contains("CMYK"), "{}", error);
+    fs::remove_file(&path).unwrap();
+}
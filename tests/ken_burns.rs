@@ -0,0 +1,75 @@
+use interpol::{ColorType, Image, Interpolator, KenBurns, Region, Samples};
+
+/// A 4x2 grayscale image with the values 0, 10, 20 ... along each row
+fn gradient() -> Image {
+    let data: Vec<u8> = (0..8).map(|i| (i % 4) * 10 + (i / 4) * 100).collect();
+    Image::new_from_parts(data, ColorType::Gray, 4, 2).unwrap()
+}
+
+fn region(s: &str) -> Region {
+    s.parse().unwrap()
+}
+
+#[test]
+fn pans_from_the_start_rectangle_to_the_end() {
+    let ken_burns = KenBurns {
+        start: region("0,0,2,2"),
+        end: region("2,0,2,2"),
+        width: 2,
+        height: 2,
+    };
+    let interpolator = Interpolator::new(vec![gradient(), gradient()], 2)
+        .unwrap()
+        .hold(1)
+        .ken_burns(ken_burns)
+        .unwrap();
+    let info = interpolator.frame_info();
+    assert_eq!((info.width, info.height), (2, 2));
+    // the camera keeps moving over frames which would otherwise be copies
+    assert_eq!(interpolator.duplicate_of(1), None);
+
+    let frames: Vec<_> = interpolator
+        .map(|frame| frame.unwrap().data().clone())
+        .collect();
+    assert_eq!(
+        frames,
+        [
+            Samples::Eight(vec![0, 10, 100, 110]),
+            Samples::Eight(vec![5, 15, 105, 115]),
+            Samples::Eight(vec![10, 20, 110, 120]),
+            Samples::Eight(vec![15, 25, 115, 125]),
+            Samples::Eight(vec![20, 30, 120, 130]),
+        ]
+    );
+}
+
+#[test]
+fn zooms_are_scaled_to_the_frame_size() {
+    let ken_burns = KenBurns {
+        start: region("0,0,4,2"),
+        end: region("1,0,2,1"),
+        width: 4,
+        height: 2,
+    };
+    let interpolator = Interpolator::new(vec![gradient(), gradient()], 1)
+        .unwrap()
+        .ken_burns(ken_burns)
+        .unwrap();
+    assert_eq!(interpolator.frame_at(0).unwrap().unwrap(), gradient());
+    // the pixels are sampled between the source pixels, a quarter of the way into the
+    // second row for the bottom of the frame
+    let last = interpolator.frame_at(1).unwrap().unwrap();
+    assert_eq!(
+        last.data(),
+        &Samples::Eight(vec![8, 13, 18, 23, 33, 38, 43, 48])
+    );
+
+    let outside = KenBurns {
+        end: region("3,0,2,2"),
+        ..ken_burns
+    };
+    assert!(Interpolator::new(vec![gradient(), gradient()], 1)
+        .unwrap()
+        .ken_burns(outside)
+        .is_err());
+}
@@ -0,0 +1,106 @@
+use std::{env, fs, thread};
+
+use interpol::{ColorType, Image, Interpolator};
+
+#[test]
+fn at_most_two_keyframes_are_resident() {
+    let dir = env::temp_dir().join(format!("interpol-lazy-loading-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let paths: Vec<_> = (0..5u8)
+        .map(|i| {
+            let path = dir.join(format!("keyframe_{}.png", i));
+            Image::new_from_parts(vec![i * 50; 2 * 2 * 3], ColorType::Rgb, 2, 2)
+                .unwrap()
+                .save(&path)
+                .unwrap();
+            path
+        })
+        .collect();
+
+    let mut interpolator = Interpolator::from_paths(paths, 3).unwrap();
+    assert_eq!(interpolator.resident_keyframes(), 0);
+
+    let mut frames = 0;
+    while let Some(frame) = interpolator.next() {
+        frame.unwrap();
+        frames += 1;
+        assert!(interpolator.resident_keyframes() <= 2);
+    }
+    assert_eq!(frames, 4 * 3 + 1);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn preloading_decodes_every_keyframe_in_order() {
+    let dir = env::temp_dir().join(format!("interpol-preload-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let paths: Vec<_> = (0..6u8)
+        .map(|i| {
+            let path = dir.join(format!("keyframe_{}.png", i));
+            Image::new_from_parts(vec![i * 40; 2 * 2 * 3], ColorType::Rgb, 2, 2)
+                .unwrap()
+                .save(&path)
+                .unwrap();
+            path
+        })
+        .collect();
+
+    let interpolator = Interpolator::from_paths(paths.clone(), 2)
+        .unwrap()
+        .preload()
+        .unwrap();
+    assert_eq!(interpolator.resident_keyframes(), 6);
+    // every other frame lands on a keyframe, which must still be in the order given
+    for i in 0..6 {
+        let frame = interpolator.frame_at(i * 2).unwrap().unwrap();
+        assert_eq!(frame.data().clone(), vec![i as u8 * 40; 2 * 2 * 3].into());
+    }
+
+    // with several broken images the first one is reported
+    for i in [2, 4] {
+        fs::write(&paths[i], b"not a png").unwrap();
+    }
+    let error = Interpolator::from_paths(paths.clone(), 2)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("keyframe_2.png"), "{}", error);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn keyframes_can_be_fetched_from_several_threads_at_once() {
+    let dir = env::temp_dir().join(format!("interpol-lazy-threads-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let paths: Vec<_> = (0..4u8)
+        .map(|i| {
+            let path = dir.join(format!("keyframe_{}.png", i));
+            Image::new_from_parts(vec![i * 60; 8 * 8], ColorType::Gray, 8, 8)
+                .unwrap()
+                .save(&path)
+                .unwrap();
+            path
+        })
+        .collect();
+
+    // frames from every transition are asked for at once, more than the cache can hold
+    let interpolator = Interpolator::from_paths(paths, 4).unwrap();
+    let expected = interpolator.render_all().unwrap();
+    thread::scope(|s| {
+        for start in 0..4 {
+            let (interpolator, expected) = (&interpolator, &expected);
+            s.spawn(move || {
+                for n in (start..expected.len()).step_by(4) {
+                    assert_eq!(interpolator.frame_at(n).unwrap().unwrap(), expected[n]);
+                }
+            });
+        }
+    });
+    assert!(interpolator.resident_keyframes() <= 2);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,42 @@
+use interpol::{ColorType, Image, Interpolation, Interpolator, Samples};
+
+const WIDTH: usize = 192;
+const HEIGHT: usize = 128;
+
+/// A textured square on a flat background, with its left edge at `left`
+fn square(left: usize) -> Image {
+    let data: Vec<u8> = (0..WIDTH * HEIGHT)
+        .map(|i| {
+            let (x, y) = (i % WIDTH, i / WIDTH);
+            if (left..left + 96).contains(&x) && (16..112).contains(&y) {
+                ((x - left) * 37 + y * 91) as u8 ^ ((y * 13) as u8)
+            } else {
+                20
+            }
+        })
+        .collect();
+    Image::new_from_parts(data, ColorType::Gray, WIDTH as u32, HEIGHT as u32).unwrap()
+}
+
+fn pixels(image: &Image) -> &[u8] {
+    match image.data() {
+        Samples::Eight(data) => data,
+        Samples::Sixteen(_) | Samples::Float(_) => unreachable!(),
+    }
+}
+
+#[test]
+fn morphing_follows_the_motion() {
+    let interpolator = Interpolator::new(vec![square(16), square(40)], 2)
+        .unwrap()
+        .interpolation(Interpolation::Morph);
+    let middle = interpolator.frame_at(1).unwrap().unwrap();
+    let expected = square(28);
+
+    // away from the edges of the square the block matching is exact
+    let (middle, expected) = (pixels(&middle), pixels(&expected));
+    for y in 40..88 {
+        let row = y * WIDTH;
+        assert_eq!(middle[row + 48..row + 96], expected[row + 48..row + 96]);
+    }
+}
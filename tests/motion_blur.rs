@@ -0,0 +1,79 @@
+use interpol::{ColorSpace, ColorType, Easing, Image, Interpolator, Samples};
+
+fn gray(value: u8) -> Image {
+    Image::new_from_parts(vec![value; 4], ColorType::Gray, 2, 2).unwrap()
+}
+
+fn values(interpolator: Interpolator) -> Vec<u8> {
+    interpolator
+        .map(|frame| match frame.unwrap().data() {
+            Samples::Eight(data) => data[0],
+            Samples::Sixteen(_) | Samples::Float(_) => panic!("expected an 8 bit frame"),
+        })
+        .collect()
+}
+
+#[test]
+fn frames_average_the_blends_up_to_the_next_frame() {
+    let interpolator = || Interpolator::new(vec![gray(0), gray(200)], 2).unwrap();
+    assert_eq!(values(interpolator()), [0, 100, 200]);
+    assert_eq!(
+        values(interpolator().motion_blur(1).unwrap()),
+        [0, 100, 200]
+    );
+    // 0 and 50, then 100 and 150, with the last image left sharp
+    assert_eq!(
+        values(interpolator().motion_blur(2).unwrap()),
+        [25, 125, 200]
+    );
+    // every sample is eased, 0 and 12.5 then 50 and 112.5
+    assert_eq!(
+        values(
+            interpolator()
+                .easing(Easing::EaseInQuad)
+                .motion_blur(2)
+                .unwrap()
+        ),
+        [6, 81, 200]
+    );
+    assert!(interpolator().motion_blur(0).is_err());
+}
+
+#[test]
+fn samples_are_averaged_in_linear_light_when_gamma_correcting() {
+    let interpolator = || Interpolator::new(vec![gray(0), gray(255)], 1).unwrap();
+    let plain = values(interpolator().motion_blur(2).unwrap());
+    let linear = values(interpolator().gamma_correct(true).motion_blur(2).unwrap());
+    // black and the half way blend average to 64 in sRGB, but far brighter in linear light
+    assert_eq!(plain[0], 64);
+    assert!(linear[0] > plain[0], "{:?}", linear);
+}
+
+#[test]
+fn samples_are_only_rounded_once() {
+    let gray = |value: u16| Image::new_from_parts(vec![value], ColorType::Gray, 1, 1).unwrap();
+    let interpolator = Interpolator::new(vec![gray(3), gray(0)], 1)
+        .unwrap()
+        .motion_blur(4)
+        .unwrap();
+    // the samples are 3, 2, 2 and 1, whose running mean would round up at every step to 3
+    assert_eq!(
+        interpolator.frame_at(0).unwrap().unwrap().data(),
+        &Samples::Sixteen(vec![2])
+    );
+}
+
+#[test]
+fn hues_are_averaged_around_the_color_wheel() {
+    let rgb = |color: [u8; 3]| Image::new_from_parts(color.to_vec(), ColorType::Rgb, 1, 1).unwrap();
+    let interpolator = Interpolator::new(vec![rgb([255, 0, 0]), rgb([0, 255, 0])], 1)
+        .unwrap()
+        .color_space(ColorSpace::Hsv)
+        .motion_blur(2)
+        .unwrap();
+    // red and the yellow half way to green average to orange rather than a duller color
+    assert_eq!(
+        interpolator.frame_at(0).unwrap().unwrap().data(),
+        &Samples::Eight(vec![255, 128, 0])
+    );
+}
@@ -0,0 +1,61 @@
+use std::{borrow::Cow, env, fs, fs::File, io::BufWriter, path::Path};
+
+use interpol::{Image, ImageInfo, Samples};
+
+/// The `eXIf` payload of a big-endian TIFF header with a single orientation entry
+fn exif_orientation(orientation: u16) -> Vec<u8> {
+    let mut exif = b"MM\0\x2a\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01".to_vec();
+    exif.extend_from_slice(&orientation.to_be_bytes());
+    exif.extend_from_slice(&[0; 6]);
+    exif
+}
+
+/// Saves a 3x2 grayscale PNG holding the values 0 to 5 along its rows
+fn save_tagged(path: &Path, orientation: u16) {
+    let mut info = png::Info::with_size(3, 2);
+    info.color_type = png::ColorType::Grayscale;
+    info.bit_depth = png::BitDepth::Eight;
+    info.exif_metadata = Some(Cow::Owned(exif_orientation(orientation)));
+
+    let file = BufWriter::new(File::create(path).unwrap());
+    let mut writer = png::Encoder::with_info(file, info)
+        .unwrap()
+        .write_header()
+        .unwrap();
+    writer.write_image_data(&[0, 1, 2, 3, 4, 5]).unwrap();
+}
+
+#[test]
+fn images_are_turned_upright_on_load() {
+    let dir = env::temp_dir().join(format!("interpol-orientation-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let cases: [(u16, (u32, u32), [u8; 6]); 8] = [
+        (1, (3, 2), [0, 1, 2, 3, 4, 5]),
+        (2, (3, 2), [2, 1, 0, 5, 4, 3]),
+        (3, (3, 2), [5, 4, 3, 2, 1, 0]),
+        (4, (3, 2), [3, 4, 5, 0, 1, 2]),
+        (5, (2, 3), [0, 3, 1, 4, 2, 5]),
+        (6, (2, 3), [3, 0, 4, 1, 5, 2]),
+        (7, (2, 3), [5, 2, 4, 1, 3, 0]),
+        (8, (2, 3), [2, 5, 1, 4, 0, 3]),
+    ];
+    for (orientation, (width, height), expected) in cases {
+        let path = dir.join(format!("orientation-{}.png", orientation));
+        save_tagged(&path, orientation);
+
+        let info = ImageInfo::from_path(&path).unwrap();
+        assert_eq!((info.width, info.height), (width, height));
+
+        let image = Image::new_from_path(&path).unwrap();
+        assert_eq!((image.width(), image.height()), (width, height));
+        assert_eq!(
+            image.data(),
+            &Samples::Eight(expected.to_vec()),
+            "orientation {}",
+            orientation
+        );
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
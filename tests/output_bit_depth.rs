@@ -0,0 +1,79 @@
+use interpol::{BitDepth, ColorType, Dither, Image, Interpolator, Samples};
+
+fn gray<D: Into<Samples>>(data: D) -> Image {
+    Image::new_from_parts(data, ColorType::Gray, 8, 8).unwrap()
+}
+
+fn frame(interpolator: &Interpolator, n: usize) -> Samples {
+    interpolator.frame_at(n).unwrap().unwrap().data().clone()
+}
+
+#[test]
+fn eight_bit_images_keep_the_precision_of_the_blend() {
+    let interpolator = Interpolator::new(vec![gray(vec![0u8; 64]), gray(vec![1u8; 64])], 4)
+        .unwrap()
+        .output_bit_depth(BitDepth::Sixteen);
+    assert_eq!(interpolator.frame_info().bit_depth, BitDepth::Sixteen);
+    // the keyframes are scaled exactly and the blends land in between them
+    assert_eq!(frame(&interpolator, 0), Samples::Sixteen(vec![0; 64]));
+    assert_eq!(frame(&interpolator, 1), Samples::Sixteen(vec![64; 64]));
+    assert_eq!(frame(&interpolator, 4), Samples::Sixteen(vec![257; 64]));
+}
+
+#[test]
+fn sixteen_bit_images_are_rounded_to_eight_bits() {
+    let interpolator = Interpolator::new(vec![gray(vec![129u16; 64]), gray(vec![65535u16; 64])], 1)
+        .unwrap()
+        .output_bit_depth(BitDepth::Eight);
+    assert_eq!(interpolator.frame_info().bit_depth, BitDepth::Eight);
+    // (129 + 128) / 257 rounds up to 1 rather than truncating to 0
+    assert_eq!(frame(&interpolator, 0), Samples::Eight(vec![1; 64]));
+    assert_eq!(frame(&interpolator, 1), Samples::Eight(vec![255; 64]));
+}
+
+#[test]
+fn dithering_keeps_the_level_between_eight_bit_values() {
+    // a quarter of the way from 10 to 11 in 8 bit terms
+    let level: u16 = 10 * 257 + 64;
+    let images = || vec![gray(vec![level; 64]), gray(vec![level; 64])];
+    let mean = |data: Samples| match data {
+        Samples::Eight(data) => data.iter().map(|&c| c as f64).sum::<f64>() / 64.0,
+        _ => unreachable!(),
+    };
+
+    let rounded = Interpolator::new(images(), 1)
+        .unwrap()
+        .output_bit_depth(BitDepth::Eight);
+    let dithered = Interpolator::new(images(), 1)
+        .unwrap()
+        .output_bit_depth(BitDepth::Eight)
+        .dither(Dither::Ordered);
+    let target = level as f64 / 257.0;
+    assert_eq!(mean(frame(&rounded, 0)), 10.0);
+    assert!((mean(frame(&dithered, 0)) - target).abs() < 0.05);
+}
+
+#[test]
+fn noise_dithering_repeats_for_the_same_seed() {
+    let level: u16 = 10 * 257 + 64;
+    let interpolator = |seed| {
+        Interpolator::new(vec![gray(vec![level; 64]), gray(vec![level + 1; 64])], 2)
+            .unwrap()
+            .output_bit_depth(BitDepth::Eight)
+            .dither(Dither::Noise)
+            .seed(seed)
+    };
+    let frames = |seed| -> Vec<_> { interpolator(seed).map(|f| f.unwrap()).collect() };
+
+    assert_eq!(frames(7), frames(7));
+    assert_ne!(frame(&interpolator(7), 0), frame(&interpolator(8), 0));
+    // only 10s and 11s, about a quarter of them rounded up
+    match frame(&interpolator(0), 0) {
+        Samples::Eight(data) => {
+            assert!(data.iter().all(|&c| c == 10 || c == 11), "{:?}", data);
+            let ups = data.iter().filter(|&&c| c == 11).count();
+            assert!((6..=26).contains(&ups), "{} of 64 rounded up", ups);
+        }
+        other => panic!("{:?}", other),
+    }
+}
@@ -0,0 +1,16 @@
+use std::path::Path;
+
+use interpol::Format;
+
+#[test]
+fn formats_follow_from_extensions() {
+    let format = |path: &str| Format::from_extension(Path::new(path));
+    assert_eq!(format("out/animation.gif"), Some(Format::Gif));
+    assert_eq!(format("animation.APNG"), Some(Format::Apng));
+    assert_eq!(format("clip.webm"), Some(Format::Video));
+    assert_eq!(format("frames.rgb"), Some(Format::Raw));
+    // a PNG could be an APNG or a sprite sheet, and directories have no extension
+    assert_eq!(format("sheet.png"), None);
+    assert_eq!(format("frames"), None);
+    assert_eq!(format("notes.txt"), None);
+}
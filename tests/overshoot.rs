@@ -0,0 +1,62 @@
+use interpol::{smooth, ColorType, Easing, Image, Interpolator, Samples};
+
+#[test]
+fn overshooting_curves_leave_the_unit_range() {
+    for easing in [Easing::BackInOut, Easing::ElasticOut] {
+        assert_eq!(easing.apply(0.0), 0.0);
+        assert!((easing.apply(1.0) - 1.0).abs() < 1e-12);
+        let curve: Vec<_> = (1..100).map(|t| easing.apply(t as f64 / 100.0)).collect();
+        assert!(
+            curve.iter().any(|&mu| mu > 1.0),
+            "{:?} never overshoots",
+            easing
+        );
+    }
+    assert!(Easing::BackInOut.apply(0.1) < 0.0);
+}
+
+#[test]
+fn blends_at_the_ends_are_exactly_the_end_values() {
+    for c1 in 0..=u8::MAX {
+        for c2 in 0..=u8::MAX {
+            assert_eq!(smooth(0.0, c1, c2), c1);
+            assert_eq!(smooth(1.0, c1, c2), c2);
+        }
+    }
+    for (c1, c2) in [(0u16, u16::MAX), (u16::MAX, 1), (12345, 54321)] {
+        assert_eq!(smooth(0.0, c1, c2), c1);
+        assert_eq!(smooth(1.0, c1, c2), c2);
+    }
+}
+
+#[test]
+fn blends_past_the_ends_extrapolate_and_clamp() {
+    assert_eq!(smooth(-0.5, 100u8, 200u8), 50);
+    assert_eq!(smooth(1.5, 100u8, 200u8), 250);
+    assert_eq!(smooth(-2.0, 100u8, 200u8), 0);
+    assert_eq!(smooth(3.0, 100u8, 200u8), 255);
+    assert_eq!(smooth(1.5, 1000u16, 65000u16), u16::MAX);
+}
+
+#[test]
+fn overshooting_frames_clamp_instead_of_wrapping() {
+    // enough values for the vectorised blend of 8 bit images as well as the leftovers
+    let image = |value| Image::new_from_parts(vec![value; 12], ColorType::Gray, 12, 1).unwrap();
+    let frames: Vec<_> = Interpolator::new(vec![image(0u8), image(255)], 20)
+        .unwrap()
+        .easing(Easing::BackInOut)
+        .map(|frame| match frame.unwrap().data() {
+            Samples::Eight(data) => data.clone(),
+            _ => unreachable!(),
+        })
+        .collect();
+
+    // backing away from black and overshooting white both stick at the limits
+    assert_eq!(frames[2], vec![0; 12]);
+    assert_eq!(frames[18], vec![255; 12]);
+    for frame in &frames {
+        assert!(frame.windows(2).all(|w| w[0] == w[1]));
+    }
+    let middle: Vec<_> = frames.iter().map(|frame| frame[0]).collect();
+    assert!(middle.windows(2).all(|w| w[0] <= w[1]), "{:?}", middle);
+}
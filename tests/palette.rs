@@ -0,0 +1,49 @@
+use std::{env, fs, fs::File, io::BufWriter, path::Path};
+
+use interpol::{ColorType, Image, Samples};
+
+const PALETTE: [u8; 9] = [255, 0, 0, 0, 255, 0, 0, 0, 255];
+
+/// Saves a 4x1 image of 2 bit palette indices, optionally with palette transparency
+fn save_indexed(path: &Path, trns: Option<&[u8]>) {
+    let file = BufWriter::new(File::create(path).unwrap());
+    let mut encoder = png::Encoder::new(file, 4, 1);
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(png::BitDepth::Two);
+    encoder.set_palette(&PALETTE[..]);
+    if let Some(trns) = trns {
+        encoder.set_trns(trns);
+    }
+
+    let mut writer = encoder.write_header().unwrap();
+    // the indices 0, 1, 2, 1 packed into a single byte
+    writer.write_image_data(&[0b00_01_10_01]).unwrap();
+}
+
+#[test]
+fn palette_images_are_expanded() {
+    let dir = env::temp_dir().join(format!("interpol-palette-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let opaque = dir.join("opaque.png");
+    save_indexed(&opaque, None);
+    let image = Image::new_from_path(&opaque).unwrap();
+    assert_eq!(image.color_type(), ColorType::Rgb);
+    assert_eq!(
+        image.data(),
+        &Samples::Eight(vec![255, 0, 0, 0, 255, 0, 0, 0, 255, 0, 255, 0])
+    );
+
+    let transparent = dir.join("transparent.png");
+    save_indexed(&transparent, Some(&[0, 128]));
+    let image = Image::new_from_path(&transparent).unwrap();
+    assert_eq!(image.color_type(), ColorType::Rgba);
+    assert_eq!(
+        image.data(),
+        &Samples::Eight(vec![
+            255, 0, 0, 0, 0, 255, 0, 128, 0, 0, 255, 255, 0, 255, 0, 128
+        ])
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
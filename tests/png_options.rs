@@ -0,0 +1,59 @@
+use std::{convert::TryInto, env, fs};
+
+use interpol::{ColorType, Compression, Image, PngFilter, PngOptions};
+
+fn gradient() -> Image {
+    let data: Vec<u16> = (0..64 * 64 * 3).map(|i| (i * 37 % 65536) as u16).collect();
+    Image::new_from_parts(data, ColorType::Rgb, 64, 64).unwrap()
+}
+
+/// The type and length of each chunk of a PNG
+fn chunks(png: &[u8]) -> Vec<(String, usize)> {
+    let mut chunks = Vec::new();
+    let mut rest = &png[8..];
+    while !rest.is_empty() {
+        let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+        chunks.push((String::from_utf8_lossy(&rest[4..8]).into_owned(), len));
+        rest = &rest[12 + len..];
+    }
+    chunks
+}
+
+#[test]
+fn every_setting_keeps_the_pixels() {
+    let image = gradient();
+    let dir = env::temp_dir().join(format!("interpol-png-options-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("frame.png");
+    for compression in Compression::VARIANTS {
+        for filter in PngFilter::VARIANTS {
+            let options = PngOptions {
+                compression: compression.parse().unwrap(),
+                filter: filter.parse().unwrap(),
+                chunk_size: None,
+            };
+            image.save_with(&path, &options).unwrap();
+            assert_eq!(Image::new_from_path(&path).unwrap(), image);
+        }
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn image_data_is_split_into_chunks() {
+    let image = gradient();
+    let options = PngOptions {
+        chunk_size: Some(1000),
+        ..PngOptions::default()
+    };
+    let mut png = Vec::new();
+    image.write_png_with(&mut png, &options).unwrap();
+
+    let idat: Vec<_> = chunks(&png)
+        .into_iter()
+        .filter(|(kind, _)| kind == "IDAT")
+        .map(|(_, len)| len)
+        .collect();
+    assert!(idat.len() > 1, "{:?}", idat);
+    assert!(idat.iter().all(|&len| len <= 1000), "{:?}", idat);
+}
@@ -0,0 +1,48 @@
+use interpol::{smooth, smooth_in, ColorType, Image, Interpolator, Samples};
+
+#[test]
+fn halfway_between_black_and_white_rounds_to_128() {
+    assert_eq!(smooth(0.5, 0u8, 255), 128);
+
+    let rgb = |v: u8| Image::new_from_parts(vec![v; 3], ColorType::Rgb, 1, 1).unwrap();
+    let interpolator = Interpolator::new(vec![rgb(0), rgb(255)], 2).unwrap();
+    assert_eq!(
+        interpolator.frame_at(1).unwrap().unwrap().data(),
+        &Samples::Eight(vec![128; 3])
+    );
+}
+
+#[test]
+fn f32_and_f64_blend_u8_values_identically_apart_from_ties() {
+    // the weights of a transition of 50 frames, as with the default --n-frames
+    const FRAMES: u32 = 50;
+    for frame in 0..=FRAMES {
+        let mu = frame as f64 / FRAMES as f64;
+        for c1 in 0..=u8::MAX {
+            for c2 in 0..=u8::MAX {
+                let blended = smooth_in::<f32, u8>(mu, c1, c2);
+                let wide = smooth_in::<f64, u8>(mu, c1, c2);
+                assert_eq!(smooth(mu, c1, c2), blended);
+
+                // blends landing exactly halfway between two values can round either way
+                // depending on how each type represents the weights
+                let exact = c1 as u32 * (FRAMES - frame) + c2 as u32 * frame;
+                if exact % FRAMES == FRAMES / 2 {
+                    let below = (exact / FRAMES) as u8;
+                    for value in [blended, wide] {
+                        assert!(
+                            value == below || value == below + 1,
+                            "blending {} -> {} by {} gave {}",
+                            c1,
+                            c2,
+                            mu,
+                            value
+                        );
+                    }
+                    continue;
+                }
+                assert_eq!(blended, wide, "blending {} -> {} by {}", c1, c2, mu);
+            }
+        }
+    }
+}
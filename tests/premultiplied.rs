@@ -0,0 +1,35 @@
+use interpol::{ColorType, Image, Interpolator, Samples};
+
+fn midpoint(start: Vec<u8>, end: Vec<u8>, premultiplied: bool) -> Samples {
+    let image = |data| Image::new_from_parts(data, ColorType::Rgba, 1, 1).unwrap();
+    Interpolator::new(vec![image(start), image(end)], 2)
+        .unwrap()
+        .premultiplied(premultiplied)
+        .nth(1)
+        .unwrap()
+        .unwrap()
+        .data()
+        .clone()
+}
+
+#[test]
+fn transparent_colors_dont_darken_the_fade() {
+    // fading in from fully transparent black keeps the color of the image fading in
+    let (transparent, red) = (vec![0, 0, 0, 0], vec![255, 0, 0, 255]);
+    assert_eq!(
+        midpoint(transparent.clone(), red.clone(), false),
+        Samples::Eight(vec![128, 0, 0, 128])
+    );
+    assert_eq!(
+        midpoint(transparent, red, true),
+        Samples::Eight(vec![255, 0, 0, 128])
+    );
+}
+
+#[test]
+fn fully_transparent_blends_stay_transparent() {
+    assert_eq!(
+        midpoint(vec![255, 255, 255, 0], vec![0, 255, 0, 0], true),
+        Samples::Eight(vec![0, 0, 0, 0])
+    );
+}
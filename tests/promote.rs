@@ -0,0 +1,77 @@
+use std::{env, fs};
+
+use interpol::{ColorType, Image, ImageLoader, Interpolator, Promote, Samples};
+
+fn image(data: Vec<u8>, color_type: ColorType) -> Image {
+    let width = (data.len() / color_type.channels()) as u32;
+    Image::new_from_parts(data, color_type, width, 1).unwrap()
+}
+
+#[test]
+fn promotion_only_adds_channels() {
+    let gray = || image(vec![10, 20], ColorType::Gray);
+    let gray_alpha = || image(vec![10, 128], ColorType::GrayAlpha);
+    let rgb = || image(vec![1, 2, 3], ColorType::Rgb);
+
+    let cases = [
+        (
+            gray().promote(Promote::Rgb),
+            ColorType::Rgb,
+            vec![10, 10, 10, 20, 20, 20],
+        ),
+        (
+            gray_alpha().promote(Promote::Rgb),
+            ColorType::Rgba,
+            vec![10, 10, 10, 128],
+        ),
+        (rgb().promote(Promote::Rgb), ColorType::Rgb, vec![1, 2, 3]),
+        (
+            gray().promote(Promote::Rgba),
+            ColorType::Rgba,
+            vec![10, 10, 10, 255, 20, 20, 20, 255],
+        ),
+        (
+            rgb().promote(Promote::Rgba),
+            ColorType::Rgba,
+            vec![1, 2, 3, 255],
+        ),
+    ];
+    for (promoted, color_type, data) in cases {
+        assert_eq!(promoted.color_type(), color_type);
+        assert_eq!(promoted.data(), &Samples::Eight(data));
+    }
+
+    let sixteen = Image::new_from_parts(vec![1000u16], ColorType::Gray, 1, 1).unwrap();
+    assert_eq!(
+        sixteen.promote(Promote::Rgba).data(),
+        &Samples::Sixteen(vec![1000, 1000, 1000, u16::MAX])
+    );
+}
+
+#[test]
+fn gray_and_color_images_blend_once_promoted() {
+    let dir = env::temp_dir().join(format!("interpol-promote-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let gray = dir.join("gray.png");
+    let rgb = dir.join("rgb.png");
+    image(vec![200], ColorType::Gray).save(&gray).unwrap();
+    image(vec![0, 100, 200], ColorType::Rgb).save(&rgb).unwrap();
+
+    let paths = vec![gray.clone(), rgb.clone()];
+    assert!(Interpolator::from_paths(paths.clone(), 2).is_err());
+
+    let loader = ImageLoader::new().promote(Promote::Rgb);
+    let frames: Vec<_> = Interpolator::from_paths_with_loader(paths, 2, loader)
+        .unwrap()
+        .map(|frame| frame.unwrap().data().clone())
+        .collect();
+    assert_eq!(
+        frames,
+        [
+            Samples::Eight(vec![200, 200, 200]),
+            Samples::Eight(vec![100, 150, 200]),
+            Samples::Eight(vec![0, 100, 200]),
+        ]
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
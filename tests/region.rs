@@ -0,0 +1,54 @@
+use interpol::{ColorType, Image, Interpolator, Region, Samples};
+
+fn gray(value: u8) -> Image {
+    Image::new_from_parts(vec![value; 4 * 3], ColorType::Gray, 4, 3).unwrap()
+}
+
+fn pixels(image: &Image) -> Vec<u8> {
+    match image.data() {
+        Samples::Eight(data) => data.clone(),
+        Samples::Sixteen(_) | Samples::Float(_) => panic!("expected an 8 bit image"),
+    }
+}
+
+#[test]
+fn only_the_region_is_interpolated() {
+    let region: Region = "1,1,2,2".parse().unwrap();
+    let frames: Vec<_> = Interpolator::new(vec![gray(0), gray(200), gray(100)], 2)
+        .unwrap()
+        .region(region)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    #[rustfmt::skip]
+    let expected: [[u8; 12]; 5] = [
+        [0, 0, 0, 0,  0, 0, 0, 0,  0, 0, 0, 0],
+        [0, 0, 0, 0,  0, 100, 100, 0,  0, 100, 100, 0],
+        // the second transition starts on the second image outside the region too
+        [200, 200, 200, 200,  200, 200, 200, 200,  200, 200, 200, 200],
+        [200, 200, 200, 200,  200, 150, 150, 200,  200, 150, 150, 200],
+        // and the last frame keeps the outside of the image it came from
+        [200, 200, 200, 200,  200, 100, 100, 200,  200, 100, 100, 200],
+    ];
+    let frames: Vec<_> = frames.iter().map(pixels).collect();
+    assert_eq!(
+        frames,
+        expected.iter().map(|f| f.to_vec()).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn regions_must_fit_in_the_images() {
+    let interpolator = || Interpolator::new(vec![gray(0), gray(200)], 2).unwrap();
+    assert!(interpolator().region("0,0,4,3".parse().unwrap()).is_ok());
+    assert!(interpolator().region("1,0,4,3".parse().unwrap()).is_err());
+    assert!(interpolator()
+        .region("0,0,1,1".parse().unwrap())
+        .and_then(|i| i.region("0,3,1,1".parse().unwrap()))
+        .is_err());
+
+    for invalid in ["1,2,3", "1,2,3,4,5", "a,0,1,1", "0,0,0,1", "-1,0,1,1"] {
+        assert!(invalid.parse::<Region>().is_err(), "{}", invalid);
+    }
+}
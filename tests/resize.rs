@@ -0,0 +1,71 @@
+use interpol::{ColorType, Filter, Fit, Gravity, Image, Samples};
+
+/// An 8x4 grayscale image with the values 0, 30, 60 ... along each row
+fn gradient() -> Image {
+    let data: Vec<u8> = (0..32).map(|i| (i % 8) * 30).collect();
+    Image::new_from_parts(data, ColorType::Gray, 8, 4).unwrap()
+}
+
+#[test]
+fn box_averages_the_pixels_covered() {
+    let half = gradient().resize_to_width(4, Filter::Box);
+    assert_eq!((half.width(), half.height()), (4, 2));
+    assert_eq!(
+        half.data(),
+        &Samples::Eight(vec![15, 75, 135, 195, 15, 75, 135, 195])
+    );
+
+    // the height is rounded to keep the aspect ratio, 8x4 to 3x1.5
+    let third = gradient().resize_to_width(3, Filter::Box);
+    assert_eq!((third.width(), third.height()), (3, 2));
+    assert_eq!(
+        third.data(),
+        &Samples::Eight(vec![26, 105, 184, 26, 105, 184])
+    );
+}
+
+#[test]
+fn lanczos_keeps_flat_areas_flat() {
+    let flat = Image::new_from_parts(vec![100u16; 64], ColorType::Gray, 8, 8).unwrap();
+    assert_eq!(
+        flat.resize_to_width(3, Filter::Lanczos).data(),
+        &Samples::Sixteen(vec![100; 9])
+    );
+
+    // shrinking a gradient is close to averaging it, and the same size is unchanged
+    let half = gradient().resize_to_width(4, Filter::Lanczos);
+    assert_eq!(
+        half.data(),
+        &Samples::Eight(vec![14, 75, 135, 196, 14, 75, 135, 196])
+    );
+    assert_eq!(gradient().resize_to_width(8, Filter::Lanczos), gradient());
+}
+
+#[test]
+fn portraits_are_fitted_to_landscapes() {
+    // a 2x4 portrait getting brighter towards the bottom
+    let data: Vec<u8> = (0..8).map(|i| (i / 2 + 1) * 10).collect();
+    let portrait = Image::new_from_parts(data, ColorType::Gray, 2, 4).unwrap();
+    let fit = |fit, gravity| {
+        let image = portrait.resize_fit(4, 2, Filter::Box, fit, gravity, [255, 255, 255]);
+        assert_eq!((image.width(), image.height()), (4, 2));
+        image.data().clone()
+    };
+
+    assert_eq!(
+        fit(Fit::Cover, Gravity::Center),
+        Samples::Eight(vec![20, 20, 20, 20, 30, 30, 30, 30])
+    );
+    assert_eq!(
+        fit(Fit::Cover, Gravity::Top),
+        Samples::Eight(vec![10, 10, 10, 10, 10, 10, 10, 10])
+    );
+    assert_eq!(
+        fit(Fit::Contain, Gravity::Left),
+        Samples::Eight(vec![15, 255, 255, 255, 35, 255, 255, 255])
+    );
+    assert_eq!(
+        fit(Fit::Stretch, Gravity::Center),
+        portrait.resize(4, 2, Filter::Box).data().clone()
+    );
+}
@@ -0,0 +1,155 @@
+use std::{env, fs, process::Command};
+
+use interpol::{ColorType, Image, Interpolator};
+
+fn gray(value: u8) -> Image {
+    Image::new_from_parts(vec![value; 4], ColorType::Gray, 2, 2).unwrap()
+}
+
+fn images() -> Vec<Image> {
+    vec![gray(0), gray(100), gray(200)]
+}
+
+/// Each segment as its start and end images and the frames it covers
+fn plan(interpolator: &Interpolator) -> Vec<(usize, usize, std::ops::Range<usize>)> {
+    interpolator
+        .segments()
+        .map(|segment| (segment.start, segment.end, segment.frames()))
+        .collect()
+}
+
+#[test]
+fn segments_cover_the_transitions_in_order() {
+    let interpolator = Interpolator::new(images(), 4)
+        .unwrap()
+        .frames_between(vec![2, 5])
+        .unwrap()
+        .hold(1);
+    assert_eq!(plan(&interpolator), vec![(0, 1, 0..3), (1, 2, 3..9)]);
+    // the frames holding the last image come after the segments
+    assert_eq!(interpolator.total_frames(), 11);
+}
+
+#[test]
+fn ping_pong_segments_return_to_the_first_image() {
+    let interpolator = Interpolator::new(images(), 3).unwrap().ping_pong(true);
+    assert_eq!(
+        plan(&interpolator),
+        vec![(0, 1, 0..3), (1, 2, 3..6), (2, 1, 6..9), (1, 0, 9..12)]
+    );
+    assert_eq!(interpolator.total_frames(), 12);
+}
+
+#[test]
+fn looping_segments_close_the_loop() {
+    let interpolator = Interpolator::new(images(), 3).unwrap().looping(true);
+    assert_eq!(
+        plan(&interpolator),
+        vec![(0, 1, 0..3), (1, 2, 3..6), (2, 0, 6..9)]
+    );
+    assert_eq!(interpolator.total_frames(), 9);
+}
+
+/// Runs `interpol --dry-run` on gray images named `a.png`, `b.png` and `c.png`, the last
+/// twice the size of the others, giving the first line of the plan and its transitions as
+/// the images at either end with the number of frames between them
+fn dry_run(name: &str, args: &[&str]) -> (String, Vec<(String, String, usize)>) {
+    let dir = env::temp_dir().join(format!("interpol-segments-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    for (file, size) in [("a.png", 2), ("b.png", 2), ("c.png", 4)] {
+        Image::new_from_parts(
+            vec![0u8; size * size],
+            ColorType::Gray,
+            size as u32,
+            size as u32,
+        )
+        .unwrap()
+        .save(dir.join(file))
+        .unwrap();
+    }
+    let output = Command::new(env!("CARGO_BIN_EXE_interpol"))
+        .current_dir(&dir)
+        .args(args)
+        .arg("--dry-run")
+        .output()
+        .unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let transitions = stdout
+        .lines()
+        .filter(|line| line.contains(" -> "))
+        .map(|line| {
+            // e.g. `  frames 0..2: "a.png" -> "b.png", 2 frames`
+            let names: Vec<_> = line.split('"').collect();
+            let frames = names[4]
+                .trim_start_matches(", ")
+                .trim_end_matches(" frames");
+            (
+                names[1].to_owned(),
+                names[3].to_owned(),
+                frames.parse().unwrap(),
+            )
+        })
+        .collect();
+    (stdout.lines().next().unwrap().to_owned(), transitions)
+}
+
+fn transition(start: &str, end: &str, frames: usize) -> (String, String, usize) {
+    (start.to_owned(), end.to_owned(), frames)
+}
+
+#[test]
+fn reversing_a_loop_keeps_the_closing_transition_last() {
+    let args = ["a.png", "b.png", "c.png", "--resize-to", "first", "--loop"];
+    let forward = [
+        transition("a.png", "b.png", 2),
+        transition("b.png", "c.png", 3),
+        transition("c.png", "a.png", 10),
+    ];
+    let reversed = [
+        transition("c.png", "b.png", 3),
+        transition("b.png", "a.png", 2),
+        transition("a.png", "c.png", 10),
+    ];
+
+    let counts = [&args[..], &["--frames-between", "2,3,10"]].concat();
+    assert_eq!(dry_run("loop", &counts).1, forward);
+    let (size, transitions) = dry_run("loop-reverse", &[&counts[..], &["--reverse"]].concat());
+    assert_eq!(transitions, reversed);
+    // the images are still resized to the first one given rather than the first played
+    assert!(size.contains(" 2x2 "), "{}", size);
+
+    // the same goes for a loop closed at an extra time
+    let times = [&args[..], &["--timeline", "0,2,5,15", "--fps", "1"]].concat();
+    assert_eq!(dry_run("timeline", &times).1, forward);
+    let transitions = dry_run("timeline-reverse", &[&times[..], &["--reverse"]].concat()).1;
+    assert_eq!(transitions, reversed);
+}
+
+#[test]
+fn reversing_ping_pong_plays_the_counts_back_to_front() {
+    let args = [
+        "a.png",
+        "b.png",
+        "c.png",
+        "--resize-to",
+        "first",
+        "--ping-pong",
+    ];
+    let args = [&args[..], &["--frames-between", "2,3", "--reverse"]].concat();
+    assert_eq!(
+        dry_run("ping-pong-reverse", &args).1,
+        [
+            transition("c.png", "b.png", 3),
+            transition("b.png", "a.png", 2),
+            transition("a.png", "b.png", 2),
+            transition("b.png", "c.png", 3),
+        ]
+    );
+}
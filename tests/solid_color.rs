@@ -0,0 +1,49 @@
+use std::{env, fs};
+
+use interpol::{ColorType, Image, Interpolator, Samples, SolidColor};
+
+#[test]
+fn solid_colors_parse() {
+    let orange: SolidColor = "#ff8000".parse().unwrap();
+    assert_eq!(orange.rgb, [255, 128, 0]);
+    assert_eq!(orange.size, None);
+
+    let blue: SolidColor = "color:0, 0,255x4x3".parse().unwrap();
+    assert_eq!(blue.rgb, [0, 0, 255]);
+    assert_eq!(blue.size, Some((4, 3)));
+
+    for invalid in [
+        "#ff80",
+        "#gg8000",
+        "color:0,0",
+        "color:0,0,256",
+        "#000000x4",
+    ] {
+        assert!(invalid.parse::<SolidColor>().is_err(), "{}", invalid);
+    }
+}
+
+#[test]
+fn solid_colors_take_the_format_of_the_nearest_image() {
+    let dir = env::temp_dir().join(format!("interpol-solid-color-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("gray.png");
+    Image::new_from_parts(vec![0u16; 3 * 2], ColorType::Gray, 3, 2)
+        .unwrap()
+        .save(&path)
+        .unwrap();
+
+    let paths = vec![path.to_str().unwrap(), "#ffffff"];
+    let last = Interpolator::from_paths(paths, 2)
+        .unwrap()
+        .last()
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        (last.width(), last.height(), last.color_type()),
+        (3, 2, ColorType::Gray)
+    );
+    assert_eq!(last.data(), &Samples::Sixteen(vec![u16::MAX; 3 * 2]));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
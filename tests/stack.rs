@@ -0,0 +1,56 @@
+use interpol::{ColorType, Image, Interpolator};
+
+fn gray(value: u8) -> Image {
+    Image::new_from_parts(vec![value; 4], ColorType::Gray, 2, 2).unwrap()
+}
+
+fn stack(values: &[u8]) -> Interpolator {
+    Interpolator::new(values.iter().map(|&v| gray(v)).collect(), 4).unwrap()
+}
+
+#[test]
+fn frames_blend_across_both_stacks() {
+    let frames: Vec<_> = stack(&[0, 100])
+        .stack(stack(&[200, 40]), 0.25)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    // a quarter of the way from each stack's frame to the other's,
+    // e.g. 0.75 * 25 + 0.25 * 160 = 58.75 for the second frame
+    let expected = [50, 59, 68, 76, 85];
+    assert_eq!(
+        frames,
+        expected.iter().map(|&v| gray(v)).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn the_weight_picks_out_either_stack() {
+    let first: Vec<_> = stack(&[0, 100]).collect::<Result<_, _>>().unwrap();
+    let blended: Vec<_> = stack(&[0, 100])
+        .stack(stack(&[200, 40]), 0.0)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(blended, first);
+
+    let second: Vec<_> = stack(&[200, 40]).collect::<Result<_, _>>().unwrap();
+    let blended: Vec<_> = stack(&[0, 100])
+        .stack(stack(&[200, 40]), 1.0)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(blended, second);
+}
+
+#[test]
+fn stacks_must_match_image_for_image() {
+    let error = stack(&[0, 100])
+        .stack(stack(&[0, 50, 100]), 0.5)
+        .unwrap_err()
+        .to_string();
+    assert_eq!(
+        error,
+        "The second stack has 3 images, but there are 2 to match them with."
+    );
+}
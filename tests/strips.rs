@@ -0,0 +1,38 @@
+use std::{env, fs};
+
+use interpol::{ColorSpace, ColorType, Image, Interpolator};
+
+#[test]
+fn strips_match_whole_frames() {
+    let dir = env::temp_dir().join(format!("interpol-strips-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    // 11 rows split into strips of 4 leaves a final strip of 3
+    let (width, height) = (7, 11);
+    let paths: Vec<_> = (0..2u16)
+        .map(|i| {
+            let data: Vec<u16> = (0..width * height * 4)
+                .map(|c| (c as u16).wrapping_mul(7919).wrapping_add(i * 30000))
+                .collect();
+            let path = dir.join(format!("keyframe_{}.png", i));
+            Image::new_from_parts(data, ColorType::Rgba, width, height)
+                .unwrap()
+                .save(&path)
+                .unwrap();
+            path
+        })
+        .collect();
+
+    let interpolator = Interpolator::from_paths(paths, 3)
+        .unwrap()
+        .color_space(ColorSpace::Hsv);
+    for n in 0..interpolator.total_frames() {
+        let path = dir.join(format!("frame_{}.png", n));
+        interpolator.save_frame_in_strips(n, &path, 4).unwrap();
+
+        let expected = interpolator.frame_at(n).unwrap().unwrap();
+        assert_eq!(Image::new_from_path(&path).unwrap(), expected);
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,46 @@
+use interpol::{ColorType, Image, Interpolator, Samples, Transition};
+
+/// The middle frame of a transition between two rows of gray pixels
+fn midpoint(transition: Transition, feather: u32, start: Vec<u8>, end: Vec<u8>) -> Samples {
+    let width = start.len() as u32;
+    let image = |data| Image::new_from_parts(data, ColorType::Gray, width, 1).unwrap();
+    Interpolator::new(vec![image(start), image(end)], 2)
+        .unwrap()
+        .transition(transition)
+        .feather(feather)
+        .nth(1)
+        .unwrap()
+        .unwrap()
+        .data()
+        .clone()
+}
+
+#[test]
+fn wipes_copy_the_end_image_behind_the_edge() {
+    assert_eq!(
+        midpoint(Transition::WipeRight, 0, vec![0; 4], vec![200; 4]),
+        Samples::Eight(vec![200, 200, 0, 0])
+    );
+    assert_eq!(
+        midpoint(Transition::WipeLeft, 0, vec![0; 4], vec![200; 4]),
+        Samples::Eight(vec![0, 0, 200, 200])
+    );
+    // the feathered edge fades across the pixels either side of it
+    assert_eq!(
+        midpoint(Transition::WipeRight, 4, vec![0; 6], vec![200; 6]),
+        Samples::Eight(vec![200, 175, 125, 75, 25, 0])
+    );
+}
+
+#[test]
+fn slides_push_the_start_image_out() {
+    assert_eq!(
+        midpoint(Transition::Slide, 0, vec![1, 2, 3, 4], vec![5, 6, 7, 8]),
+        Samples::Eight(vec![3, 4, 5, 6])
+    );
+    // sliding an image on to itself still moves it
+    assert_eq!(
+        midpoint(Transition::Slide, 0, vec![1, 2, 3, 4], vec![1, 2, 3, 4]),
+        Samples::Eight(vec![3, 4, 1, 2])
+    );
+}
@@ -0,0 +1,29 @@
+#![cfg(feature = "webp")]
+
+use std::{env, fs};
+
+use interpol::{save_webp, ColorType, Image};
+
+#[test]
+fn webp_animations_keep_every_frame_and_their_alpha() {
+    let path = env::temp_dir().join(format!("interpol-{}-animation.webp", std::process::id()));
+    // gray with alpha frames, each with its own shade and transparency
+    let frames: Vec<_> = [(0u8, 64u8), (100, 128), (200, 255)]
+        .iter()
+        .map(|&(luma, alpha)| {
+            Image::new_from_parts([luma, alpha].repeat(4), ColorType::GrayAlpha, 2, 2).unwrap()
+        })
+        .collect();
+    save_webp(&frames, &path, 40, None).unwrap();
+
+    let data = fs::read(&path).unwrap();
+    let decoded = webp::AnimDecoder::new(&data).decode().unwrap();
+    assert_eq!(decoded.len(), 3);
+    for (frame, &(luma, alpha)) in decoded.into_iter().zip(&[(0, 64), (100, 128), (200, 255)]) {
+        assert_eq!((frame.width(), frame.height()), (2, 2));
+        // the frames are lossless, with the gray spread over RGB
+        assert_eq!(frame.get_image(), &[luma, luma, luma, alpha].repeat(4)[..]);
+    }
+
+    fs::remove_file(&path).unwrap();
+}